@@ -1,11 +1,56 @@
-use std::time::Duration;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
 use bumpalo::Bump;
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use glua::{lexer::Token, Parser};
 use logos::Logos;
+use pretty_bytes::converter::convert;
 
-static CODE: &'static str = include_str!("../test.lua");
+static CODE: &str = include_str!("../test.lua");
+
+/// The system allocator, keeping track of the most memory allocated at once
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The most memory allocated at once by `f`
+fn peak_memory(f: impl FnOnce()) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+
+    PEAK.store(before, Ordering::Relaxed);
+
+    f();
+
+    PEAK.load(Ordering::Relaxed) - before
+}
 
 fn lexer(c: &mut Criterion) {
     c.benchmark_group("lexer")
@@ -53,10 +98,115 @@ fn parser(c: &mut Criterion) {
                 let tokens = Parser::lex(CODE, &bump).unwrap();
                 let mut parser = Parser::new_in(&tokens, &bump);
 
+                let _ = black_box(parser.parse_chunk());
+            })
+        })
+        .bench_function("parse_stream", |b| {
+            b.iter(|| {
+                let bump = Bump::new();
+
+                let mut parser = Parser::stream_in(CODE, &bump);
+
                 let _ = black_box(parser.parse_chunk());
             })
         });
 }
 
-criterion_group!(benches, lexer, parser);
+/// Large generated files, where lexing up front holds every token in memory at once: one made of
+/// many statements, and one that's a single table
+fn generated(c: &mut Criterion) {
+    let statements = (0..100_000)
+        .map(|i| {
+            format!(
+                "items[{}] = {{ name = \"item{}\", value = {} }}\n",
+                i,
+                i,
+                i * 2
+            )
+        })
+        .collect::<String>();
+
+    let table = (0..100_000)
+        .map(|i| {
+            format!(
+                "    [{}] = {{ name = \"item{}\", value = {} }},\n",
+                i,
+                i,
+                i * 2
+            )
+        })
+        .collect::<String>();
+
+    let table = format!("return {{\n{}}}\n", table);
+
+    for (name, code) in [("statements", &statements), ("table", &table)] {
+        report_memory(name, code);
+
+        c.benchmark_group(format!("generated_{}", name))
+            .sample_size(50)
+            .throughput(Throughput::Bytes(code.len() as u64))
+            .bench_function("parse_full", |b| {
+                b.iter(|| {
+                    let bump = Bump::new();
+
+                    let tokens = Parser::lex(code, &bump).unwrap();
+                    let mut parser = Parser::new_in(&tokens, &bump);
+
+                    let _ = black_box(parser.parse_chunk());
+                })
+            })
+            .bench_function("parse_stream", |b| {
+                b.iter(|| {
+                    let bump = Bump::new();
+
+                    let mut parser = Parser::stream_in(code, &bump);
+
+                    let _ = black_box(parser.parse_chunk());
+                })
+            });
+    }
+}
+
+/// Print the most tokens held and memory allocated at once while parsing `code`, both with and
+/// without streaming
+fn report_memory(name: &str, code: &str) {
+    let mut tokens = 0;
+
+    let full = peak_memory(|| {
+        let bump = Bump::new();
+
+        let lexed = Parser::lex(code, &bump).unwrap();
+        let mut parser = Parser::new_in(&lexed, &bump);
+
+        let _ = black_box(parser.parse_chunk());
+
+        tokens = parser.peak_buffered_tokens();
+    });
+
+    println!(
+        "generated_{}/parse_full: {} tokens held, {} allocated",
+        name,
+        tokens,
+        convert(full as f64)
+    );
+
+    let stream = peak_memory(|| {
+        let bump = Bump::new();
+
+        let mut parser = Parser::stream_in(code, &bump);
+
+        let _ = black_box(parser.parse_chunk());
+
+        tokens = parser.peak_buffered_tokens();
+    });
+
+    println!(
+        "generated_{}/parse_stream: {} tokens held, {} allocated",
+        name,
+        tokens,
+        convert(stream as f64)
+    );
+}
+
+criterion_group!(benches, lexer, parser, generated);
 criterion_main!(benches);
@@ -0,0 +1,165 @@
+use logos::Span;
+
+use crate::{
+    analysis::{
+        realm::Realms,
+        scope::{Resolution, ScopeTree},
+    },
+    api::Realm,
+    ast::{
+        exps::FunctionCall,
+        node::Node,
+        stats::FunctionDef,
+        visitors::listener::{traverse, Ancestors, Listener},
+        Block, Exp, Name,
+    },
+};
+
+/// A file's uses of hooks: the handlers it adds and removes, the hooks it runs and the gamemode
+/// methods it defines, in source order
+#[derive(Clone, Debug, Default)]
+pub struct Hooks<'a> {
+    pub uses: Vec<HookUse<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HookUse<'a> {
+    pub kind: HookKind,
+    /// The hook's name, spanning its string literal or the method name of a gamemode method
+    pub name: Name<'a>,
+    pub realm: Option<Realm>,
+    /// The identifier passed to `hook.Add` or `hook.Remove`, if it's a string literal
+    pub identifier: Option<Name<'a>>,
+    /// The function handling the hook, for `hook.Add` and gamemode methods
+    pub handler: Option<Span>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HookKind {
+    /// `hook.Add`
+    Add,
+    /// `hook.Remove`
+    Remove,
+    /// `hook.Run` or `hook.Call`, which can run hooks the game doesn't
+    Run,
+    /// A method defined on `GM` or `GAMEMODE`, e.g. `function GM:PlayerSpawn(ply) end`
+    Gamemode,
+}
+
+impl<'a> Hooks<'a> {
+    /// Find the uses of hooks in `chunk`, whose locals are resolved by `tree` and realms inferred
+    /// by `realms`.
+    ///
+    /// Only hooks named by string literals are found.
+    pub fn collect(chunk: Block<'a>, tree: &ScopeTree<'a>, realms: &Realms) -> Self {
+        let mut collector = Collector {
+            tree,
+            realms,
+            hooks: Hooks::default(),
+        };
+
+        traverse(&mut collector, chunk);
+
+        collector.hooks
+    }
+}
+
+struct Collector<'a, 't> {
+    tree: &'t ScopeTree<'a>,
+    realms: &'t Realms,
+    hooks: Hooks<'a>,
+}
+
+impl<'a> Collector<'a, '_> {
+    fn is_global(&self, name: &Name) -> bool {
+        self.tree
+            .reference_at(name.span().start)
+            .is_some_and(|reference| reference.resolution == Resolution::Global)
+    }
+}
+
+impl<'a> Listener<'a> for Collector<'a, '_> {
+    fn enter_function_call(
+        &mut self,
+        call: &Node<'a, &'a FunctionCall<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        let Exp::Member(member) = **call.lhs else {
+            return;
+        };
+
+        let Exp::Ref(library) = **member.lhs else {
+            return;
+        };
+
+        if library != "hook" || !self.is_global(&Node::morph(&member.lhs, library)) {
+            return;
+        }
+
+        let kind = match *member.name {
+            "Add" => HookKind::Add,
+            "Remove" => HookKind::Remove,
+            "Run" | "Call" => HookKind::Run,
+            _ => return,
+        };
+
+        let Some(name) = call.args.first().and_then(string) else {
+            return;
+        };
+
+        let identifier = match kind {
+            HookKind::Add | HookKind::Remove => call.args.get(1).and_then(string),
+            _ => None,
+        };
+
+        let handler = match kind {
+            HookKind::Add => call.args.get(2).map(|handler| handler.span()),
+            _ => None,
+        };
+
+        self.hooks.uses.push(HookUse {
+            kind,
+            name,
+            realm: self.realms.realm_at(name.span().start),
+            identifier,
+            handler,
+        });
+    }
+
+    fn enter_function_def_stat(
+        &mut self,
+        v: &Node<'a, &'a FunctionDef<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        if v.local {
+            return;
+        }
+
+        let name = &v.name;
+
+        // `function GM:Name()` or `function GM.Name(self)`
+        let method = match (name.fields, name.method) {
+            ([], Some(method)) => method,
+            ([method], None) => *method,
+            _ => return,
+        };
+
+        if matches!(*name.base, "GM" | "GAMEMODE") && self.is_global(&name.base) {
+            self.hooks.uses.push(HookUse {
+                kind: HookKind::Gamemode,
+                name: method,
+                realm: self.realms.realm_at(method.span().start),
+                identifier: None,
+                handler: Some(v.body.span()),
+            });
+        }
+    }
+}
+
+/// A string literal, as a name spanning the literal
+fn string<'a>(exp: &Node<'a, &'a Exp<'a>>) -> Option<Name<'a>> {
+    match **exp {
+        Exp::String(bytes) => Some(Node::morph(exp, std::str::from_utf8(bytes).ok()?)),
+        _ => None,
+    }
+}
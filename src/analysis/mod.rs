@@ -0,0 +1,6 @@
+//! Semantic passes over a parsed chunk, which linting and editor features are built on.
+
+pub mod hooks;
+pub mod net;
+pub mod realm;
+pub mod scope;
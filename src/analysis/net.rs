@@ -0,0 +1,340 @@
+use logos::Span;
+
+use crate::{
+    analysis::{
+        realm::Realms,
+        scope::{Resolution, ScopeTree},
+    },
+    api::{Database, Realm},
+    ast::{
+        exps::{Function, FunctionCall, MethodCall},
+        node::Node,
+        visitors::listener::{traverse, Ancestor, Ancestors, Listener},
+        Block, Exp, Name, Stat,
+    },
+    lint::stdlib,
+};
+
+/// The functions that send the message begun by `net.Start`
+const SEND_FUNCTIONS: &[&str] = &[
+    "Broadcast",
+    "Send",
+    "SendOmit",
+    "SendPAS",
+    "SendPVS",
+    "SendToServer",
+];
+
+/// A file's uses of the `net` library: the messages it sends and receives, and the message names
+/// it registers
+#[derive(Clone, Debug, Default)]
+pub struct NetUsage<'a> {
+    pub sends: Vec<NetMessage<'a>>,
+    pub receives: Vec<NetMessage<'a>>,
+    /// Names passed to `util.AddNetworkString`
+    pub registrations: Vec<Name<'a>>,
+}
+
+/// A message begun by `net.Start` or handled by `net.Receive`, along with the values written to
+/// or read from it
+#[derive(Clone, Debug)]
+pub struct NetMessage<'a> {
+    /// The message's name, spanning its string literal
+    pub name: Name<'a>,
+    pub realm: Option<Realm>,
+    /// Values in the order they're written or read
+    pub values: Vec<NetValue<'a>>,
+    /// Whether `values` is known to be every value in order, i.e. none are written or read
+    /// conditionally, in a loop or by calling a function that might do so itself
+    pub exact: bool,
+    /// How many of the first `values` are certain, which is all of them if `exact`
+    pub certain: usize,
+}
+
+/// A call to a `net.Write*` or `net.Read*` function
+#[derive(Clone, Debug)]
+pub struct NetValue<'a> {
+    /// The function's name, e.g. `WriteUInt`
+    pub function: Name<'a>,
+    /// The bit count passed to `WriteInt`, `WriteUInt`, `ReadInt` or `ReadUInt`, if it's a literal
+    pub bits: Option<u32>,
+}
+
+impl<'a> NetUsage<'a> {
+    /// Find the uses of `net` in `chunk`, whose locals are resolved by `tree` and realms inferred
+    /// by `realms`.
+    ///
+    /// Only messages named by string literals are found, and only the reads in a callback passed
+    /// directly to `net.Receive` are known.
+    pub fn collect(chunk: Block<'a>, tree: &ScopeTree<'a>, realms: &Realms) -> Self {
+        let mut collector = Collector {
+            tree,
+            realms,
+            usage: NetUsage::default(),
+            open: Vec::new(),
+            callbacks: Vec::new(),
+        };
+
+        traverse(&mut collector, chunk);
+
+        collector.usage
+    }
+}
+
+impl<'a> NetValue<'a> {
+    /// The type of value, e.g. `UInt` for `WriteUInt`. `Bit` and `Bool` are both a single bit,
+    /// so both are `Bool`.
+    pub fn kind(&self) -> &'a str {
+        let name: &'a str = *self.function;
+
+        match name.trim_start_matches("Write").trim_start_matches("Read") {
+            "Bit" => "Bool",
+            kind => kind,
+        }
+    }
+}
+
+/// The innermost function enclosing the current node, `None` for the chunk
+type FunctionKey = Option<Span>;
+
+struct Collector<'a, 't> {
+    tree: &'t ScopeTree<'a>,
+    realms: &'t Realms,
+    usage: NetUsage<'a>,
+    /// Messages begun but not yet sent by each function, with the conditional statements
+    /// enclosing the `net.Start`
+    open: Vec<(FunctionKey, usize, Vec<Span>)>,
+    /// The callbacks passed to `net.Receive`, with the message they receive
+    callbacks: Vec<(Span, usize)>,
+}
+
+impl<'a> Collector<'a, '_> {
+    /// The library and name of a call like `net.Start`, if the library is a global
+    fn library_call(&self, call: &FunctionCall<'a>) -> Option<(&'a str, Name<'a>)> {
+        let Exp::Member(member) = **call.lhs else {
+            return None;
+        };
+
+        let Exp::Ref(library) = **member.lhs else {
+            return None;
+        };
+
+        match self.resolution(member.lhs.span())? {
+            Resolution::Global => Some((library, member.name)),
+            Resolution::Local(_) => None,
+        }
+    }
+
+    fn resolution(&self, span: Span) -> Option<Resolution> {
+        self.tree
+            .reference_at(span.start)
+            .map(|reference| reference.resolution)
+    }
+
+    fn call(&mut self, call: &FunctionCall<'a>, ancestors: &Ancestors<'a>) {
+        let key = ancestors.function().map(|function| function.span());
+
+        let Some((library, function)) = self.library_call(call) else {
+            if self.might_be_helper(call) {
+                self.helper_called(key);
+            }
+
+            return;
+        };
+
+        // A global table of the addon's own functions, rather than a library
+        if !stdlib::LUA.contains(&library) && Database::bundled().library(library).is_none() {
+            self.helper_called(key);
+
+            return;
+        }
+
+        match (library, *function) {
+            ("util", "AddNetworkString") => {
+                if let Some(name) = call.args.first().and_then(string) {
+                    self.usage.registrations.push(name);
+                }
+            }
+            ("net", "Start") => {
+                let Some(name) = call.args.first().and_then(string) else {
+                    return;
+                };
+
+                self.close(key.clone());
+
+                self.open
+                    .push((key, self.usage.sends.len(), conditionals(ancestors)));
+
+                let message = self.message(name);
+                self.usage.sends.push(message);
+            }
+            ("net", "Receive") => {
+                let Some(name) = call.args.first().and_then(string) else {
+                    return;
+                };
+
+                let mut message = self.message(name);
+
+                match call.args.get(1) {
+                    Some(callback) if matches!(***callback, Exp::Function(_)) => {
+                        self.callbacks
+                            .push((callback.span(), self.usage.receives.len()));
+                    }
+                    // The reads can't be found
+                    _ => message.exact = false,
+                }
+
+                self.usage.receives.push(message);
+            }
+            ("net", name) if SEND_FUNCTIONS.contains(&name) => self.close(key),
+            ("net", name) if name.starts_with("Write") || name.starts_with("Read") => {
+                let bits = match name {
+                    "WriteInt" | "WriteUInt" => call.args.get(1),
+                    "ReadInt" | "ReadUInt" => call.args.first(),
+                    _ => None,
+                };
+
+                let value = NetValue {
+                    function,
+                    bits: bits.and_then(|bits| match ***bits {
+                        Exp::Number(n) if n.fract() == 0.0 && n >= 0.0 => Some(n as u32),
+                        _ => None,
+                    }),
+                };
+
+                let conditionals = conditionals(ancestors);
+
+                let found = match name.starts_with("Write") {
+                    true => self.open.iter().rev().find(|(k, ..)| *k == key).map(
+                        |(_, index, guards)| {
+                            // Conditional on something the `net.Start` isn't
+                            let conditional = conditionals.iter().any(|c| !guards.contains(c));
+
+                            (*index, conditional)
+                        },
+                    ),
+                    false => self
+                        .callbacks
+                        .iter()
+                        .find(|(callback, _)| Some(callback) == key.as_ref())
+                        .map(|&(_, index)| (index, !conditionals.is_empty())),
+                };
+
+                let Some((index, conditional)) = found else {
+                    return;
+                };
+
+                let message = match name.starts_with("Write") {
+                    true => &mut self.usage.sends[index],
+                    false => &mut self.usage.receives[index],
+                };
+
+                message.exact &= !conditional;
+                message.values.push(value);
+
+                if message.exact {
+                    message.certain += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether a call that isn't to a library might be to a helper that writes or reads values
+    /// itself, which is any call but one to a global function of Lua or the GMod API
+    fn might_be_helper(&self, call: &FunctionCall<'a>) -> bool {
+        let Exp::Ref(name) = **call.lhs else {
+            return true;
+        };
+
+        let known = stdlib::LUA.contains(&name) || Database::bundled().function(name).is_some();
+
+        !(known && self.resolution(call.lhs.span()) == Some(Resolution::Global))
+    }
+
+    fn message(&self, name: Name<'a>) -> NetMessage<'a> {
+        NetMessage {
+            name,
+            realm: self.realms.realm_at(name.span().start),
+            values: Vec::new(),
+            exact: true,
+            certain: 0,
+        }
+    }
+
+    /// Stop adding writes to the message begun in `key`
+    fn close(&mut self, key: FunctionKey) {
+        self.open.retain(|(k, ..)| *k != key);
+    }
+
+    /// A function that might write or read values of its own has been called in `key`, so any
+    /// more values in the messages open there aren't known
+    fn helper_called(&mut self, key: FunctionKey) {
+        for (_, index, _) in self.open.iter().filter(|(k, ..)| *k == key) {
+            self.usage.sends[*index].exact = false;
+        }
+
+        for (_, index) in self
+            .callbacks
+            .iter()
+            .filter(|(callback, _)| Some(callback) == key.as_ref())
+        {
+            self.usage.receives[*index].exact = false;
+        }
+    }
+}
+
+impl<'a> Listener<'a> for Collector<'a, '_> {
+    fn enter_function_call(
+        &mut self,
+        v: &Node<'a, &'a FunctionCall<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+        self.call(v, ancestors);
+    }
+
+    fn enter_method_call(&mut self, v: &Node<'a, &'a MethodCall<'a>>, ancestors: &Ancestors<'a>) {
+        let database = Database::bundled();
+
+        // Methods of the GMod API can't be helpers, e.g. `ent:Health()`, but those of an object
+        // whose type isn't known might be, e.g. `ent:WriteState()`
+        if !database
+            .classes
+            .iter()
+            .any(|class| class.function(*v.name).is_some())
+        {
+            self.helper_called(ancestors.function().map(|function| function.span()));
+        }
+    }
+
+    fn leave_function(&mut self, v: &Node<'a, &'a Function<'a>>, _ancestors: &Ancestors<'a>) {
+        self.close(Some(v.span()));
+    }
+}
+
+/// A string literal, as a name spanning the literal
+fn string<'a>(exp: &Node<'a, &'a Exp<'a>>) -> Option<Name<'a>> {
+    match **exp {
+        Exp::String(bytes) => Some(Node::morph(exp, std::str::from_utf8(bytes).ok()?)),
+        _ => None,
+    }
+}
+
+/// The `if`s and loops enclosing the current node within its function
+fn conditionals(ancestors: &Ancestors) -> Vec<Span> {
+    ancestors
+        .iter()
+        .take_while(|ancestor| !matches!(ancestor, Ancestor::Function(_)))
+        .filter_map(|ancestor| match ancestor {
+            Ancestor::Stat(stat) => match **stat {
+                Stat::For(_)
+                | Stat::ForIn(_)
+                | Stat::IfElse(_)
+                | Stat::RepeatUntil(_)
+                | Stat::While(_) => Some(stat.span()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
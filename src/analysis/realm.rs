@@ -0,0 +1,189 @@
+use logos::Span;
+
+use crate::{
+    api::Realm,
+    ast::{
+        exps::{binary::BinOp, unary::UnOp},
+        visitors::listener::{traverse, Ancestors, Listener},
+        Block, Exp, Stat,
+    },
+};
+
+/// The realm a file runs in according to Garry's Mod's conventions, if it follows any, e.g.
+/// `lua/autorun/client/hud.lua`, `sv_data.lua` or `entities/crate/init.lua`.
+///
+/// Both `/` and `\` are treated as separators, so a `file://` URI works as well as a path.
+pub fn file_realm(path: &str) -> Option<Realm> {
+    let path = path.replace('\\', "/").to_lowercase();
+
+    let segments = path.split('/').collect::<Vec<_>>();
+
+    let (&name, dirs) = segments.split_last()?;
+
+    if name.starts_with("cl_") {
+        return Some(Realm::Client);
+    } else if name.starts_with("sv_") {
+        return Some(Realm::Server);
+    } else if name.starts_with("sh_") || name == "shared.lua" {
+        return Some(Realm::Shared);
+    }
+
+    // Directories only mean something within the innermost `lua` or `gamemodes` directory, so an
+    // addon kept in a folder named e.g. `effects` isn't taken to be client-side
+    let root = dirs
+        .iter()
+        .rposition(|&dir| dir == "lua" || dir == "gamemodes")?;
+
+    match (dirs[root], &dirs[root + 1..]) {
+        ("lua", dirs) => lua_dir_realm(dirs, name),
+        // `gamemodes/<name>/gamemode` and `gamemodes/<name>/entities`, laid out like `lua`
+        (_, [_, "gamemode", ..]) if name == "init.lua" => Some(Realm::Server),
+        (_, [_, "entities", dirs @ ..]) => lua_dir_realm(dirs, name),
+        _ => None,
+    }
+}
+
+/// The realm of the file `name` in `dirs` within a `lua` directory
+fn lua_dir_realm(dirs: &[&str], name: &str) -> Option<Realm> {
+    match dirs {
+        ["autorun", "client", ..] => Some(Realm::Client),
+        ["autorun", "server", ..] => Some(Realm::Server),
+        ["autorun", ..] => Some(Realm::Shared),
+        ["entities" | "weapons", ..] if name == "init.lua" => Some(Realm::Server),
+        ["effects" | "matproxy" | "postprocess" | "skins" | "vgui", ..] => Some(Realm::Client),
+        _ => None,
+    }
+}
+
+/// The parts of a chunk known to only run in one realm, either because of the file they're in or
+/// because they're guarded by a check of `CLIENT` or `SERVER`.
+#[derive(Clone, Debug)]
+pub struct Realms {
+    file: Option<Realm>,
+    /// Regions in the order they were entered, so the innermost region containing an offset is
+    /// the last one
+    regions: Vec<Region>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub span: Span,
+    pub realm: Realm,
+    /// The condition that guards the region, e.g. the `SERVER` in `if SERVER then`
+    pub guard: Span,
+}
+
+impl Realms {
+    /// Find the guarded regions of `chunk`, which runs in `file` if known.
+    ///
+    /// A region is the body of an `if` or `else` whose condition checks `CLIENT` or `SERVER`
+    /// (possibly negated or combined with `and`), or the rest of a block after an
+    /// `if CLIENT then return end`.
+    pub fn infer(chunk: Block, file: Option<Realm>) -> Self {
+        let mut realms = Self {
+            file,
+            regions: Vec::new(),
+        };
+
+        traverse(&mut realms, chunk);
+
+        realms
+    }
+
+    /// The realm of the file
+    pub fn file(&self) -> Option<Realm> {
+        self.file
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// The innermost region containing `offset`
+    pub fn region_at(&self, offset: usize) -> Option<&Region> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.span.start <= offset && offset < region.span.end)
+    }
+
+    /// The realm the code at `offset` runs in, if known
+    pub fn realm_at(&self, offset: usize) -> Option<Realm> {
+        self.region_at(offset)
+            .map(|region| region.realm)
+            .or(self.file)
+    }
+
+    fn push(&mut self, block: Block, realm: Option<Realm>, guard: Span) {
+        let (Some(realm), Some(first), Some(last)) = (realm, block.first(), block.last()) else {
+            return;
+        };
+
+        self.regions.push(Region {
+            span: first.span().start..last.span().end,
+            realm,
+            guard,
+        });
+    }
+}
+
+impl<'a> Listener<'a> for Realms {
+    fn enter_block(&mut self, v: Block<'a>, _ancestors: &Ancestors<'a>) {
+        for (i, stat) in v.iter().enumerate() {
+            let Stat::IfElse(s) = **stat else {
+                continue;
+            };
+
+            let (then, mut otherwise) = guarded(&s.cond);
+
+            self.push(s.body, then, s.cond.span());
+
+            let mut guard = s.cond.span();
+
+            for (cond, block) in s.else_ifs {
+                let (then, next) = guarded(cond);
+
+                match then {
+                    Some(_) => self.push(block, then, cond.span()),
+                    None => self.push(block, otherwise, guard.clone()),
+                }
+
+                if next.is_some() {
+                    otherwise = next;
+                    guard = cond.span();
+                }
+            }
+
+            match s.else_block {
+                Some(else_block) => self.push(else_block, otherwise, guard),
+                // `if CLIENT then return end` guards the rest of the block
+                None if s.else_ifs.is_empty()
+                    && matches!(s.body.last().map(|stat| **stat), Some(Stat::Return(_))) =>
+                {
+                    self.push(&v[i + 1..], otherwise, guard)
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// The realms that the branches of an `if` with the condition `cond` run in: the body, and the
+/// `else` if `cond` is false
+fn guarded(cond: &Exp) -> (Option<Realm>, Option<Realm>) {
+    match cond {
+        Exp::Ref("CLIENT") => (Some(Realm::Client), Some(Realm::Server)),
+        Exp::Ref("SERVER") => (Some(Realm::Server), Some(Realm::Client)),
+        Exp::Paren(e) => guarded(e),
+        Exp::Unary(e) if e.op == UnOp::Not => {
+            let (then, otherwise) = guarded(&e.exp);
+
+            (otherwise, then)
+        }
+        // Both sides must be true for the body, so either side's realm holds
+        Exp::Binary(e) if e.op == BinOp::And => (guarded(&e.lhs).0.or(guarded(&e.rhs).0), None),
+        // Both sides must be false for the `else`
+        Exp::Binary(e) if e.op == BinOp::Or => (None, guarded(&e.lhs).1.or(guarded(&e.rhs).1)),
+        _ => (None, None),
+    }
+}
@@ -0,0 +1,362 @@
+use logos::Span;
+
+use crate::ast::{
+    node::Node,
+    stats::{FunctionDef, RepeatUntil, VarDef},
+    visitors::listener::{traverse, Ancestor, Ancestors, Listener},
+    Block, Name, Stat,
+};
+
+/// The lexical scopes of a chunk, the locals declared in them and every variable reference,
+/// resolved to the local it refers to or a global
+#[derive(Debug)]
+pub struct ScopeTree<'a> {
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding<'a>>,
+    references: Vec<Reference<'a>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ScopeId(usize);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BindingId(usize);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ReferenceId(usize);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScopeKind {
+    Chunk,
+    Function,
+    /// The body of a `for`, `while` or `repeat` loop
+    Loop,
+    /// The body of a `do` or a branch of an `if`
+    Block,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    /// `None` for the chunk
+    pub parent: Option<ScopeId>,
+    /// Locals declared directly in the scope, in order of declaration
+    pub bindings: Vec<BindingId>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BindingKind {
+    /// `local x`
+    Local,
+    /// `local function f() end`
+    LocalFunction,
+    /// A function parameter
+    Param,
+    /// The implicit `self` parameter of a method, whose name spans the method name
+    SelfParam,
+    /// The variable of a numeric `for`
+    ForVar,
+    /// A variable of a `for in`
+    ForInVar,
+}
+
+/// A declaration of a local
+#[derive(Clone, Debug)]
+pub struct Binding<'a> {
+    pub name: Name<'a>,
+    pub kind: BindingKind,
+    pub scope: ScopeId,
+    /// References resolving to the binding, in source order
+    pub references: Vec<ReferenceId>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    Read,
+    /// Assignment to the variable itself, e.g. `x = 1` or `function x() end`, but not `x.y = 1`
+    Write,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    Local(BindingId),
+    Global,
+}
+
+/// An occurrence of a variable outside of its declaration
+#[derive(Clone, Debug)]
+pub struct Reference<'a> {
+    pub name: Name<'a>,
+    pub access: Access,
+    pub resolution: Resolution,
+    /// The innermost scope the reference is in
+    pub scope: ScopeId,
+}
+
+impl<'a> ScopeTree<'a> {
+    pub fn build(chunk: Block<'a>) -> Self {
+        let mut resolver = Resolver {
+            tree: ScopeTree {
+                scopes: Vec::new(),
+                bindings: Vec::new(),
+                references: Vec::new(),
+            },
+            current: None,
+            visible: Vec::new(),
+            open: Vec::new(),
+        };
+
+        traverse(&mut resolver, chunk);
+
+        resolver.tree
+    }
+
+    /// The chunk's scope, the root of the tree
+    pub fn root(&self) -> ScopeId {
+        ScopeId(0)
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0]
+    }
+
+    pub fn binding(&self, id: BindingId) -> &Binding<'a> {
+        &self.bindings[id.0]
+    }
+
+    pub fn reference(&self, id: ReferenceId) -> &Reference<'a> {
+        &self.references[id.0]
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (BindingId, &Binding<'a>)> {
+        self.bindings
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (BindingId(i), b))
+    }
+
+    /// Every reference in source order
+    pub fn references(&self) -> impl Iterator<Item = &Reference<'a>> {
+        self.references.iter()
+    }
+
+    /// References to globals, in source order
+    pub fn globals(&self) -> impl Iterator<Item = &Reference<'a>> {
+        self.references
+            .iter()
+            .filter(|reference| reference.resolution == Resolution::Global)
+    }
+
+    /// The binding declared at `offset`
+    pub fn binding_at(&self, offset: usize) -> Option<(BindingId, &Binding<'a>)> {
+        self.bindings()
+            .find(|(_, binding)| contains(&binding.name.span(), offset))
+    }
+
+    /// The reference at `offset`
+    pub fn reference_at(&self, offset: usize) -> Option<&Reference<'a>> {
+        self.references
+            .iter()
+            .find(|reference| contains(&reference.name.span(), offset))
+    }
+
+    /// Enclosing scopes of `scope`, starting with itself
+    pub fn ancestors(&self, scope: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+        std::iter::successors(Some(scope), move |&scope| self.scope(scope).parent)
+    }
+
+    /// The innermost function (or the chunk) enclosing `scope`
+    pub fn function_scope(&self, scope: ScopeId) -> ScopeId {
+        self.ancestors(scope)
+            .find(|&scope| {
+                matches!(
+                    self.scope(scope).kind,
+                    ScopeKind::Function | ScopeKind::Chunk
+                )
+            })
+            .unwrap_or(self.root())
+    }
+}
+
+/// Whether `offset` is within `span`, including its end so the cursor after a name counts
+fn contains(span: &Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+struct Resolver<'a> {
+    tree: ScopeTree<'a>,
+    current: Option<ScopeId>,
+    /// Bindings in scope, innermost last
+    visible: Vec<BindingId>,
+    /// The number of bindings visible on entering each open scope, innermost last
+    open: Vec<usize>,
+}
+
+impl<'a> Resolver<'a> {
+    fn open(&mut self, kind: ScopeKind) {
+        let parent = self.current;
+
+        self.current = Some(ScopeId(self.tree.scopes.len()));
+        self.tree.scopes.push(Scope {
+            kind,
+            parent,
+            bindings: Vec::new(),
+        });
+
+        self.open.push(self.visible.len());
+    }
+
+    fn close(&mut self) {
+        let visible = self.open.pop().unwrap();
+
+        self.visible.truncate(visible);
+        self.current = self.tree.scopes[self.scope().0].parent;
+    }
+
+    fn scope(&self) -> ScopeId {
+        self.current.unwrap()
+    }
+
+    fn declare(&mut self, name: Name<'a>, kind: BindingKind) {
+        let id = BindingId(self.tree.bindings.len());
+        let scope = self.scope();
+
+        self.tree.bindings.push(Binding {
+            name,
+            kind,
+            scope,
+            references: Vec::new(),
+        });
+
+        self.tree.scopes[scope.0].bindings.push(id);
+        self.visible.push(id);
+    }
+
+    fn reference(&mut self, name: Name<'a>, access: Access) {
+        let id = ReferenceId(self.tree.references.len());
+
+        // Later declarations shadow earlier ones
+        let resolution = self
+            .visible
+            .iter()
+            .rev()
+            .find(|binding| *self.tree.bindings[binding.0].name == *name)
+            .map_or(Resolution::Global, |&binding| Resolution::Local(binding));
+
+        if let Resolution::Local(binding) = resolution {
+            self.tree.bindings[binding.0].references.push(id);
+        }
+
+        self.tree.references.push(Reference {
+            name,
+            access,
+            resolution,
+            scope: self.scope(),
+        });
+    }
+}
+
+impl<'a> Listener<'a> for Resolver<'a> {
+    /// Every block is a scope, holding the variables of the loop or function it's the body of
+    fn enter_block(&mut self, _v: Block<'a>, ancestors: &Ancestors<'a>) {
+        let Some(parent) = ancestors.parent() else {
+            return self.open(ScopeKind::Chunk);
+        };
+
+        match parent {
+            Ancestor::Function(function) => {
+                self.open(ScopeKind::Function);
+
+                // Methods have an implicit `self`, defined by the statement holding the function
+                let method = ancestors.iter().nth(1).and_then(|ancestor| match ancestor {
+                    Ancestor::Stat(stat) => match **stat {
+                        Stat::FunctionDef(def) => def.name.method,
+                        _ => None,
+                    },
+                    _ => None,
+                });
+
+                if let Some(method) = method {
+                    self.declare(Node::new(method.span(), "self"), BindingKind::SelfParam);
+                }
+
+                function
+                    .params
+                    .iter()
+                    .for_each(|param| self.declare(*param, BindingKind::Param));
+            }
+            Ancestor::Stat(stat) => match **stat {
+                Stat::For(s) => {
+                    self.open(ScopeKind::Loop);
+                    self.declare(s.init.0, BindingKind::ForVar);
+                }
+                Stat::ForIn(s) => {
+                    self.open(ScopeKind::Loop);
+
+                    s.names
+                        .iter()
+                        .for_each(|name| self.declare(*name, BindingKind::ForInVar));
+                }
+                Stat::RepeatUntil(_) | Stat::While(_) => self.open(ScopeKind::Loop),
+                _ => self.open(ScopeKind::Block),
+            },
+            Ancestor::Exp(_) => self.open(ScopeKind::Block),
+        }
+    }
+
+    fn leave_block(&mut self, _v: Block<'a>, ancestors: &Ancestors<'a>) {
+        // The condition of a `repeat` can see the body's locals, so its scope is closed after it
+        match ancestors.parent() {
+            Some(Ancestor::Stat(stat)) if matches!(**stat, Stat::RepeatUntil(_)) => {}
+            _ => self.close(),
+        }
+    }
+
+    fn leave_repeat_until_stat(
+        &mut self,
+        _v: &Node<'a, &'a RepeatUntil<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        self.close();
+    }
+
+    fn enter_function_def_stat(
+        &mut self,
+        v: &Node<'a, &'a FunctionDef<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        let name = &v.name;
+
+        if v.local {
+            // In scope within its own body, so it can recurse
+            self.declare(name.base, BindingKind::LocalFunction);
+        } else if name.fields.is_empty() && name.method.is_none() {
+            self.reference(name.base, Access::Write);
+        } else {
+            self.reference(name.base, Access::Read);
+        }
+    }
+
+    fn leave_var_def_stat(&mut self, v: &Node<'a, &'a VarDef<'a>>, _ancestors: &Ancestors<'a>) {
+        // The locals aren't in scope until after the statement, so `local x = x` reads the outer
+        // `x`
+        v.names
+            .iter()
+            .for_each(|name| self.declare(*name, BindingKind::Local));
+    }
+
+    fn enter_ref_exp(&mut self, v: &Node<'a, &'a str>, ancestors: &Ancestors<'a>) {
+        // Only assigning to the variable itself writes it, not assigning to a field of it
+        let access = match ancestors.parent() {
+            Some(Ancestor::Stat(stat)) => match **stat {
+                Stat::Assignment(s) if s.vars.iter().any(|var| var.span() == v.span()) => {
+                    Access::Write
+                }
+                _ => Access::Read,
+            },
+            _ => Access::Read,
+        };
+
+        self.reference(*v, access);
+    }
+}
@@ -0,0 +1,414 @@
+//! Definitions of the Garry's Mod API: global functions, libraries, classes, hooks and enums,
+//! along with the realms they're available in.
+//!
+//! The definitions are loaded from JSON so they can be regenerated from a dump of the wiki without
+//! touching any code. A subset covering the most used parts of the API is bundled and available
+//! from [`Database::bundled`]. The format is:
+//!
+//! ```json
+//! {
+//!   "functions": [Function],
+//!   "libraries": [{ "name": "net", "functions": [Function] }],
+//!   "classes": [{ "name": "Player", "parent": "Entity", "functions": [Function] }],
+//!   "hooks": [{ "name": "GM", "functions": [Function] }],
+//!   "enums": [{
+//!     "name": "MOVETYPE",
+//!     "realm": "shared",
+//!     "members": [{ "name": "MOVETYPE_NONE", "value": 0 }]
+//!   }]
+//! }
+//! ```
+//!
+//! Where a `Function` is:
+//!
+//! ```json
+//! {
+//!   "name": "Start",
+//!   "realm": "shared",
+//!   "description": "Begins a new net message",
+//!   "args": [{ "name": "messageName", "type": "string", "default": "nil" }],
+//!   "returns": [{ "type": "boolean" }]
+//! }
+//! ```
+//!
+//! Realms are `client`, `server`, `shared` or `menu`. `description`, `args`, `returns`, `default`
+//! and `parent` are optional.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::OnceLock,
+};
+
+use serde_json::Value;
+
+static BUNDLED: &str = include_str!("gmod.json");
+
+/// Where a part of the API can be used
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Realm {
+    Client,
+    Server,
+    /// Both the client and the server
+    Shared,
+    /// The main menu
+    Menu,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Database {
+    /// Global functions, e.g. `AddCSLuaFile`
+    pub functions: Vec<Function>,
+    pub libraries: Vec<Library>,
+    pub classes: Vec<Class>,
+    /// Hooks grouped by the table they're called on, e.g. `GM` or `ENTITY`
+    pub hooks: Vec<HookTable>,
+    pub enums: Vec<Enum>,
+}
+
+/// A global table of functions, e.g. `net`
+#[derive(Clone, Debug)]
+pub struct Library {
+    pub name: String,
+    pub functions: Vec<Function>,
+}
+
+/// A type of object with methods, e.g. `Entity`
+#[derive(Clone, Debug)]
+pub struct Class {
+    pub name: String,
+    /// The class methods are inherited from, e.g. `Entity` for `Player`
+    pub parent: Option<String>,
+    pub functions: Vec<Function>,
+}
+
+/// Functions called by the game on a table, e.g. `GM:PlayerSpawn` or `ENT:Think`
+#[derive(Clone, Debug)]
+pub struct HookTable {
+    pub name: String,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub realm: Realm,
+    pub description: String,
+    pub args: Vec<Arg>,
+    pub returns: Vec<Return>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Arg {
+    /// `...` for varargs
+    pub name: String,
+    pub ty: String,
+    /// The value used if the argument is omitted, as written on the wiki
+    pub default: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Return {
+    pub ty: String,
+}
+
+/// A group of global constants sharing a prefix, e.g. `MOVETYPE_*`
+#[derive(Clone, Debug)]
+pub struct Enum {
+    pub name: String,
+    pub realm: Realm,
+    pub members: Vec<EnumMember>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumMember {
+    pub name: String,
+    pub value: i64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A value that doesn't match the format, at a path like `libraries[2].functions[0].name`
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+}
+
+impl Realm {
+    /// Whether code running in `self` can use something available in `other`
+    pub fn can_use(self, other: Realm) -> bool {
+        match (self, other) {
+            (_, Realm::Shared) => true,
+            (Realm::Shared, _) => false,
+            _ => self == other,
+        }
+    }
+}
+
+impl Display for Realm {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Client => "client",
+            Self::Server => "server",
+            Self::Shared => "shared",
+            Self::Menu => "menu",
+        })
+    }
+}
+
+impl Database {
+    /// The definitions bundled with the crate, loaded on first use
+    pub fn bundled() -> &'static Database {
+        static DATABASE: OnceLock<Database> = OnceLock::new();
+
+        DATABASE.get_or_init(|| {
+            Database::from_json(BUNDLED).expect("the bundled API definitions are valid")
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(json)?;
+
+        let root = Decoder {
+            value: &value,
+            path: String::new(),
+        };
+
+        Ok(Self {
+            functions: root.field("functions").list(Decoder::function)?,
+            libraries: root.field("libraries").list(|library| {
+                Ok(Library {
+                    name: library.field("name").string()?,
+                    functions: library.field("functions").list(Decoder::function)?,
+                })
+            })?,
+            classes: root.field("classes").list(|class| {
+                Ok(Class {
+                    name: class.field("name").string()?,
+                    parent: class.field("parent").optional(Decoder::string)?,
+                    functions: class.field("functions").list(Decoder::function)?,
+                })
+            })?,
+            hooks: root.field("hooks").list(|table| {
+                Ok(HookTable {
+                    name: table.field("name").string()?,
+                    functions: table.field("functions").list(Decoder::function)?,
+                })
+            })?,
+            enums: root.field("enums").list(|e| {
+                Ok(Enum {
+                    name: e.field("name").string()?,
+                    realm: e.field("realm").realm()?,
+                    members: e.field("members").list(|member| {
+                        Ok(EnumMember {
+                            name: member.field("name").string()?,
+                            value: member.field("value").integer()?,
+                        })
+                    })?,
+                })
+            })?,
+        })
+    }
+
+    /// The global function `name`
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    pub fn library(&self, name: &str) -> Option<&Library> {
+        self.libraries.iter().find(|library| library.name == name)
+    }
+
+    pub fn class(&self, name: &str) -> Option<&Class> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    /// The method `name` of `class` or the classes it inherits from
+    pub fn method(&self, class: &str, name: &str) -> Option<&Function> {
+        let mut class = self.class(class);
+
+        while let Some(c) = class {
+            if let Some(function) = c.function(name) {
+                return Some(function);
+            }
+
+            class = c.parent.as_deref().and_then(|parent| self.class(parent));
+        }
+
+        None
+    }
+
+    /// The hooks called on `table`, e.g. `GM`
+    pub fn hook_table(&self, table: &str) -> Option<&HookTable> {
+        self.hooks.iter().find(|hooks| hooks.name == table)
+    }
+
+    /// The enum with the member `name`, along with the member
+    pub fn enum_member(&self, name: &str) -> Option<(&Enum, &EnumMember)> {
+        self.enums.iter().find_map(|e| {
+            e.members
+                .iter()
+                .find(|member| member.name == name)
+                .map(|member| (e, member))
+        })
+    }
+
+    /// The names of every global defined: functions, libraries and enum members
+    pub fn globals(&self) -> impl Iterator<Item = &str> {
+        let functions = self.functions.iter().map(|function| &function.name);
+        let libraries = self.libraries.iter().map(|library| &library.name);
+        let members = self.enums.iter().flat_map(|e| &e.members);
+
+        functions
+            .chain(libraries)
+            .chain(members.map(|member| &member.name))
+            .map(String::as_str)
+    }
+}
+
+impl Library {
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl Class {
+    /// The method `name` of this class alone, see [`Database::method`] to include parents
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl HookTable {
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl Function {
+    /// The function's signature with the given qualified name, e.g.
+    /// `net.Start(messageName: string, unreliable: boolean = false) -> boolean`
+    pub fn signature(&self, name: &str) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match &arg.default {
+                Some(default) => format!("{}: {} = {}", arg.name, arg.ty, default),
+                None => format!("{}: {}", arg.name, arg.ty),
+            })
+            .collect::<Vec<_>>();
+
+        let mut signature = format!("{}({})", name, args.join(", "));
+
+        if !self.returns.is_empty() {
+            let returns = self.returns.iter().map(|ret| ret.ty.as_str());
+
+            signature.push_str(" -> ");
+            signature.push_str(&returns.collect::<Vec<_>>().join(", "));
+        }
+
+        signature
+    }
+}
+
+/// A JSON value along with its path from the root, for errors
+struct Decoder<'v> {
+    value: &'v Value,
+    path: String,
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl<'v> Decoder<'v> {
+    fn field(&self, name: &str) -> Decoder<'v> {
+        Decoder {
+            value: &self.value[name],
+            path: match self.path.is_empty() {
+                true => name.to_owned(),
+                false => format!("{}.{}", self.path, name),
+            },
+        }
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T> {
+        Err(Error::Invalid {
+            path: self.path.clone(),
+            message: message.to_owned(),
+        })
+    }
+
+    fn string(&self) -> Result<String> {
+        match self.value.as_str() {
+            Some(s) => Ok(s.to_owned()),
+            None => self.error("expected a string"),
+        }
+    }
+
+    fn integer(&self) -> Result<i64> {
+        match self.value.as_i64() {
+            Some(i) => Ok(i),
+            None => self.error("expected an integer"),
+        }
+    }
+
+    fn realm(&self) -> Result<Realm> {
+        match self.value.as_str() {
+            Some("client") => Ok(Realm::Client),
+            Some("server") => Ok(Realm::Server),
+            Some("shared") => Ok(Realm::Shared),
+            Some("menu") => Ok(Realm::Menu),
+            _ => self.error("expected `client`, `server`, `shared` or `menu`"),
+        }
+    }
+
+    /// Decode the value with `f` if it's present
+    fn optional<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<Option<T>> {
+        match self.value {
+            Value::Null => Ok(None),
+            _ => f(self).map(Some),
+        }
+    }
+
+    /// Decode every element with `f`, treating a missing list as empty
+    fn list<T>(&self, mut f: impl FnMut(&Decoder<'v>) -> Result<T>) -> Result<Vec<T>> {
+        let values = match self.value {
+            Value::Null => return Ok(Vec::new()),
+            Value::Array(values) => values,
+            _ => return self.error("expected an array"),
+        };
+
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                f(&Decoder {
+                    value,
+                    path: format!("{}[{}]", self.path, i),
+                })
+            })
+            .collect()
+    }
+
+    fn function(&self) -> Result<Function> {
+        Ok(Function {
+            name: self.field("name").string()?,
+            realm: self.field("realm").realm()?,
+            description: self
+                .field("description")
+                .optional(Decoder::string)?
+                .unwrap_or_default(),
+            args: self.field("args").list(|arg| {
+                Ok(Arg {
+                    name: arg.field("name").string()?,
+                    ty: arg.field("type").string()?,
+                    default: arg.field("default").optional(Decoder::string)?,
+                })
+            })?,
+            returns: self.field("returns").list(|ret| {
+                Ok(Return {
+                    ty: ret.field("type").string()?,
+                })
+            })?,
+        })
+    }
+}
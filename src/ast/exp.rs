@@ -1,5 +1,6 @@
-use crate::ast::exps::{
-    Binary, Function, FunctionCall, Index, Member, MethodCall, TableConstructor, Unary,
+use crate::ast::{
+    exps::{Binary, Function, FunctionCall, Index, Member, MethodCall, TableConstructor, Unary},
+    node::Node,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -13,6 +14,8 @@ pub enum Exp<'a> {
     MethodCall(MethodCall<'a>),
     Nil,
     Number(f64),
+    /// A parenthesised expression, which truncates multiple results to the first, e.g. `(f())`
+    Paren(Node<'a, &'a Exp<'a>>),
     Ref(&'a str),
     String(&'a [u8]),
     Table(TableConstructor<'a>),
@@ -3,13 +3,16 @@ use std::{
     fmt::{Display, Formatter},
 };
 
-use crate::ast::{node::Node, Exp};
+use crate::{
+    ast::{node::Node, Exp},
+    parser::Precedence,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Binary<'a> {
-    pub lhs: Node<&'a Exp<'a>>,
+    pub lhs: Node<'a, &'a Exp<'a>>,
     pub op: BinOp,
-    pub rhs: Node<&'a Exp<'a>>,
+    pub rhs: Node<'a, &'a Exp<'a>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -32,11 +35,31 @@ pub enum BinOp {
 }
 
 impl<'a> Binary<'a> {
-    pub fn new(lhs: Node<&'a Exp>, op: BinOp, rhs: Node<&'a Exp>) -> Self {
+    pub fn new(lhs: Node<'a, &'a Exp>, op: BinOp, rhs: Node<'a, &'a Exp>) -> Self {
         Self { lhs, op, rhs }
     }
 }
 
+impl BinOp {
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            BinOp::Or => Precedence::Or,
+            BinOp::And => Precedence::And,
+            BinOp::Eq | BinOp::Gt | BinOp::GtEq | BinOp::Lt | BinOp::LtEq | BinOp::Ne => {
+                Precedence::Comparative
+            }
+            BinOp::Concat => Precedence::Concat,
+            BinOp::Add | BinOp::Sub => Precedence::Additive,
+            BinOp::Div | BinOp::Mod | BinOp::Mul => Precedence::Multiplicative,
+            BinOp::Exp => Precedence::Exponentiation,
+        }
+    }
+
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, BinOp::Concat | BinOp::Exp)
+    }
+}
+
 impl Display for BinOp {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(
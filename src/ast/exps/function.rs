@@ -1,13 +1,23 @@
-use crate::ast::Block;
+use crate::ast::{node::Node, Block, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Function<'a> {
-    pub params: &'a [&'a str],
+    pub params: &'a [Name<'a>],
+    /// The trailing `...` parameter, if any
+    pub vararg: Option<Node<'a, ()>>,
     pub body: Block<'a>,
 }
 
 impl<'a> Function<'a> {
-    pub fn new(params: &'a [&'a str], body: Block<'a>) -> Self {
-        Self { params, body }
+    pub fn new(params: &'a [Name<'a>], vararg: Option<Node<'a, ()>>, body: Block<'a>) -> Self {
+        Self {
+            params,
+            vararg,
+            body,
+        }
+    }
+
+    pub fn is_vararg(&self) -> bool {
+        self.vararg.is_some()
     }
 }
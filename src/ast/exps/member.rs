@@ -1,13 +1,13 @@
-use crate::ast::{node::Node, Exp};
+use crate::ast::{node::Node, Exp, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Member<'a> {
-    pub lhs: Node<&'a Exp<'a>>,
-    pub name: &'a str,
+    pub lhs: Node<'a, &'a Exp<'a>>,
+    pub name: Name<'a>,
 }
 
 impl<'a> Member<'a> {
-    pub fn new(lhs: Node<&'a Exp>, name: &'a str) -> Self {
+    pub fn new(lhs: Node<'a, &'a Exp>, name: Name<'a>) -> Self {
         Self { lhs, name }
     }
 }
@@ -1,14 +1,14 @@
-use crate::ast::{node::Node, Exp};
+use crate::ast::{node::Node, Exp, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct MethodCall<'a> {
-    pub lhs: Node<&'a Exp<'a>>,
-    pub name: &'a str,
-    pub args: &'a [Node<&'a Exp<'a>>],
+    pub lhs: Node<'a, &'a Exp<'a>>,
+    pub name: Name<'a>,
+    pub args: &'a [Node<'a, &'a Exp<'a>>],
 }
 
 impl<'a> MethodCall<'a> {
-    pub fn new(lhs: Node<&'a Exp>, name: &'a str, args: &'a [Node<&'a Exp>]) -> Self {
+    pub fn new(lhs: Node<'a, &'a Exp>, name: Name<'a>, args: &'a [Node<'a, &'a Exp>]) -> Self {
         Self { lhs, name, args }
     }
 }
@@ -7,8 +7,8 @@ pub struct TableConstructor<'a> {
 
 #[derive(Clone, Copy, Debug)]
 pub struct Field<'a> {
-    pub key: Option<Node<&'a Exp<'a>>>,
-    pub value: Node<&'a Exp<'a>>,
+    pub key: Option<Node<'a, &'a Exp<'a>>>,
+    pub value: Node<'a, &'a Exp<'a>>,
 }
 
 impl<'a> TableConstructor<'a> {
@@ -18,7 +18,7 @@ impl<'a> TableConstructor<'a> {
 }
 
 impl<'a> Field<'a> {
-    pub fn new(key: Option<Node<&'a Exp>>, value: Node<&'a Exp>) -> Self {
+    pub fn new(key: Option<Node<'a, &'a Exp>>, value: Node<'a, &'a Exp>) -> Self {
         Self { key, value }
     }
 }
@@ -8,7 +8,7 @@ use crate::ast::{node::Node, Exp};
 #[derive(Clone, Copy, Debug)]
 pub struct Unary<'a> {
     pub op: UnOp,
-    pub exp: Node<&'a Exp<'a>>,
+    pub exp: Node<'a, &'a Exp<'a>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -19,7 +19,7 @@ pub enum UnOp {
 }
 
 impl<'a> Unary<'a> {
-    pub fn new(op: UnOp, exp: Node<&'a Exp>) -> Self {
+    pub fn new(op: UnOp, exp: Node<'a, &'a Exp>) -> Self {
         Self { op, exp }
     }
 }
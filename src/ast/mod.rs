@@ -8,6 +8,10 @@ pub mod exps;
 pub mod node;
 mod stat;
 pub mod stats;
+pub mod trivia;
 pub mod visitors;
 
-pub type Block<'a> = &'a [Node<&'a Stat<'a>>];
+pub type Block<'a> = &'a [Node<'a, &'a Stat<'a>>];
+
+/// An occurrence of an identifier, spanning just the identifier itself
+pub type Name<'a> = Node<'a, &'a str>;
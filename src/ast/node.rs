@@ -1,31 +1,54 @@
-use std::ops::Deref;
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    ops::Deref,
+};
 
 use logos::Span;
 
 use crate::ast::{
     exps::Member,
+    trivia::{Comment, Trivia},
     visitors::{renderer::Renderer, walk_exp, Visitor},
     Exp,
 };
 
 #[derive(Clone, Copy, Debug)]
-pub struct Node<T> {
-    span: (usize, usize),
+pub struct Node<'a, T> {
+    /// Byte offsets, narrowed so that the trivia pointer doesn't make every node bigger
+    span: (u32, u32),
+    trivia: Option<&'a Trivia<'a>>,
     inner: T,
 }
 
 // impl<T> Copy for Node<T> where T: Copy {}
 
-impl<T> Node<T> {
+impl<'a, T> Node<'a, T> {
     pub fn new(span: Span, inner: T) -> Self {
         Self {
-            span: (span.start, span.end),
+            span: (span.start as u32, span.end as u32),
+            trivia: None,
             inner,
         }
     }
 
     pub fn span(&self) -> Span {
-        self.span.0..self.span.1
+        self.span.0 as usize..self.span.1 as usize
+    }
+
+    /// Comments attached to this node, only present when parsing with comments
+    pub fn trivia(&self) -> Option<&'a Trivia<'a>> {
+        self.trivia
+    }
+
+    pub fn leading_comments(&self) -> &'a [Comment<'a>] {
+        self.trivia.map(|trivia| trivia.leading).unwrap_or_default()
+    }
+
+    pub fn trailing_comments(&self) -> &'a [Comment<'a>] {
+        self.trivia
+            .map(|trivia| trivia.trailing)
+            .unwrap_or_default()
     }
 
     pub fn into_inner(self) -> T {
@@ -33,43 +56,58 @@ impl<T> Node<T> {
     }
 
     #[inline]
-    pub fn map<U>(this: Self, cb: impl FnOnce(T) -> U) -> Node<U> {
+    pub fn with_trivia(mut self, trivia: Option<&'a Trivia<'a>>) -> Self {
+        self.trivia = trivia;
+
+        self
+    }
+
+    #[inline]
+    pub fn map<U>(this: Self, cb: impl FnOnce(T) -> U) -> Node<'a, U> {
         Node {
             span: this.span,
+            trivia: this.trivia,
             inner: cb(this.inner),
         }
     }
 
     #[inline]
-    pub fn morph<U>(this: &Self, inner: U) -> Node<U> {
+    pub fn morph<U>(this: &Self, inner: U) -> Node<'a, U> {
         Node {
             span: this.span,
+            trivia: this.trivia,
             inner,
         }
     }
 }
 
-impl ToString for Node<&Exp<'_>> {
-    fn to_string(&self) -> String {
+impl Display for Node<'_, &Exp<'_>> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let mut renderer = Renderer::default();
 
         walk_exp(&mut renderer, self);
 
-        renderer.into_inner()
+        f.write_str(&renderer.into_inner())
     }
 }
 
-impl ToString for Node<&Member<'_>> {
-    fn to_string(&self) -> String {
+impl Display for Node<'_, &Member<'_>> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let mut renderer = Renderer::default();
 
         renderer.visit_member_exp(self);
 
-        renderer.into_inner()
+        f.write_str(&renderer.into_inner())
+    }
+}
+
+impl Display for Node<'_, &str> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.inner)
     }
 }
 
-impl<T> Deref for Node<T> {
+impl<T> Deref for Node<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -13,6 +13,8 @@ pub enum Stat<'a> {
     /// GMod specific continue statement
     Continue,
     Do(Do<'a>),
+    /// Placeholder for source that couldn't be parsed, only produced when recovering from errors
+    Error,
     For(For<'a>),
     ForIn(ForIn<'a>),
     FunctionCall(FunctionCall<'a>),
@@ -2,12 +2,12 @@ use crate::ast::{node::Node, Exp};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Assignment<'a> {
-    pub vars: &'a [Node<&'a Exp<'a>>],
-    pub exps: &'a [Node<&'a Exp<'a>>],
+    pub vars: &'a [Node<'a, &'a Exp<'a>>],
+    pub exps: &'a [Node<'a, &'a Exp<'a>>],
 }
 
 impl<'a> Assignment<'a> {
-    pub fn new(vars: &'a [Node<&'a Exp>], exps: &'a [Node<&'a Exp>]) -> Self {
+    pub fn new(vars: &'a [Node<'a, &'a Exp>], exps: &'a [Node<'a, &'a Exp>]) -> Self {
         Self { vars, exps }
     }
 }
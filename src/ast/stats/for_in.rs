@@ -1,14 +1,14 @@
-use crate::ast::{node::Node, Block, Exp};
+use crate::ast::{node::Node, Block, Exp, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct ForIn<'a> {
-    pub names: &'a [&'a str],
-    pub exps: &'a [Node<&'a Exp<'a>>],
+    pub names: &'a [Name<'a>],
+    pub exps: &'a [Node<'a, &'a Exp<'a>>],
     pub body: Block<'a>,
 }
 
 impl<'a> ForIn<'a> {
-    pub fn new(names: &'a [&'a str], exps: &'a [Node<&'a Exp>], body: Block<'a>) -> Self {
+    pub fn new(names: &'a [Name<'a>], exps: &'a [Node<'a, &'a Exp>], body: Block<'a>) -> Self {
         Self { names, exps, body }
     }
 }
@@ -1,14 +1,76 @@
-use crate::ast::{exps::Function, node::Node};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use logos::Span;
+
+use crate::ast::{exps::Function, node::Node, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct FunctionDef<'a> {
     pub local: bool,
-    pub name: &'a str,
-    pub body: Node<&'a Function<'a>>,
+    pub name: FunctionName<'a>,
+    pub body: Node<'a, &'a Function<'a>>,
+}
+
+/// The name of a function definition, e.g. `a.b.c:d` in `function a.b.c:d() end`
+#[derive(Clone, Copy, Debug)]
+pub struct FunctionName<'a> {
+    pub base: Name<'a>,
+    /// The fields following the base, always empty for local functions
+    pub fields: &'a [Name<'a>],
+    /// The method name following the `:`, if any
+    pub method: Option<Name<'a>>,
 }
 
 impl<'a> FunctionDef<'a> {
-    pub fn new(local: bool, name: &'a str, body: Node<&'a Function>) -> Self {
+    pub fn new(local: bool, name: FunctionName<'a>, body: Node<'a, &'a Function>) -> Self {
         Self { local, name, body }
     }
 }
+
+impl<'a> FunctionName<'a> {
+    pub fn new(base: Name<'a>, fields: &'a [Name<'a>], method: Option<Name<'a>>) -> Self {
+        Self {
+            base,
+            fields,
+            method,
+        }
+    }
+
+    /// The span of the whole name
+    pub fn span(&self) -> Span {
+        let end = match (self.method, self.fields.last()) {
+            (Some(method), _) => method.span().end,
+            (None, Some(field)) => field.span().end,
+            (None, None) => self.base.span().end,
+        };
+
+        self.base.span().start..end
+    }
+
+    /// The last segment of the name, i.e. the name the function is assigned to
+    pub fn last(&self) -> &Name<'a> {
+        self.method
+            .as_ref()
+            .or(self.fields.last())
+            .unwrap_or(&self.base)
+    }
+}
+
+impl Display for FunctionName<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.base)?;
+
+        for field in self.fields {
+            write!(f, ".{}", field)?;
+        }
+
+        if let Some(method) = self.method {
+            write!(f, ":{}", method)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
+use crate::ast::Name;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Goto<'a> {
-    pub label: &'a str,
+    pub label: Name<'a>,
 }
 
 impl<'a> Goto<'a> {
-    pub fn new(label: &'a str) -> Self {
+    pub fn new(label: Name<'a>) -> Self {
         Self { label }
     }
 }
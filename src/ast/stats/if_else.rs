@@ -2,17 +2,17 @@ use crate::ast::{node::Node, Block, Exp};
 
 #[derive(Clone, Copy, Debug)]
 pub struct IfElse<'a> {
-    pub cond: Node<&'a Exp<'a>>,
+    pub cond: Node<'a, &'a Exp<'a>>,
     pub body: Block<'a>,
-    pub else_ifs: &'a [(Node<&'a Exp<'a>>, Block<'a>)],
+    pub else_ifs: &'a [(Node<'a, &'a Exp<'a>>, Block<'a>)],
     pub else_block: Option<Block<'a>>,
 }
 
 impl<'a> IfElse<'a> {
     pub fn new(
-        cond: Node<&'a Exp>,
+        cond: Node<'a, &'a Exp>,
         body: Block<'a>,
-        else_ifs: &'a [(Node<&'a Exp>, Block<'a>)],
+        else_ifs: &'a [(Node<'a, &'a Exp>, Block<'a>)],
         else_block: Option<Block<'a>>,
     ) -> Self {
         Self {
@@ -1,10 +1,12 @@
-#[derive(Clone, Copy, Debug, PartialEq)]
+use crate::ast::Name;
+
+#[derive(Clone, Copy, Debug)]
 pub struct Label<'a> {
-    name: &'a str,
+    pub name: Name<'a>,
 }
 
 impl<'a> Label<'a> {
-    pub fn new(name: &'a str) -> Self {
+    pub fn new(name: Name<'a>) -> Self {
         Self { name }
     }
 }
@@ -1,7 +1,16 @@
 pub use self::{
-    assignment::Assignment, do_::Do, for_::For, for_in::ForIn, function_def::FunctionDef,
-    goto::Goto, if_else::IfElse, label::Label, repeat_until::RepeatUntil, return_::Return,
-    var_def::VarDef, while_::While,
+    assignment::Assignment,
+    do_::Do,
+    for_::For,
+    for_in::ForIn,
+    function_def::{FunctionDef, FunctionName},
+    goto::Goto,
+    if_else::IfElse,
+    label::Label,
+    repeat_until::RepeatUntil,
+    return_::Return,
+    var_def::VarDef,
+    while_::While,
 };
 
 mod assignment;
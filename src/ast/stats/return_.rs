@@ -2,11 +2,11 @@ use crate::ast::{node::Node, Exp};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Return<'a> {
-    pub exps: &'a [Node<&'a Exp<'a>>],
+    pub exps: &'a [Node<'a, &'a Exp<'a>>],
 }
 
 impl<'a> Return<'a> {
-    pub fn new(exps: &'a [Node<&'a Exp>]) -> Self {
+    pub fn new(exps: &'a [Node<'a, &'a Exp>]) -> Self {
         Self { exps }
     }
 }
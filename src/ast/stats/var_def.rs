@@ -1,13 +1,13 @@
-use crate::ast::{node::Node, Exp};
+use crate::ast::{node::Node, Exp, Name};
 
 #[derive(Clone, Copy, Debug)]
 pub struct VarDef<'a> {
-    pub names: &'a [&'a str],
-    pub init_exps: Option<&'a [Node<&'a Exp<'a>>]>,
+    pub names: &'a [Name<'a>],
+    pub init_exps: Option<&'a [Node<'a, &'a Exp<'a>>]>,
 }
 
 impl<'a> VarDef<'a> {
-    pub fn new(names: &'a [&'a str], init_exps: Option<&'a [Node<&'a Exp>]>) -> Self {
+    pub fn new(names: &'a [Name<'a>], init_exps: Option<&'a [Node<'a, &'a Exp>]>) -> Self {
         Self { names, init_exps }
     }
 }
@@ -3,11 +3,11 @@ use crate::ast::{node::Node, Block, Exp};
 #[derive(Clone, Copy, Debug)]
 pub struct While<'a> {
     pub body: Block<'a>,
-    pub cond: Node<&'a Exp<'a>>,
+    pub cond: Node<'a, &'a Exp<'a>>,
 }
 
 impl<'a> While<'a> {
-    pub fn new(cond: Node<&'a Exp>, body: Block<'a>) -> Self {
+    pub fn new(cond: Node<'a, &'a Exp>, body: Block<'a>) -> Self {
         Self { body, cond }
     }
 }
@@ -0,0 +1,59 @@
+use logos::Span;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommentKind {
+    /// `-- comment`
+    Line,
+    /// `--[[ comment ]]`
+    Block,
+    /// GMod specific `// comment`
+    CLine,
+    /// GMod specific `/* comment */`
+    CBlock,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Comment<'a> {
+    pub kind: CommentKind,
+    /// The comment's text, excluding its delimiters
+    pub text: &'a str,
+    /// Whether the comment starts on a new line, rather than following other code or comments
+    pub own_line: bool,
+    span: (usize, usize),
+}
+
+/// Comments attached to a node.
+///
+/// `leading` comments precede the node, `trailing` comments either follow it on the same line or
+/// are inside the node but not attached to any of its children (e.g. before a closing `}`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Trivia<'a> {
+    pub leading: &'a [Comment<'a>],
+    pub trailing: &'a [Comment<'a>],
+}
+
+impl<'a> Comment<'a> {
+    pub fn new(kind: CommentKind, text: &'a str, own_line: bool, span: Span) -> Self {
+        Self {
+            kind,
+            text,
+            own_line,
+            span: (span.start, span.end),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span.0..self.span.1
+    }
+
+    /// Whether the comment spans to the end of its line
+    pub fn is_line(&self) -> bool {
+        matches!(self.kind, CommentKind::Line | CommentKind::CLine)
+    }
+}
+
+impl Trivia<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty()
+    }
+}
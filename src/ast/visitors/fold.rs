@@ -0,0 +1,451 @@
+use std::mem;
+
+use bumpalo::Bump;
+use logos::Span;
+
+use crate::ast::{
+    exps::{
+        table::Field, Binary, Function, FunctionCall, Index, Member, MethodCall, TableConstructor,
+        Unary,
+    },
+    node::Node,
+    stats::{
+        Assignment, Do, For, ForIn, FunctionDef, FunctionName, Goto, IfElse, Label, RepeatUntil,
+        Return, VarDef, While,
+    },
+    trivia::{Comment, Trivia},
+    Block, Exp, Name, Stat,
+};
+
+/// Rebuilds a tree into the arena returned by [`Fold::bump`], allowing any node to be replaced
+/// along the way.
+///
+/// Every method defaults to copying its node as is, so implementors only need to override the
+/// nodes they rewrite. Spans and comments are kept, the `walk_*` functions can be called to fold
+/// the children of a node before or after rewriting it.
+pub trait Fold<'b> {
+    fn bump(&self) -> &'b Bump;
+
+    fn fold_block(&mut self, v: Block) -> Block<'b> {
+        walk_block(self, v)
+    }
+
+    // Statements
+    fn fold_stat(&mut self, v: &Node<&Stat>) -> Node<'b, &'b Stat<'b>> {
+        walk_stat(self, v)
+    }
+
+    fn fold_assignment_stat(&mut self, v: &Node<&Assignment>) -> Stat<'b> {
+        walk_assignment_stat(self, v)
+    }
+
+    fn fold_do_stat(&mut self, v: &Node<&Do>) -> Stat<'b> {
+        walk_do_stat(self, v)
+    }
+
+    fn fold_for_stat(&mut self, v: &Node<&For>) -> Stat<'b> {
+        walk_for_stat(self, v)
+    }
+
+    fn fold_for_in_stat(&mut self, v: &Node<&ForIn>) -> Stat<'b> {
+        walk_for_in_stat(self, v)
+    }
+
+    fn fold_function_def_stat(&mut self, v: &Node<&FunctionDef>) -> Stat<'b> {
+        walk_function_def_stat(self, v)
+    }
+
+    fn fold_goto_stat(&mut self, v: &Node<&Goto>) -> Stat<'b> {
+        walk_goto_stat(self, v)
+    }
+
+    fn fold_if_else_stat(&mut self, v: &Node<&IfElse>) -> Stat<'b> {
+        walk_if_else_stat(self, v)
+    }
+
+    fn fold_label_stat(&mut self, v: &Node<&Label>) -> Stat<'b> {
+        walk_label_stat(self, v)
+    }
+
+    fn fold_repeat_until_stat(&mut self, v: &Node<&RepeatUntil>) -> Stat<'b> {
+        walk_repeat_until_stat(self, v)
+    }
+
+    fn fold_return_stat(&mut self, v: &Node<&Return>) -> Stat<'b> {
+        walk_return_stat(self, v)
+    }
+
+    fn fold_var_def_stat(&mut self, v: &Node<&VarDef>) -> Stat<'b> {
+        walk_var_def_stat(self, v)
+    }
+
+    fn fold_while_stat(&mut self, v: &Node<&While>) -> Stat<'b> {
+        walk_while_stat(self, v)
+    }
+
+    // Expressions
+    fn fold_exp(&mut self, v: &Node<&Exp>) -> Node<'b, &'b Exp<'b>> {
+        walk_exp(self, v)
+    }
+
+    fn fold_binary_exp(&mut self, v: &Node<&Binary>) -> Exp<'b> {
+        walk_binary_exp(self, v)
+    }
+
+    fn fold_function_exp(&mut self, v: &Node<&Function>) -> Exp<'b> {
+        Exp::Function(self.fold_function(v))
+    }
+
+    fn fold_index_exp(&mut self, v: &Node<&Index>) -> Exp<'b> {
+        walk_index_exp(self, v)
+    }
+
+    fn fold_member_exp(&mut self, v: &Node<&Member>) -> Exp<'b> {
+        walk_member_exp(self, v)
+    }
+
+    fn fold_table_exp(&mut self, v: &Node<&TableConstructor>) -> Exp<'b> {
+        walk_table_exp(self, v)
+    }
+
+    fn fold_paren_exp(&mut self, v: &Node<&Node<&Exp>>) -> Exp<'b> {
+        walk_paren_exp(self, v)
+    }
+
+    fn fold_unary_exp(&mut self, v: &Node<&Unary>) -> Exp<'b> {
+        walk_unary_exp(self, v)
+    }
+
+    fn fold_ref_exp(&mut self, v: &Name) -> Exp<'b> {
+        Exp::Ref(self.fold_name(v).into_inner())
+    }
+
+    // Common
+    fn fold_function(&mut self, v: &Node<&Function>) -> Function<'b> {
+        walk_function(self, v)
+    }
+
+    fn fold_function_call(&mut self, v: &Node<&FunctionCall>) -> FunctionCall<'b> {
+        walk_function_call(self, v)
+    }
+
+    fn fold_method_call(&mut self, v: &Node<&MethodCall>) -> MethodCall<'b> {
+        walk_method_call(self, v)
+    }
+
+    /// Fold an identifier, be it a variable, parameter, field, method or label
+    fn fold_name(&mut self, v: &Name) -> Name<'b> {
+        walk_name(self, v)
+    }
+
+    fn fold_trivia(&mut self, v: Option<&Trivia>) -> Option<&'b Trivia<'b>> {
+        walk_trivia(self, v)
+    }
+
+    /// Map the span of every node, name and comment
+    fn fold_span(&mut self, v: Span) -> Span {
+        v
+    }
+}
+
+/// Copy `v`'s span and comments onto `inner`
+fn node<'b, F: Fold<'b> + ?Sized, T, U>(folder: &mut F, v: &Node<U>, inner: T) -> Node<'b, &'b T> {
+    let trivia = folder.fold_trivia(v.trivia());
+    let span = folder.fold_span(v.span());
+
+    Node::new(span, &*folder.bump().alloc(inner)).with_trivia(trivia)
+}
+
+fn fold_exps<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &[Node<&Exp>],
+) -> &'b [Node<'b, &'b Exp<'b>>] {
+    folder
+        .bump()
+        .alloc_slice_fill_iter(v.iter().map(|e| folder.fold_exp(e)))
+}
+
+fn fold_names<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &[Name]) -> &'b [Name<'b>] {
+    folder
+        .bump()
+        .alloc_slice_fill_iter(v.iter().map(|name| folder.fold_name(name)))
+}
+
+pub fn walk_block<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: Block) -> Block<'b> {
+    folder
+        .bump()
+        .alloc_slice_fill_iter(v.iter().map(|s| folder.fold_stat(s)))
+}
+
+// Statement walkers
+pub fn walk_stat<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&Stat>,
+) -> Node<'b, &'b Stat<'b>> {
+    let stat = match **v {
+        Stat::Assignment(s) => folder.fold_assignment_stat(&Node::morph(v, s)),
+        Stat::Do(s) => folder.fold_do_stat(&Node::morph(v, s)),
+        Stat::For(s) => folder.fold_for_stat(&Node::morph(v, s)),
+        Stat::ForIn(s) => folder.fold_for_in_stat(&Node::morph(v, s)),
+        Stat::FunctionCall(s) => Stat::FunctionCall(folder.fold_function_call(&Node::morph(v, s))),
+        Stat::FunctionDef(s) => folder.fold_function_def_stat(&Node::morph(v, s)),
+        Stat::Goto(s) => folder.fold_goto_stat(&Node::morph(v, s)),
+        Stat::IfElse(s) => folder.fold_if_else_stat(&Node::morph(v, s)),
+        Stat::Label(s) => folder.fold_label_stat(&Node::morph(v, s)),
+        Stat::MethodCall(s) => Stat::MethodCall(folder.fold_method_call(&Node::morph(v, s))),
+        Stat::RepeatUntil(s) => folder.fold_repeat_until_stat(&Node::morph(v, s)),
+        Stat::Return(s) => folder.fold_return_stat(&Node::morph(v, s)),
+        Stat::VarDef(s) => folder.fold_var_def_stat(&Node::morph(v, s)),
+        Stat::While(s) => folder.fold_while_stat(&Node::morph(v, s)),
+        Stat::Break => Stat::Break,
+        Stat::Continue => Stat::Continue,
+        Stat::Error => Stat::Error,
+        Stat::None => Stat::None,
+    };
+
+    node(folder, v, stat)
+}
+
+pub fn walk_assignment_stat<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&Assignment>,
+) -> Stat<'b> {
+    let vars = fold_exps(folder, v.vars);
+    let exps = fold_exps(folder, v.exps);
+
+    Assignment::new(vars, exps).into()
+}
+
+pub fn walk_do_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Do>) -> Stat<'b> {
+    Do::new(folder.fold_block(v.body)).into()
+}
+
+pub fn walk_for_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&For>) -> Stat<'b> {
+    let init = (folder.fold_name(&v.init.0), folder.fold_exp(&v.init.1));
+    let test = folder.fold_exp(&v.test);
+    let update = v.update.as_ref().map(|update| folder.fold_exp(update));
+    let body = folder.fold_block(v.body);
+
+    For::new(init, test, update, body).into()
+}
+
+pub fn walk_for_in_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&ForIn>) -> Stat<'b> {
+    let names = fold_names(folder, v.names);
+    let exps = fold_exps(folder, v.exps);
+    let body = folder.fold_block(v.body);
+
+    ForIn::new(names, exps, body).into()
+}
+
+pub fn walk_function_def_stat<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&FunctionDef>,
+) -> Stat<'b> {
+    let name = FunctionName::new(
+        folder.fold_name(&v.name.base),
+        fold_names(folder, v.name.fields),
+        v.name
+            .method
+            .as_ref()
+            .map(|method| folder.fold_name(method)),
+    );
+    let body = folder.fold_function(&v.body);
+
+    FunctionDef::new(v.local, name, node(folder, &v.body, body)).into()
+}
+
+pub fn walk_goto_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Goto>) -> Stat<'b> {
+    Goto::new(folder.fold_name(&v.label)).into()
+}
+
+pub fn walk_if_else_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&IfElse>) -> Stat<'b> {
+    let cond = folder.fold_exp(&v.cond);
+    let body = folder.fold_block(v.body);
+
+    let else_ifs = folder.bump().alloc_slice_fill_iter(
+        v.else_ifs
+            .iter()
+            .map(|(cond, block)| (folder.fold_exp(cond), folder.fold_block(block))),
+    );
+
+    let else_block = v.else_block.map(|block| folder.fold_block(block));
+
+    IfElse::new(cond, body, else_ifs, else_block).into()
+}
+
+pub fn walk_label_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Label>) -> Stat<'b> {
+    Label::new(folder.fold_name(&v.name)).into()
+}
+
+pub fn walk_repeat_until_stat<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&RepeatUntil>,
+) -> Stat<'b> {
+    let body = folder.fold_block(v.body);
+    let cond = folder.fold_exp(&v.cond);
+
+    RepeatUntil::new(body, cond).into()
+}
+
+pub fn walk_return_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Return>) -> Stat<'b> {
+    Return::new(fold_exps(folder, v.exps)).into()
+}
+
+pub fn walk_var_def_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&VarDef>) -> Stat<'b> {
+    let names = fold_names(folder, v.names);
+    let init_exps = v.init_exps.map(|exps| fold_exps(folder, exps));
+
+    VarDef::new(names, init_exps).into()
+}
+
+pub fn walk_while_stat<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&While>) -> Stat<'b> {
+    let cond = folder.fold_exp(&v.cond);
+    let body = folder.fold_block(v.body);
+
+    While::new(cond, body).into()
+}
+
+// Expression walkers
+pub fn walk_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Exp>) -> Node<'b, &'b Exp<'b>> {
+    let exp = match **v {
+        Exp::Binary(e) => folder.fold_binary_exp(&Node::morph(v, e)),
+        Exp::Function(e) => folder.fold_function_exp(&Node::morph(v, e)),
+        Exp::FunctionCall(e) => Exp::FunctionCall(folder.fold_function_call(&Node::morph(v, e))),
+        Exp::Index(e) => folder.fold_index_exp(&Node::morph(v, e)),
+        Exp::Member(e) => folder.fold_member_exp(&Node::morph(v, e)),
+        Exp::MethodCall(e) => Exp::MethodCall(folder.fold_method_call(&Node::morph(v, e))),
+        Exp::Table(e) => folder.fold_table_exp(&Node::morph(v, e)),
+        Exp::Paren(e) => folder.fold_paren_exp(&Node::morph(v, e)),
+        Exp::Unary(e) => folder.fold_unary_exp(&Node::morph(v, e)),
+        Exp::Ref(e) => folder.fold_ref_exp(&Node::morph(v, e)),
+        Exp::Bool(e) => Exp::Bool(*e),
+        Exp::Nil => Exp::Nil,
+        Exp::Number(e) => Exp::Number(*e),
+        Exp::String(e) => Exp::String(folder.bump().alloc_slice_copy(e)),
+        Exp::VarArgs => Exp::VarArgs,
+    };
+
+    node(folder, v, exp)
+}
+
+pub fn walk_binary_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Binary>) -> Exp<'b> {
+    let lhs = folder.fold_exp(&v.lhs);
+    let rhs = folder.fold_exp(&v.rhs);
+
+    Binary::new(lhs, v.op, rhs).into()
+}
+
+pub fn walk_index_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Index>) -> Exp<'b> {
+    let lhs = folder.fold_exp(&v.lhs);
+    let exp = folder.fold_exp(&v.exp);
+
+    Index::new(lhs, exp).into()
+}
+
+pub fn walk_member_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Member>) -> Exp<'b> {
+    let lhs = folder.fold_exp(&v.lhs);
+
+    Member::new(lhs, folder.fold_name(&v.name)).into()
+}
+
+pub fn walk_table_exp<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&TableConstructor>,
+) -> Exp<'b> {
+    let fields = folder
+        .bump()
+        .alloc_slice_fill_iter(v.fields.iter().map(|f| {
+            let key = f.key.as_ref().map(|key| folder.fold_exp(key));
+
+            Field::new(key, folder.fold_exp(&f.value))
+        }));
+
+    TableConstructor::new(fields).into()
+}
+
+pub fn walk_paren_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Node<&Exp>>) -> Exp<'b> {
+    let exp = folder.fold_exp(v);
+
+    // Parentheses only change the meaning of calls and `...`, so they're dropped once the
+    // expression inside has been rewritten into anything else, e.g. a folded constant. Renderers
+    // add back any that precedence needs
+    let rewritten = mem::discriminant(*exp) != mem::discriminant(****v);
+    let truncates = matches!(
+        **exp,
+        Exp::FunctionCall(_) | Exp::MethodCall(_) | Exp::VarArgs
+    );
+
+    match rewritten && !truncates {
+        true => **exp,
+        false => Exp::Paren(exp),
+    }
+}
+
+pub fn walk_unary_exp<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Node<&Unary>) -> Exp<'b> {
+    Unary::new(v.op, folder.fold_exp(&v.exp)).into()
+}
+
+// Common walkers
+pub fn walk_function<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&Function>,
+) -> Function<'b> {
+    let params = fold_names(folder, v.params);
+    let vararg = v.vararg.map(|vararg| {
+        Node::new(folder.fold_span(vararg.span()), ())
+            .with_trivia(folder.fold_trivia(vararg.trivia()))
+    });
+    let body = folder.fold_block(v.body);
+
+    Function::new(params, vararg, body)
+}
+
+pub fn walk_function_call<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&FunctionCall>,
+) -> FunctionCall<'b> {
+    let lhs = folder.fold_exp(&v.lhs);
+    let args = fold_exps(folder, v.args);
+
+    FunctionCall::new(lhs, args)
+}
+
+pub fn walk_method_call<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: &Node<&MethodCall>,
+) -> MethodCall<'b> {
+    let lhs = folder.fold_exp(&v.lhs);
+    let name = folder.fold_name(&v.name);
+    let args = fold_exps(folder, v.args);
+
+    MethodCall::new(lhs, name, args)
+}
+
+pub fn walk_name<'b, F: Fold<'b> + ?Sized>(folder: &mut F, v: &Name) -> Name<'b> {
+    let trivia = folder.fold_trivia(v.trivia());
+    let span = folder.fold_span(v.span());
+
+    Node::new(span, &*folder.bump().alloc_str(v)).with_trivia(trivia)
+}
+
+pub fn walk_trivia<'b, F: Fold<'b> + ?Sized>(
+    folder: &mut F,
+    v: Option<&Trivia>,
+) -> Option<&'b Trivia<'b>> {
+    let bump = folder.bump();
+
+    let mut comments = |comments: &[Comment]| {
+        &*bump.alloc_slice_fill_iter(comments.iter().map(|c| {
+            let span = folder.fold_span(c.span());
+
+            Comment::new(c.kind, bump.alloc_str(c.text), c.own_line, span)
+        }))
+    };
+
+    v.map(|trivia| {
+        &*bump.alloc(Trivia {
+            leading: comments(trivia.leading),
+            trailing: comments(trivia.trailing),
+        })
+    })
+}
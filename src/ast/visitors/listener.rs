@@ -0,0 +1,508 @@
+use crate::ast::{
+    exps::{Binary, Function, FunctionCall, Index, Member, MethodCall, TableConstructor, Unary},
+    node::Node,
+    stats::{
+        Assignment, Do, For, ForIn, FunctionDef, Goto, IfElse, Label, RepeatUntil, Return, VarDef,
+        While,
+    },
+    Block, Exp, Stat,
+};
+
+/// Callbacks for a source order traversal of a tree by [`traverse`], each given the ancestors of
+/// the node being entered or left.
+///
+/// Unlike a [`Visitor`](super::Visitor), a listener can't skip children, but doesn't have to
+/// recurse itself either.
+#[allow(unused_variables)]
+pub trait Listener<'a> {
+    fn enter_block(&mut self, v: Block<'a>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_block(&mut self, v: Block<'a>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_stat(&mut self, v: &Node<'a, &'a Stat<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_stat(&mut self, v: &Node<'a, &'a Stat<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_exp(&mut self, v: &Node<'a, &'a Exp<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_exp(&mut self, v: &Node<'a, &'a Exp<'a>>, ancestors: &Ancestors<'a>) {}
+
+    /// Entered for both function expressions and the bodies of function definitions, after the
+    /// expression or statement holding it
+    fn enter_function(&mut self, v: &Node<'a, &'a Function<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_function(&mut self, v: &Node<'a, &'a Function<'a>>, ancestors: &Ancestors<'a>) {}
+
+    // Statements, entered after `enter_stat` and left before `leave_stat`
+
+    fn enter_assignment_stat(
+        &mut self,
+        v: &Node<'a, &'a Assignment<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_assignment_stat(
+        &mut self,
+        v: &Node<'a, &'a Assignment<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_do_stat(&mut self, v: &Node<'a, &'a Do<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_do_stat(&mut self, v: &Node<'a, &'a Do<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_for_stat(&mut self, v: &Node<'a, &'a For<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_for_stat(&mut self, v: &Node<'a, &'a For<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_for_in_stat(&mut self, v: &Node<'a, &'a ForIn<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_for_in_stat(&mut self, v: &Node<'a, &'a ForIn<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_function_def_stat(
+        &mut self,
+        v: &Node<'a, &'a FunctionDef<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_function_def_stat(
+        &mut self,
+        v: &Node<'a, &'a FunctionDef<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_goto_stat(&mut self, v: &Node<'a, &'a Goto<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_goto_stat(&mut self, v: &Node<'a, &'a Goto<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_if_else_stat(&mut self, v: &Node<'a, &'a IfElse<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_if_else_stat(&mut self, v: &Node<'a, &'a IfElse<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_label_stat(&mut self, v: &Node<'a, &'a Label<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_label_stat(&mut self, v: &Node<'a, &'a Label<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_repeat_until_stat(
+        &mut self,
+        v: &Node<'a, &'a RepeatUntil<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_repeat_until_stat(
+        &mut self,
+        v: &Node<'a, &'a RepeatUntil<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_return_stat(&mut self, v: &Node<'a, &'a Return<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_return_stat(&mut self, v: &Node<'a, &'a Return<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_var_def_stat(&mut self, v: &Node<'a, &'a VarDef<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_var_def_stat(&mut self, v: &Node<'a, &'a VarDef<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_while_stat(&mut self, v: &Node<'a, &'a While<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_while_stat(&mut self, v: &Node<'a, &'a While<'a>>, ancestors: &Ancestors<'a>) {}
+
+    // Expressions, entered after `enter_exp` and left before `leave_exp`
+
+    fn enter_binary_exp(&mut self, v: &Node<'a, &'a Binary<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_binary_exp(&mut self, v: &Node<'a, &'a Binary<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_index_exp(&mut self, v: &Node<'a, &'a Index<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_index_exp(&mut self, v: &Node<'a, &'a Index<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_member_exp(&mut self, v: &Node<'a, &'a Member<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_member_exp(&mut self, v: &Node<'a, &'a Member<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_paren_exp(
+        &mut self,
+        v: &Node<'a, &'a Node<'a, &'a Exp<'a>>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_paren_exp(
+        &mut self,
+        v: &Node<'a, &'a Node<'a, &'a Exp<'a>>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_ref_exp(&mut self, v: &Node<'a, &'a str>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_ref_exp(&mut self, v: &Node<'a, &'a str>, ancestors: &Ancestors<'a>) {}
+
+    fn enter_table_exp(
+        &mut self,
+        v: &Node<'a, &'a TableConstructor<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_table_exp(
+        &mut self,
+        v: &Node<'a, &'a TableConstructor<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_unary_exp(&mut self, v: &Node<'a, &'a Unary<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_unary_exp(&mut self, v: &Node<'a, &'a Unary<'a>>, ancestors: &Ancestors<'a>) {}
+
+    // Calls, whether statements or expressions
+
+    fn enter_function_call(
+        &mut self,
+        v: &Node<'a, &'a FunctionCall<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn leave_function_call(
+        &mut self,
+        v: &Node<'a, &'a FunctionCall<'a>>,
+        ancestors: &Ancestors<'a>,
+    ) {
+    }
+
+    fn enter_method_call(&mut self, v: &Node<'a, &'a MethodCall<'a>>, ancestors: &Ancestors<'a>) {}
+
+    fn leave_method_call(&mut self, v: &Node<'a, &'a MethodCall<'a>>, ancestors: &Ancestors<'a>) {}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Ancestor<'a> {
+    Stat(Node<'a, &'a Stat<'a>>),
+    Exp(Node<'a, &'a Exp<'a>>),
+    Function(Node<'a, &'a Function<'a>>),
+}
+
+/// The nodes enclosing the current node, outermost first
+#[derive(Debug, Default)]
+pub struct Ancestors<'a> {
+    stack: Vec<Ancestor<'a>>,
+}
+
+impl<'a> Ancestors<'a> {
+    /// The innermost enclosing node
+    pub fn parent(&self) -> Option<&Ancestor<'a>> {
+        self.stack.last()
+    }
+
+    /// Enclosing nodes, innermost first
+    pub fn iter(&self) -> impl Iterator<Item = &Ancestor<'a>> {
+        self.stack.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// The innermost enclosing function
+    pub fn function(&self) -> Option<&Node<'a, &'a Function<'a>>> {
+        self.iter().find_map(|ancestor| match ancestor {
+            Ancestor::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn in_function(&self) -> bool {
+        self.function().is_some()
+    }
+
+    /// The innermost enclosing loop in the current function, which `break` and `continue` apply
+    /// to
+    pub fn loop_stat(&self) -> Option<&Node<'a, &'a Stat<'a>>> {
+        self.iter()
+            .take_while(|ancestor| !matches!(ancestor, Ancestor::Function(_)))
+            .find_map(|ancestor| match ancestor {
+                Ancestor::Stat(stat) => match **stat {
+                    Stat::For(_) | Stat::ForIn(_) | Stat::RepeatUntil(_) | Stat::While(_) => {
+                        Some(stat)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+    }
+
+    pub fn in_loop(&self) -> bool {
+        self.loop_stat().is_some()
+    }
+}
+
+/// Walk `block` in source order, calling `listener` on entering and leaving each node
+pub fn traverse<'a, L: Listener<'a> + ?Sized>(listener: &mut L, block: Block<'a>) {
+    Traversal {
+        listener,
+        ancestors: Ancestors::default(),
+    }
+    .block(block);
+}
+
+struct Traversal<'a, 'l, L: ?Sized> {
+    listener: &'l mut L,
+    ancestors: Ancestors<'a>,
+}
+
+impl<'a, L: Listener<'a> + ?Sized> Traversal<'a, '_, L> {
+    fn block(&mut self, v: Block<'a>) {
+        self.listener.enter_block(v, &self.ancestors);
+
+        v.iter().for_each(|s| self.stat(s));
+
+        self.listener.leave_block(v, &self.ancestors);
+    }
+
+    fn exps(&mut self, v: &'a [Node<'a, &'a Exp<'a>>]) {
+        v.iter().for_each(|e| self.exp(e));
+    }
+
+    fn stat(&mut self, v: &Node<'a, &'a Stat<'a>>) {
+        self.listener.enter_stat(v, &self.ancestors);
+        self.enter_stat_kind(v);
+        self.ancestors.stack.push(Ancestor::Stat(*v));
+
+        let stat: &'a Stat<'a> = **v;
+
+        match stat {
+            Stat::Assignment(s) => {
+                self.exps(s.vars);
+                self.exps(s.exps);
+            }
+            Stat::Do(s) => self.block(s.body),
+            Stat::For(s) => {
+                self.exp(&s.init.1);
+                self.exp(&s.test);
+
+                if let Some(update) = &s.update {
+                    self.exp(update);
+                }
+
+                self.block(s.body);
+            }
+            Stat::ForIn(s) => {
+                self.exps(s.exps);
+                self.block(s.body);
+            }
+            Stat::FunctionCall(s) => {
+                self.exp(&s.lhs);
+                self.exps(s.args);
+            }
+            Stat::FunctionDef(s) => self.function(&s.body),
+            Stat::IfElse(s) => {
+                self.exp(&s.cond);
+                self.block(s.body);
+
+                for (cond, block) in s.else_ifs {
+                    self.exp(cond);
+                    self.block(block);
+                }
+
+                if let Some(else_block) = s.else_block {
+                    self.block(else_block);
+                }
+            }
+            Stat::MethodCall(s) => {
+                self.exp(&s.lhs);
+                self.exps(s.args);
+            }
+            Stat::RepeatUntil(s) => {
+                self.block(s.body);
+                self.exp(&s.cond);
+            }
+            Stat::Return(s) => self.exps(s.exps),
+            Stat::VarDef(s) => {
+                if let Some(init_exps) = s.init_exps {
+                    self.exps(init_exps);
+                }
+            }
+            Stat::While(s) => {
+                self.exp(&s.cond);
+                self.block(s.body);
+            }
+            Stat::Break
+            | Stat::Continue
+            | Stat::Error
+            | Stat::Goto(_)
+            | Stat::Label(_)
+            | Stat::None => {}
+        }
+
+        self.ancestors.stack.pop();
+        self.leave_stat_kind(v);
+        self.listener.leave_stat(v, &self.ancestors);
+    }
+
+    fn exp(&mut self, v: &Node<'a, &'a Exp<'a>>) {
+        self.listener.enter_exp(v, &self.ancestors);
+        self.enter_exp_kind(v);
+        self.ancestors.stack.push(Ancestor::Exp(*v));
+
+        let exp: &'a Exp<'a> = **v;
+
+        match exp {
+            Exp::Binary(e) => {
+                self.exp(&e.lhs);
+                self.exp(&e.rhs);
+            }
+            Exp::Function(e) => self.function(&Node::morph(v, e)),
+            Exp::FunctionCall(e) => {
+                self.exp(&e.lhs);
+                self.exps(e.args);
+            }
+            Exp::Index(e) => {
+                self.exp(&e.lhs);
+                self.exp(&e.exp);
+            }
+            Exp::Member(e) => self.exp(&e.lhs),
+            Exp::MethodCall(e) => {
+                self.exp(&e.lhs);
+                self.exps(e.args);
+            }
+            Exp::Table(e) => {
+                for field in e.fields {
+                    if let Some(key) = &field.key {
+                        self.exp(key);
+                    }
+
+                    self.exp(&field.value);
+                }
+            }
+            Exp::Paren(e) => self.exp(e),
+            Exp::Unary(e) => self.exp(&e.exp),
+            Exp::Bool(_)
+            | Exp::Nil
+            | Exp::Number(_)
+            | Exp::Ref(_)
+            | Exp::String(_)
+            | Exp::VarArgs => {}
+        }
+
+        self.ancestors.stack.pop();
+        self.leave_exp_kind(v);
+        self.listener.leave_exp(v, &self.ancestors);
+    }
+
+    fn function(&mut self, v: &Node<'a, &'a Function<'a>>) {
+        self.listener.enter_function(v, &self.ancestors);
+        self.ancestors.stack.push(Ancestor::Function(*v));
+
+        self.block(v.body);
+
+        self.ancestors.stack.pop();
+        self.listener.leave_function(v, &self.ancestors);
+    }
+
+    fn enter_stat_kind(&mut self, v: &Node<'a, &'a Stat<'a>>) {
+        let Self {
+            listener,
+            ancestors,
+        } = self;
+        let node: &'a Stat<'a> = **v;
+
+        match node {
+            Stat::Assignment(s) => listener.enter_assignment_stat(&Node::morph(v, s), ancestors),
+            Stat::Do(s) => listener.enter_do_stat(&Node::morph(v, s), ancestors),
+            Stat::For(s) => listener.enter_for_stat(&Node::morph(v, s), ancestors),
+            Stat::ForIn(s) => listener.enter_for_in_stat(&Node::morph(v, s), ancestors),
+            Stat::FunctionDef(s) => listener.enter_function_def_stat(&Node::morph(v, s), ancestors),
+            Stat::Goto(s) => listener.enter_goto_stat(&Node::morph(v, s), ancestors),
+            Stat::IfElse(s) => listener.enter_if_else_stat(&Node::morph(v, s), ancestors),
+            Stat::Label(s) => listener.enter_label_stat(&Node::morph(v, s), ancestors),
+            Stat::RepeatUntil(s) => listener.enter_repeat_until_stat(&Node::morph(v, s), ancestors),
+            Stat::Return(s) => listener.enter_return_stat(&Node::morph(v, s), ancestors),
+            Stat::VarDef(s) => listener.enter_var_def_stat(&Node::morph(v, s), ancestors),
+            Stat::While(s) => listener.enter_while_stat(&Node::morph(v, s), ancestors),
+            Stat::FunctionCall(s) => listener.enter_function_call(&Node::morph(v, s), ancestors),
+            Stat::MethodCall(s) => listener.enter_method_call(&Node::morph(v, s), ancestors),
+            _ => {}
+        }
+    }
+
+    fn leave_stat_kind(&mut self, v: &Node<'a, &'a Stat<'a>>) {
+        let Self {
+            listener,
+            ancestors,
+        } = self;
+        let node: &'a Stat<'a> = **v;
+
+        match node {
+            Stat::Assignment(s) => listener.leave_assignment_stat(&Node::morph(v, s), ancestors),
+            Stat::Do(s) => listener.leave_do_stat(&Node::morph(v, s), ancestors),
+            Stat::For(s) => listener.leave_for_stat(&Node::morph(v, s), ancestors),
+            Stat::ForIn(s) => listener.leave_for_in_stat(&Node::morph(v, s), ancestors),
+            Stat::FunctionDef(s) => listener.leave_function_def_stat(&Node::morph(v, s), ancestors),
+            Stat::Goto(s) => listener.leave_goto_stat(&Node::morph(v, s), ancestors),
+            Stat::IfElse(s) => listener.leave_if_else_stat(&Node::morph(v, s), ancestors),
+            Stat::Label(s) => listener.leave_label_stat(&Node::morph(v, s), ancestors),
+            Stat::RepeatUntil(s) => listener.leave_repeat_until_stat(&Node::morph(v, s), ancestors),
+            Stat::Return(s) => listener.leave_return_stat(&Node::morph(v, s), ancestors),
+            Stat::VarDef(s) => listener.leave_var_def_stat(&Node::morph(v, s), ancestors),
+            Stat::While(s) => listener.leave_while_stat(&Node::morph(v, s), ancestors),
+            Stat::FunctionCall(s) => listener.leave_function_call(&Node::morph(v, s), ancestors),
+            Stat::MethodCall(s) => listener.leave_method_call(&Node::morph(v, s), ancestors),
+            _ => {}
+        }
+    }
+
+    fn enter_exp_kind(&mut self, v: &Node<'a, &'a Exp<'a>>) {
+        let Self {
+            listener,
+            ancestors,
+        } = self;
+        let node: &'a Exp<'a> = **v;
+
+        match node {
+            Exp::Binary(e) => listener.enter_binary_exp(&Node::morph(v, e), ancestors),
+            Exp::Index(e) => listener.enter_index_exp(&Node::morph(v, e), ancestors),
+            Exp::Member(e) => listener.enter_member_exp(&Node::morph(v, e), ancestors),
+            Exp::Paren(e) => listener.enter_paren_exp(&Node::morph(v, e), ancestors),
+            Exp::Ref(e) => listener.enter_ref_exp(&Node::morph(v, e), ancestors),
+            Exp::Table(e) => listener.enter_table_exp(&Node::morph(v, e), ancestors),
+            Exp::Unary(e) => listener.enter_unary_exp(&Node::morph(v, e), ancestors),
+            Exp::FunctionCall(e) => listener.enter_function_call(&Node::morph(v, e), ancestors),
+            Exp::MethodCall(e) => listener.enter_method_call(&Node::morph(v, e), ancestors),
+            _ => {}
+        }
+    }
+
+    fn leave_exp_kind(&mut self, v: &Node<'a, &'a Exp<'a>>) {
+        let Self {
+            listener,
+            ancestors,
+        } = self;
+        let node: &'a Exp<'a> = **v;
+
+        match node {
+            Exp::Binary(e) => listener.leave_binary_exp(&Node::morph(v, e), ancestors),
+            Exp::Index(e) => listener.leave_index_exp(&Node::morph(v, e), ancestors),
+            Exp::Member(e) => listener.leave_member_exp(&Node::morph(v, e), ancestors),
+            Exp::Paren(e) => listener.leave_paren_exp(&Node::morph(v, e), ancestors),
+            Exp::Ref(e) => listener.leave_ref_exp(&Node::morph(v, e), ancestors),
+            Exp::Table(e) => listener.leave_table_exp(&Node::morph(v, e), ancestors),
+            Exp::Unary(e) => listener.leave_unary_exp(&Node::morph(v, e), ancestors),
+            Exp::FunctionCall(e) => listener.leave_function_call(&Node::morph(v, e), ancestors),
+            Exp::MethodCall(e) => listener.leave_method_call(&Node::morph(v, e), ancestors),
+            _ => {}
+        }
+    }
+}
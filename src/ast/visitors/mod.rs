@@ -8,8 +8,12 @@ use crate::ast::{
     Block, Exp, Stat,
 };
 
+pub mod fold;
+pub mod listener;
 pub mod renderer;
 
+/// Read-only traversal of a tree, visiting children in source order. See [`listener`] for a
+/// traversal with access to the ancestors of each node.
 pub trait Visitor {
     // Statements
     fn visit_stat(&mut self, v: &Node<&Stat>) {
@@ -64,6 +68,8 @@ pub trait Visitor {
 
     fn visit_continue_stat(&mut self) {}
 
+    fn visit_error_stat(&mut self) {}
+
     fn visit_none_stat(&mut self) {}
 
     // Expressions
@@ -101,6 +107,10 @@ pub trait Visitor {
 
     fn visit_number_exp(&mut self, _v: &Node<&f64>) {}
 
+    fn visit_paren_exp(&mut self, v: &Node<&Node<&Exp>>) {
+        walk_paren_exp(self, v);
+    }
+
     fn visit_ref_exp(&mut self, _v: &Node<&str>) {}
 
     fn visit_string_exp(&mut self, _v: &Node<&[u8]>) {}
@@ -140,13 +150,14 @@ pub fn walk_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Stat>) {
         Stat::While(s) => visitor.visit_while_stat(&Node::morph(v, s)),
         Stat::Break => visitor.visit_break_stat(),
         Stat::Continue => visitor.visit_continue_stat(),
+        Stat::Error => visitor.visit_error_stat(),
         Stat::None => visitor.visit_none_stat(),
     };
 }
 
 pub fn walk_assignment_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Assignment>) {
-    v.exps.iter().for_each(|e| visitor.visit_exp(e));
     v.vars.iter().for_each(|e| visitor.visit_exp(e));
+    v.exps.iter().for_each(|e| visitor.visit_exp(e));
 }
 
 pub fn walk_do_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Do>) {
@@ -154,18 +165,19 @@ pub fn walk_do_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Do>) {
 }
 
 pub fn walk_for_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&For>) {
-    v.body.iter().for_each(|s| visitor.visit_stat(s));
     visitor.visit_exp(&v.init.1);
     visitor.visit_exp(&v.test);
 
     if let Some(ref update) = v.update {
         visitor.visit_exp(update);
     }
+
+    v.body.iter().for_each(|s| visitor.visit_stat(s));
 }
 
 pub fn walk_for_in_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&ForIn>) {
-    v.body.iter().for_each(|s| visitor.visit_stat(s));
     v.exps.iter().for_each(|e| visitor.visit_exp(e));
+    v.body.iter().for_each(|s| visitor.visit_stat(s));
 }
 
 pub fn walk_function_def_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&FunctionDef>) {
@@ -173,12 +185,12 @@ pub fn walk_function_def_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Fu
 }
 
 pub fn walk_if_else_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&IfElse>) {
-    v.body.iter().for_each(|s| visitor.visit_stat(s));
     visitor.visit_exp(&v.cond);
+    v.body.iter().for_each(|s| visitor.visit_stat(s));
 
     v.else_ifs.iter().for_each(|(exp, block)| {
-        block.iter().for_each(|s| visitor.visit_stat(s));
         visitor.visit_exp(exp);
+        block.iter().for_each(|s| visitor.visit_stat(s));
     });
 
     if let Some(else_block) = &v.else_block {
@@ -202,8 +214,8 @@ pub fn walk_var_def_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&VarDef>
 }
 
 pub fn walk_while_stat<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&While>) {
-    v.body.iter().for_each(|s| visitor.visit_stat(s));
     visitor.visit_exp(&v.cond);
+    v.body.iter().for_each(|s| visitor.visit_stat(s));
 }
 
 // Expression walkers
@@ -220,6 +232,7 @@ pub fn walk_exp<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Exp>) {
         Exp::Bool(e) => visitor.visit_bool_exp(&Node::morph(v, e)),
         Exp::Nil => visitor.visit_nil_exp(&Node::morph(v, ())),
         Exp::Number(e) => visitor.visit_number_exp(&Node::morph(v, e)),
+        Exp::Paren(e) => visitor.visit_paren_exp(&Node::morph(v, e)),
         Exp::Ref(e) => visitor.visit_ref_exp(&Node::morph(v, e)),
         Exp::String(e) => visitor.visit_string_exp(&Node::morph(v, e)),
         Exp::VarArgs => visitor.visit_var_args_exp(&Node::morph(v, ())),
@@ -246,25 +259,29 @@ pub fn walk_member_exp<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Member>)
 
 pub fn walk_table_exp<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&TableConstructor>) {
     v.fields.iter().for_each(|f| {
-        visitor.visit_exp(&f.value);
-
         if let Some(key) = &f.key {
             visitor.visit_exp(key);
         }
+
+        visitor.visit_exp(&f.value);
     })
 }
 
+pub fn walk_paren_exp<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Node<&Exp>>) {
+    visitor.visit_exp(v);
+}
+
 pub fn walk_unary_exp<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&Unary>) {
     visitor.visit_exp(&v.exp);
 }
 
 // Common walkers
 pub fn walk_function_call<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&FunctionCall>) {
-    v.args.iter().for_each(|e| visitor.visit_exp(e));
     visitor.visit_exp(&v.lhs);
+    v.args.iter().for_each(|e| visitor.visit_exp(e));
 }
 
 pub fn walk_method_call<V: Visitor + ?Sized>(visitor: &mut V, v: &Node<&MethodCall>) {
-    v.args.iter().for_each(|e| visitor.visit_exp(e));
     visitor.visit_exp(&v.lhs);
+    v.args.iter().for_each(|e| visitor.visit_exp(e));
 }
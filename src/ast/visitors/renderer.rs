@@ -1,35 +1,569 @@
-use crate::ast::{
-    exps::Member,
-    node::Node,
-    visitors::{walk_exp, Visitor},
+use std::fmt::Write;
+
+use crate::{
+    ast::{
+        exps::{
+            unary::UnOp, Binary, Function, FunctionCall, Index, Member, MethodCall,
+            TableConstructor, Unary,
+        },
+        node::Node,
+        stats::{
+            Assignment, Do, For, ForIn, FunctionDef, Goto, IfElse, Label, RepeatUntil, Return,
+            VarDef, While,
+        },
+        visitors::{walk_exp, walk_stat, Visitor},
+        Block, Exp, Name, Stat,
+    },
+    parser::Precedence,
 };
 
+const INDENT: &str = "    ";
+
+/// Renders a syntax tree back into valid GLua.
+///
+/// Parentheses are inserted wherever operator precedence requires them and strings are
+/// re-escaped, so the output always parses back into an equivalent tree. Comments are not
+/// rendered.
+#[derive(Default)]
 pub struct Renderer {
     pub inner: String,
+    indent: usize,
 }
 
-impl Default for Renderer {
-    fn default() -> Self {
-        let inner = String::new();
+impl Renderer {
+    /// Render `block` as a chunk
+    pub fn render(block: Block) -> String {
+        let mut renderer = Self::default();
 
-        Self { inner }
+        block.iter().for_each(|s| renderer.visit_stat(s));
+
+        renderer.into_inner()
     }
-}
 
-impl Renderer {
     pub fn into_inner(self) -> String {
         self.inner
     }
+
+    fn push_indent(&mut self) {
+        (0..self.indent).for_each(|_| self.inner.push_str(INDENT));
+    }
+
+    /// Render `block` one level deeper than the current statement, leaving the output at the
+    /// start of the line that closes it
+    fn push_block(&mut self, block: Block) {
+        self.inner.push('\n');
+
+        self.indent += 1;
+        block.iter().for_each(|s| self.visit_stat(s));
+        self.indent -= 1;
+
+        self.push_indent();
+    }
+
+    fn push_list<T>(&mut self, items: &[T], mut cb: impl FnMut(&mut Self, &T)) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.inner.push_str(", ");
+            }
+
+            cb(self, item);
+        }
+    }
+
+    fn push_exps(&mut self, exps: &[Node<&Exp>]) {
+        self.push_list(exps, |r, e| r.visit_exp(e));
+    }
+
+    fn push_names(&mut self, names: &[Name]) {
+        self.push_list(names, |r, name| r.inner.push_str(name));
+    }
+
+    fn push_args(&mut self, args: &[Node<&Exp>]) {
+        self.inner.push('(');
+        self.push_exps(args);
+        self.inner.push(')');
+    }
+
+    fn push_function(&mut self, function: &Function) {
+        self.inner.push('(');
+        self.push_names(function.params);
+
+        if function.is_vararg() {
+            if !function.params.is_empty() {
+                self.inner.push_str(", ");
+            }
+
+            self.inner.push_str("...");
+        }
+
+        self.inner.push(')');
+
+        match is_empty(function.body) {
+            true => self.inner.push(' '),
+            false => self.push_block(function.body),
+        }
+
+        self.inner.push_str("end");
+    }
+
+    fn push_parenthesised(&mut self, v: &Node<&Exp>, parens: bool) {
+        if parens {
+            self.inner.push('(');
+        }
+
+        self.visit_exp(v);
+
+        if parens {
+            self.inner.push(')');
+        }
+    }
+
+    /// Render the lhs of a call, index or member expression, which must be a prefix expression
+    fn push_prefix(&mut self, v: &Node<&Exp>) {
+        self.push_parenthesised(v, !is_prefix(v));
+    }
+
+    /// Render an operand of an operator with `precedence`, `strict` operands needing parentheses
+    /// even when their precedence is equal, as they would otherwise be re-associated
+    fn push_operand(&mut self, v: &Node<&Exp>, precedence: Precedence, strict: bool) {
+        let parens = match exp_precedence(v) {
+            Some(operand) if strict => operand <= precedence,
+            Some(operand) => operand < precedence,
+            None => false,
+        };
+
+        self.push_parenthesised(v, parens);
+    }
 }
 
 impl Visitor for Renderer {
+    fn visit_stat(&mut self, v: &Node<&Stat>) {
+        if matches!(**v, Stat::Error | Stat::None) {
+            return;
+        }
+
+        self.push_indent();
+
+        let start = self.inner.len();
+
+        walk_stat(self, v);
+
+        // A statement starting with `(` would otherwise be parsed as a call of the end of the
+        // previous statement
+        if self.inner[start..].starts_with('(') {
+            self.inner.insert(start, ';');
+        }
+
+        self.inner.push('\n');
+    }
+
+    fn visit_assignment_stat(&mut self, v: &Node<&Assignment>) {
+        self.push_exps(v.vars);
+        self.inner.push_str(" = ");
+        self.push_exps(v.exps);
+    }
+
+    fn visit_do_stat(&mut self, v: &Node<&Do>) {
+        self.inner.push_str("do");
+        self.push_block(v.body);
+        self.inner.push_str("end");
+    }
+
+    fn visit_for_stat(&mut self, v: &Node<&For>) {
+        let _ = write!(self.inner, "for {} = ", v.init.0);
+
+        self.visit_exp(&v.init.1);
+        self.inner.push_str(", ");
+        self.visit_exp(&v.test);
+
+        if let Some(update) = &v.update {
+            self.inner.push_str(", ");
+            self.visit_exp(update);
+        }
+
+        self.inner.push_str(" do");
+        self.push_block(v.body);
+        self.inner.push_str("end");
+    }
+
+    fn visit_for_in_stat(&mut self, v: &Node<&ForIn>) {
+        self.inner.push_str("for ");
+        self.push_names(v.names);
+        self.inner.push_str(" in ");
+        self.push_exps(v.exps);
+        self.inner.push_str(" do");
+        self.push_block(v.body);
+        self.inner.push_str("end");
+    }
+
+    fn visit_function_def_stat(&mut self, v: &Node<&FunctionDef>) {
+        if v.local {
+            self.inner.push_str("local ");
+        }
+
+        let _ = write!(self.inner, "function {}", v.name);
+
+        self.push_function(&v.body);
+    }
+
+    fn visit_goto_stat(&mut self, v: &Node<&Goto>) {
+        let _ = write!(self.inner, "goto {}", v.label);
+    }
+
+    fn visit_if_else_stat(&mut self, v: &Node<&IfElse>) {
+        self.inner.push_str("if ");
+        self.visit_exp(&v.cond);
+        self.inner.push_str(" then");
+        self.push_block(v.body);
+
+        for (cond, body) in v.else_ifs {
+            self.inner.push_str("elseif ");
+            self.visit_exp(cond);
+            self.inner.push_str(" then");
+            self.push_block(body);
+        }
+
+        if let Some(else_block) = v.else_block {
+            self.inner.push_str("else");
+            self.push_block(else_block);
+        }
+
+        self.inner.push_str("end");
+    }
+
+    fn visit_label_stat(&mut self, v: &Node<&Label>) {
+        let _ = write!(self.inner, "::{}::", v.name);
+    }
+
+    fn visit_repeat_until_stat(&mut self, v: &Node<&RepeatUntil>) {
+        self.inner.push_str("repeat");
+        self.push_block(v.body);
+        self.inner.push_str("until ");
+        self.visit_exp(&v.cond);
+    }
+
+    fn visit_return_stat(&mut self, v: &Node<&Return>) {
+        self.inner.push_str("return");
+
+        if !v.exps.is_empty() {
+            self.inner.push(' ');
+            self.push_exps(v.exps);
+        }
+    }
+
+    fn visit_var_def_stat(&mut self, v: &Node<&VarDef>) {
+        self.inner.push_str("local ");
+        self.push_names(v.names);
+
+        if let Some(init_exps) = v.init_exps {
+            self.inner.push_str(" = ");
+            self.push_exps(init_exps);
+        }
+    }
+
+    fn visit_while_stat(&mut self, v: &Node<&While>) {
+        self.inner.push_str("while ");
+        self.visit_exp(&v.cond);
+        self.inner.push_str(" do");
+        self.push_block(v.body);
+        self.inner.push_str("end");
+    }
+
+    fn visit_break_stat(&mut self) {
+        self.inner.push_str("break");
+    }
+
+    fn visit_continue_stat(&mut self) {
+        self.inner.push_str("continue");
+    }
+
+    fn visit_exp(&mut self, v: &Node<&Exp>) {
+        walk_exp(self, v);
+    }
+
+    fn visit_binary_exp(&mut self, v: &Node<&Binary>) {
+        let precedence = v.op.precedence();
+        let right = v.op.is_right_associative();
+
+        self.push_operand(&v.lhs, precedence, right);
+
+        let _ = write!(self.inner, " {} ", v.op);
+
+        // A unary operator on the rhs binds the rest of the operand without needing parentheses
+        match *v.rhs {
+            Exp::Unary(_) => self.visit_exp(&v.rhs),
+            _ => self.push_operand(&v.rhs, precedence, !right),
+        }
+    }
+
+    fn visit_function_exp(&mut self, v: &Node<&Function>) {
+        self.inner.push_str("function");
+        self.push_function(v);
+    }
+
+    fn visit_index_exp(&mut self, v: &Node<&Index>) {
+        self.push_prefix(&v.lhs);
+        self.inner.push('[');
+        self.visit_exp(&v.exp);
+        self.inner.push(']');
+    }
+
     fn visit_member_exp(&mut self, v: &Node<&Member>) {
-        walk_exp(self, &v.lhs);
+        self.push_prefix(&v.lhs);
+
+        let _ = write!(self.inner, ".{}", v.name);
+    }
 
-        self.inner.push_str(&format!(".{}", v.name));
+    fn visit_paren_exp(&mut self, v: &Node<&Node<&Exp>>) {
+        self.inner.push('(');
+        self.visit_exp(v);
+        self.inner.push(')');
+    }
+
+    fn visit_table_exp(&mut self, v: &Node<&TableConstructor>) {
+        if v.fields.is_empty() {
+            self.inner.push_str("{}");
+
+            return;
+        }
+
+        // Fields containing functions are spread over multiple lines, anything else stays inline
+        let multi_line = v
+            .fields
+            .iter()
+            .any(|field| matches!(*field.value, Exp::Function(_)));
+
+        self.inner.push('{');
+
+        if multi_line {
+            self.indent += 1;
+        }
+
+        for (i, field) in v.fields.iter().enumerate() {
+            match multi_line {
+                true => {
+                    self.inner.push('\n');
+                    self.push_indent();
+                }
+                false => self.inner.push(' '),
+            }
+
+            if let Some(key) = &field.key {
+                match **key {
+                    Exp::String(name) if is_name(name) => {
+                        let _ = write!(self.inner, "{} = ", std::str::from_utf8(name).unwrap());
+                    }
+                    _ => {
+                        self.inner.push('[');
+                        self.visit_exp(key);
+                        self.inner.push_str("] = ");
+                    }
+                }
+            }
+
+            self.visit_exp(&field.value);
+
+            if multi_line || i < v.fields.len() - 1 {
+                self.inner.push(',');
+            }
+        }
+
+        match multi_line {
+            true => {
+                self.indent -= 1;
+
+                self.inner.push('\n');
+                self.push_indent();
+            }
+            false => self.inner.push(' '),
+        }
+
+        self.inner.push('}');
+    }
+
+    fn visit_unary_exp(&mut self, v: &Node<&Unary>) {
+        let _ = write!(self.inner, "{}", v.op);
+
+        // Both `not` and a nested negation, which would otherwise start a comment, need a space
+        let space = match v.op {
+            UnOp::Not => true,
+            UnOp::Neg => is_negative(&v.exp),
+            UnOp::Len => false,
+        };
+
+        if space {
+            self.inner.push(' ');
+        }
+
+        // Unary operators bind tighter than everything bar exponentiation, and nest without
+        // needing parentheses
+        match *v.exp {
+            Exp::Unary(_) => self.visit_exp(&v.exp),
+            _ => self.push_operand(&v.exp, Precedence::Unary, true),
+        }
+    }
+
+    fn visit_bool_exp(&mut self, v: &Node<&bool>) {
+        let _ = write!(self.inner, "{}", **v);
+    }
+
+    fn visit_nil_exp(&mut self, _v: &Node<()>) {
+        self.inner.push_str("nil");
+    }
+
+    fn visit_number_exp(&mut self, v: &Node<&f64>) {
+        self.inner.push_str(&render_number(***v));
     }
 
     fn visit_ref_exp(&mut self, v: &Node<&str>) {
-        self.inner.push_str(**v);
+        self.inner.push_str(v);
+    }
+
+    fn visit_string_exp(&mut self, v: &Node<&[u8]>) {
+        self.inner.push_str(&quote_string(v, '"'));
+    }
+
+    fn visit_var_args_exp(&mut self, _v: &Node<()>) {
+        self.inner.push_str("...");
+    }
+
+    fn visit_function_call(&mut self, v: &Node<&FunctionCall>) {
+        self.push_prefix(&v.lhs);
+        self.push_args(v.args);
+    }
+
+    fn visit_method_call(&mut self, v: &Node<&MethodCall>) {
+        self.push_prefix(&v.lhs);
+
+        let _ = write!(self.inner, ":{}", v.name);
+
+        self.push_args(v.args);
+    }
+}
+
+/// Render `value` so that it parses back to the same number
+pub(crate) fn render_number(value: f64) -> String {
+    if value.is_nan() {
+        "(0 / 0)".to_owned()
+    } else if value.is_infinite() {
+        // Overflows to infinity when parsed
+        (if value > 0.0 { "1e999" } else { "-1e999" }).to_owned()
+    } else if value.fract() == 0.0 && value.abs() < 1e16 {
+        format!("{}", value)
+    } else {
+        // `Debug` switches to exponent notation for very large or small values
+        format!("{:?}", value)
+    }
+}
+
+/// Quote and escape `bytes` as a string literal delimited by `quote`
+pub(crate) fn quote_string(bytes: &[u8], quote: char) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+
+    out.push(quote);
+
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if c == quote => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                // Always use three digits, so that a following digit can't be mistaken as part of
+                // the escape
+                c if c.is_ascii_control() => {
+                    let _ = write!(out, "\\{:03}", c as u8);
+                }
+                c => out.push(c),
+            }
+        }
+
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\{:03}", byte);
+        }
+    }
+
+    out.push(quote);
+
+    out
+}
+
+/// Whether `exp` is a prefix expression, which can be called or indexed without parentheses
+pub(crate) fn is_prefix(exp: &Exp) -> bool {
+    matches!(
+        exp,
+        Exp::Ref(_)
+            | Exp::Index(_)
+            | Exp::Member(_)
+            | Exp::FunctionCall(_)
+            | Exp::MethodCall(_)
+            | Exp::Paren(_)
+    )
+}
+
+/// The precedence of `exp` as an operand, `None` if it never needs parenthesising
+pub(crate) fn exp_precedence(exp: &Exp) -> Option<Precedence> {
+    match exp {
+        Exp::Binary(binary) => Some(binary.op.precedence()),
+        Exp::Unary(_) => Some(Precedence::Unary),
+        Exp::Number(value) if value.is_sign_negative() => Some(Precedence::Unary),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_negative(exp: &Exp) -> bool {
+    match exp {
+        Exp::Unary(unary) => unary.op == UnOp::Neg,
+        Exp::Number(value) => value.is_sign_negative(),
+        _ => false,
+    }
+}
+
+fn is_empty(block: Block) -> bool {
+    block
+        .iter()
+        .all(|s| matches!(**s, Stat::Error | Stat::None))
+}
+
+/// Words that lex as something other than a name
+const RESERVED: &[&[u8]] = &[
+    b"and",
+    b"break",
+    b"continue",
+    b"do",
+    b"else",
+    b"elseif",
+    b"end",
+    b"false",
+    b"for",
+    b"function",
+    b"goto",
+    b"if",
+    b"in",
+    b"local",
+    b"nil",
+    b"not",
+    b"or",
+    b"repeat",
+    b"return",
+    b"then",
+    b"true",
+    b"until",
+    b"while",
+];
+
+/// Whether `bytes` is a name, and so can be used as a table key without brackets
+pub(crate) fn is_name(bytes: &[u8]) -> bool {
+    match bytes {
+        [first, rest @ ..] => {
+            (first.is_ascii_alphabetic() || *first == b'_')
+                && rest.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+                && !RESERVED.contains(&bytes)
+        }
+        [] => false,
     }
 }
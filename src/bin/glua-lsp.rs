@@ -0,0 +1,19 @@
+use std::{io, process::ExitCode};
+
+use glua::lsp::Server;
+
+fn main() -> ExitCode {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    match Server::new().run(stdin.lock(), stdout.lock()) {
+        // Exiting without a shutdown request is an error
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {}", err);
+
+            ExitCode::FAILURE
+        }
+    }
+}
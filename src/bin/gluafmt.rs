@@ -0,0 +1,190 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    process::ExitCode,
+};
+
+use bumpalo::Bump;
+use glua::{
+    diagnostic::Diagnostic,
+    formatter::{self, Config, OperatorStyle, QuoteStyle, TrailingSeparator},
+    SourceMap,
+};
+
+const USAGE: &str = "\
+Usage: gluafmt [OPTIONS] [FILES]...
+
+Formats GLua files, or stdin to stdout if no files are given.
+
+Options:
+    --check                       Don't write anything, fail if any input isn't formatted
+    -w, --write                   Overwrite files with their formatted source
+    --indent-width <N>            Spaces per level of indentation [default: 4]
+    --tabs                        Indent with tabs
+    --max-width <N>               Maximum line width [default: 100]
+    --quote-style <STYLE>         prefer-double, prefer-single, double or single
+    --operators <STYLE>           lua or c, also used for comments [default: lua]
+    --no-operator-spacing         Don't put spaces around arithmetic and comparison operators
+    --trailing-separator <WHEN>   always, never or multiline [default: multiline]
+    -h, --help                    Print this message";
+
+struct Options {
+    config: Config,
+    check: bool,
+    write: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut success = true;
+
+    if options.files.is_empty() {
+        let mut source = String::new();
+
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("error: failed to read stdin: {}", err);
+
+            return ExitCode::FAILURE;
+        }
+
+        success = run(&options, "<stdin>", &source);
+    }
+
+    for path in &options.files {
+        match fs::read_to_string(path) {
+            Ok(source) => success &= run(&options, path, &source),
+            Err(err) => {
+                eprintln!("error: failed to read {}: {}", path, err);
+
+                success = false;
+            }
+        }
+    }
+
+    match success {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Format one input, returning whether it succeeded
+fn run(options: &Options, name: &str, source: &str) -> bool {
+    let bump = Bump::new();
+
+    let formatted = match formatter::format(source, &bump, &options.config) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            let source_map = SourceMap::new(source).with_name(name);
+
+            let diagnostic = Diagnostic::from_error(&err, &source_map);
+
+            eprint!(
+                "{}",
+                diagnostic.render(&source_map, io::stderr().is_terminal())
+            );
+
+            return false;
+        }
+    };
+
+    if options.check {
+        if formatted != source {
+            eprintln!("{} is not formatted", name);
+
+            return false;
+        }
+
+        return true;
+    }
+
+    if options.write && name != "<stdin>" {
+        if formatted != source {
+            if let Err(err) = fs::write(name, formatted) {
+                eprintln!("error: failed to write {}: {}", name, err);
+
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    io::stdout().write_all(formatted.as_bytes()).is_ok()
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        config: Config::default(),
+        check: false,
+        write: false,
+        files: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "--check" => options.check = true,
+            "-w" | "--write" => options.write = true,
+            "--tabs" => options.config.use_tabs = true,
+            "--no-operator-spacing" => options.config.operator_spacing = false,
+            "--indent-width" => options.config.indent_width = number(&arg, value(&arg)?)?,
+            "--max-width" => options.config.max_width = number(&arg, value(&arg)?)?,
+            "--quote-style" => {
+                options.config.quote_style = match value(&arg)?.as_str() {
+                    "prefer-double" => QuoteStyle::PreferDouble,
+                    "prefer-single" => QuoteStyle::PreferSingle,
+                    "double" => QuoteStyle::AlwaysDouble,
+                    "single" => QuoteStyle::AlwaysSingle,
+                    other => return Err(format!("unknown quote style `{}`", other)),
+                }
+            }
+            "--operators" => {
+                options.config.operator_style = match value(&arg)?.as_str() {
+                    "lua" => OperatorStyle::Lua,
+                    "c" => OperatorStyle::C,
+                    other => return Err(format!("unknown operator style `{}`", other)),
+                }
+            }
+            "--trailing-separator" => {
+                options.config.trailing_separator = match value(&arg)?.as_str() {
+                    "always" => TrailingSeparator::Always,
+                    "never" => TrailingSeparator::Never,
+                    "multiline" => TrailingSeparator::MultiLine,
+                    other => return Err(format!("unknown trailing separator `{}`", other)),
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+
+    if options.check && options.write {
+        return Err("--check and --write can't be used together".to_owned());
+    }
+
+    Ok(options)
+}
+
+fn number(name: &str, value: String) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for {}", value, name))
+}
@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+    path::Path,
+    process::ExitCode,
+};
+
+use bumpalo::Bump;
+use glua::{
+    analysis::{hooks::Hooks, net::NetUsage, realm::Realms, scope::ScopeTree},
+    api::Realm,
+    diagnostic::Diagnostic,
+    lint::{self, stdlib, Config},
+    project::{self, File, Project},
+};
+
+const USAGE: &str = "\
+Usage: glualint [OPTIONS] [FILES]...
+
+Lints GLua files and every .lua file in the given directories, or stdin if no files are given,
+failing if there are any warnings.
+
+Options:
+    --std <STD>                 Globals provided by the environment: gmod, lua or none
+                                [default: gmod]
+    --globals <NAMES>           Comma separated globals that can be read and set, e.g. MYADDON_*
+    --read-globals <NAMES>      Comma separated globals that can only be read
+    --realm <REALM>             client, server or shared, instead of inferring it from file paths
+    --net                       Check the net messages sent and received across all the files
+    --hooks                     Check the hooks added, run and defined across all the files
+    --includes                  Check the files included and sent with AddCSLuaFile exist, aren't
+                                included in a cycle, and are sent if they're included on the client
+    -h, --help                  Print this message";
+
+struct Options {
+    config: Config,
+    realm: Option<Realm>,
+    net: bool,
+    hooks: bool,
+    includes: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut success = true;
+    let mut inputs = Vec::new();
+
+    if options.files.is_empty() {
+        let mut source = String::new();
+
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("error: failed to read stdin: {}", err);
+
+            return ExitCode::FAILURE;
+        }
+
+        inputs.push(("<stdin>".to_owned(), source));
+    }
+
+    for path in &options.files {
+        let path = Path::new(path);
+
+        // Directories are linted as a whole project
+        let paths = match path.is_dir() {
+            true => match project::lua_files(path) {
+                Ok(paths) => paths,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+
+                    success = false;
+
+                    continue;
+                }
+            },
+            false => vec![path.to_owned()],
+        };
+
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(source) => inputs.push((path.to_string_lossy().into_owned(), source)),
+                Err(err) => {
+                    eprintln!("error: failed to read {}: {}", path.display(), err);
+
+                    success = false;
+                }
+            }
+        }
+    }
+
+    let bump = Bump::new();
+
+    let project = Project::from_sources(
+        inputs
+            .iter()
+            .map(|(name, source)| (name.clone(), source.as_str())),
+        &bump,
+    );
+
+    let files = project.files();
+
+    let mut linted = files
+        .iter()
+        .map(|file| run(&options, file))
+        .collect::<Vec<_>>();
+
+    if options.net {
+        let usages = files
+            .iter()
+            .zip(&linted)
+            .filter_map(|(file, linted)| Some((&file.source_map, linted.net.as_ref()?)))
+            .collect::<Vec<_>>();
+
+        let net = lint::net_messages(&usages);
+
+        let parsed = linted.iter_mut().filter(|file| file.net.is_some());
+
+        for (file, diagnostics) in parsed.zip(net) {
+            file.diagnostics.extend(diagnostics);
+        }
+    }
+
+    if options.hooks {
+        let hooks = files
+            .iter()
+            .zip(&linted)
+            .filter_map(|(file, linted)| Some((&file.source_map, linted.hooks.as_ref()?)))
+            .collect::<Vec<_>>();
+
+        let hooks = lint::hooks(&hooks);
+
+        let parsed = linted.iter_mut().filter(|file| file.hooks.is_some());
+
+        for (file, diagnostics) in parsed.zip(hooks) {
+            file.diagnostics.extend(diagnostics);
+        }
+    }
+
+    if options.includes {
+        for (file, diagnostics) in linted.iter_mut().zip(lint::project(&project)) {
+            file.diagnostics.extend(diagnostics);
+        }
+    }
+
+    let colour = io::stdout().is_terminal();
+
+    for (file, linted) in files.iter().zip(&mut linted) {
+        linted
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span().map(|span| span.start));
+
+        for diagnostic in &linted.diagnostics {
+            print!("{}", diagnostic.render(&file.source_map, colour));
+        }
+
+        success &= linted.diagnostics.is_empty();
+    }
+
+    match success {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// A file's diagnostics, along with its net messages and hooks if it parsed
+struct Linted<'a> {
+    diagnostics: Vec<Diagnostic>,
+    net: Option<NetUsage<'a>>,
+    hooks: Option<Hooks<'a>>,
+}
+
+/// Lint one file, finding its net messages and hooks too if they're to be checked
+fn run<'a>(options: &Options, file: &File<'a>) -> Linted<'a> {
+    let Some(chunk) = file.chunk else {
+        return Linted {
+            diagnostics: file.error.iter().cloned().collect(),
+            net: None,
+            hooks: None,
+        };
+    };
+
+    let realm = options.realm.or(file.realm);
+
+    let tree = ScopeTree::build(chunk);
+    let realms = Realms::infer(chunk, realm);
+
+    Linted {
+        diagnostics: lint::lint(chunk, realm, &options.config),
+        net: options
+            .net
+            .then(|| NetUsage::collect(chunk, &tree, &realms)),
+        hooks: options.hooks.then(|| Hooks::collect(chunk, &tree, &realms)),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut std = "gmod".to_owned();
+    let mut globals = Vec::new();
+    let mut read_globals = Vec::new();
+    let mut realm = None;
+    let mut net = false;
+    let mut hooks = false;
+    let mut includes = false;
+    let mut files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "--std" => std = value(&arg)?,
+            "--globals" => globals.push(value(&arg)?),
+            "--read-globals" => read_globals.push(value(&arg)?),
+            "--net" => net = true,
+            "--hooks" => hooks = true,
+            "--includes" => includes = true,
+            "--realm" => {
+                realm = Some(match value(&arg)?.as_str() {
+                    "client" => Realm::Client,
+                    "server" => Realm::Server,
+                    "shared" => Realm::Shared,
+                    other => return Err(format!("unknown realm `{}`", other)),
+                })
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => files.push(arg),
+        }
+    }
+
+    let config = match std.as_str() {
+        "gmod" => Config::default(),
+        "lua" => Config::empty().with_read_globals(stdlib::LUA.iter().copied()),
+        "none" => Config::empty(),
+        other => return Err(format!("unknown std `{}`", other)),
+    };
+
+    let names = |lists: &[String]| {
+        lists
+            .iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    };
+
+    Ok(Options {
+        config: config
+            .with_globals(names(&globals))
+            .with_read_globals(names(&read_globals)),
+        realm,
+        net,
+        hooks,
+        includes,
+        files,
+    })
+}
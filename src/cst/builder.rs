@@ -0,0 +1,357 @@
+use bumpalo::Bump;
+use logos::Span;
+
+use crate::{
+    ast::{
+        exps::Function, node::Node, stats::FunctionName, trivia::Comment, Block, Exp, Name, Stat,
+    },
+    cst::{GreenElement, GreenNode, GreenToken, NodeKind},
+    lexer::Token,
+    parser::SpannedToken,
+};
+
+/// Build a green tree from the tokens and comments of `source` and the chunk parsed from them.
+///
+/// Nodes are formed from the spans of the chunk's nodes, tokens that are between the children of a
+/// node, e.g. keywords, separators and trivia, belong to that node.
+pub(super) fn build<'a>(
+    source: &'a str,
+    tokens: &[SpannedToken<'a>],
+    comments: &[Comment<'a>],
+    chunk: Block<'a>,
+    bump: &'a Bump,
+) -> &'a GreenNode<'a> {
+    let mut builder = Builder {
+        bump,
+        tokens: merge(source, tokens, comments, bump),
+        pos: 0,
+        stack: Vec::new(),
+    };
+
+    builder.start(NodeKind::Chunk, 0);
+    builder.block(chunk);
+    builder.finish(source.len());
+
+    match builder.stack.pop().unwrap().1[..] {
+        [GreenElement::Node(root)] => root,
+        _ => unreachable!(),
+    }
+}
+
+/// Interleave `tokens` and `comments`, filling the gaps between them with whitespace
+fn merge<'a>(
+    source: &'a str,
+    tokens: &[SpannedToken<'a>],
+    comments: &[Comment<'a>],
+    bump: &'a Bump,
+) -> Vec<(Span, &'a GreenToken<'a>)> {
+    let mut merged = Vec::with_capacity((tokens.len() + comments.len()) * 2);
+
+    let mut tokens = tokens.iter().peekable();
+    let mut comments = comments.iter().peekable();
+
+    let mut end = 0;
+
+    loop {
+        let token_first = match (tokens.peek(), comments.peek()) {
+            (Some((_, span)), Some(comment)) => span.start < comment.span().start,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+
+        let (span, token) = if token_first {
+            let (token, span) = tokens.next().unwrap();
+
+            (span.clone(), *token)
+        } else {
+            let comment = comments.next().unwrap();
+
+            (comment.span(), Token::Comment(comment.text))
+        };
+
+        if end < span.start {
+            let whitespace = GreenToken::new(&source[end..span.start], None);
+
+            merged.push((end..span.start, &*bump.alloc(whitespace)));
+        }
+
+        end = span.end;
+
+        let token = GreenToken::new(&source[span.clone()], Some(token));
+
+        merged.push((span, &*bump.alloc(token)));
+    }
+
+    if end < source.len() {
+        let whitespace = GreenToken::new(&source[end..], None);
+
+        merged.push((end..source.len(), &*bump.alloc(whitespace)));
+    }
+
+    merged
+}
+
+struct Builder<'a> {
+    bump: &'a Bump,
+    tokens: Vec<(Span, &'a GreenToken<'a>)>,
+    pos: usize,
+    /// The kinds and children of the nodes being built
+    stack: Vec<(NodeKind, Vec<GreenElement<'a>>)>,
+}
+
+impl<'a> Builder<'a> {
+    /// Start a node at `start`, tokens before it belonging to the enclosing node
+    fn start(&mut self, kind: NodeKind, start: usize) {
+        self.take_tokens(|span| span.start < start);
+
+        self.stack.push((kind, Vec::new()));
+    }
+
+    /// Finish the current node at `end`
+    fn finish(&mut self, end: usize) {
+        self.take_tokens(|span| span.end <= end);
+
+        let (kind, children) = self.stack.pop().unwrap();
+
+        let node = GreenNode::new(kind, self.bump.alloc_slice_copy(&children));
+
+        let node = GreenElement::Node(self.bump.alloc(node));
+
+        match self.stack.last_mut() {
+            Some((_, parent)) => parent.push(node),
+            // Keep the root around to be returned by `build`
+            None => self.stack.push((kind, vec![node])),
+        }
+    }
+
+    fn node(&mut self, kind: NodeKind, span: Span, f: impl FnOnce(&mut Self)) {
+        self.start(kind, span.start);
+
+        f(self);
+
+        self.finish(span.end);
+    }
+
+    fn take_tokens(&mut self, predicate: impl Fn(&Span) -> bool) {
+        let Some((_, children)) = self.stack.last_mut() else {
+            return;
+        };
+
+        while let Some((span, token)) = self.tokens.get(self.pos) {
+            if !predicate(span) {
+                break;
+            }
+
+            children.push(GreenElement::Token(token));
+
+            self.pos += 1;
+        }
+    }
+
+    /// Start of the first token after the one starting at `start`, skipping trivia
+    fn next_token_start(&self, start: usize) -> usize {
+        self.tokens[self.pos..]
+            .iter()
+            .find(|(span, token)| span.start > start && !token.is_trivia())
+            .map_or(start, |(span, _)| span.start)
+    }
+
+    fn block(&mut self, block: Block<'a>) {
+        block.iter().for_each(|s| self.stat(s));
+    }
+
+    fn exps(&mut self, exps: &[Node<'a, &'a Exp<'a>>]) {
+        exps.iter().for_each(|e| self.exp(e));
+    }
+
+    fn name(&mut self, name: &Name<'a>) {
+        self.node(NodeKind::Name, name.span(), |_| {});
+    }
+
+    fn names(&mut self, names: &[Name<'a>]) {
+        names.iter().for_each(|name| self.name(name));
+    }
+
+    fn function_name(&mut self, name: &FunctionName<'a>) {
+        self.node(NodeKind::FunctionName, name.span(), |b| {
+            b.name(&name.base);
+            b.names(name.fields);
+
+            if let Some(method) = &name.method {
+                b.name(method);
+            }
+        });
+    }
+
+    fn function_body(&mut self, span: Span, function: &Function<'a>) {
+        self.node(NodeKind::FunctionBody, span, |b| {
+            b.names(function.params);
+            b.block(function.body);
+        });
+    }
+
+    fn stat(&mut self, v: &Node<'a, &'a Stat<'a>>) {
+        let kind = match **v {
+            Stat::Assignment(_) => NodeKind::AssignmentStat,
+            Stat::Break => NodeKind::BreakStat,
+            Stat::Continue => NodeKind::ContinueStat,
+            Stat::Do(_) => NodeKind::DoStat,
+            Stat::Error => NodeKind::ErrorStat,
+            Stat::For(_) => NodeKind::ForStat,
+            Stat::ForIn(_) => NodeKind::ForInStat,
+            Stat::FunctionCall(_) => NodeKind::FunctionCallStat,
+            Stat::FunctionDef(_) => NodeKind::FunctionDefStat,
+            Stat::Goto(_) => NodeKind::GotoStat,
+            Stat::IfElse(_) => NodeKind::IfElseStat,
+            Stat::Label(_) => NodeKind::LabelStat,
+            Stat::MethodCall(_) => NodeKind::MethodCallStat,
+            Stat::RepeatUntil(_) => NodeKind::RepeatUntilStat,
+            Stat::Return(_) => NodeKind::ReturnStat,
+            Stat::VarDef(_) => NodeKind::VarDefStat,
+            Stat::While(_) => NodeKind::WhileStat,
+            // Only holds comments, which are tokens of the enclosing node
+            Stat::None => return,
+        };
+
+        let stat: &'a Stat<'a> = **v;
+
+        self.node(kind, v.span(), |b| match stat {
+            Stat::Assignment(s) => {
+                b.exps(s.vars);
+                b.exps(s.exps);
+            }
+            Stat::Do(s) => b.block(s.body),
+            Stat::For(s) => {
+                b.name(&s.init.0);
+                b.exp(&s.init.1);
+                b.exp(&s.test);
+
+                if let Some(update) = &s.update {
+                    b.exp(update);
+                }
+
+                b.block(s.body);
+            }
+            Stat::ForIn(s) => {
+                b.names(s.names);
+                b.exps(s.exps);
+                b.block(s.body);
+            }
+            Stat::FunctionCall(s) => {
+                b.exp(&s.lhs);
+                b.exps(s.args);
+            }
+            Stat::FunctionDef(s) => {
+                b.function_name(&s.name);
+                b.function_body(s.body.span(), &s.body);
+            }
+            Stat::Goto(s) => b.name(&s.label),
+            Stat::IfElse(s) => {
+                b.exp(&s.cond);
+                b.block(s.body);
+
+                for (cond, block) in s.else_ifs {
+                    b.exp(cond);
+                    b.block(block);
+                }
+
+                if let Some(else_block) = s.else_block {
+                    b.block(else_block);
+                }
+            }
+            Stat::MethodCall(s) => {
+                b.exp(&s.lhs);
+                b.name(&s.name);
+                b.exps(s.args);
+            }
+            Stat::RepeatUntil(s) => {
+                b.block(s.body);
+                b.exp(&s.cond);
+            }
+            Stat::Return(s) => b.exps(s.exps),
+            Stat::VarDef(s) => {
+                b.names(s.names);
+
+                if let Some(init_exps) = s.init_exps {
+                    b.exps(init_exps);
+                }
+            }
+            Stat::While(s) => {
+                b.exp(&s.cond);
+                b.block(s.body);
+            }
+            // The name of a label is part of its `::name::` token
+            Stat::Break | Stat::Continue | Stat::Error | Stat::Label(_) | Stat::None => {}
+        });
+    }
+
+    fn exp(&mut self, v: &Node<'a, &'a Exp<'a>>) {
+        let kind = match **v {
+            Exp::Binary(_) => NodeKind::BinaryExp,
+            Exp::Bool(_) => NodeKind::BoolExp,
+            Exp::Function(_) => NodeKind::FunctionExp,
+            Exp::FunctionCall(_) => NodeKind::FunctionCallExp,
+            Exp::Index(_) => NodeKind::IndexExp,
+            Exp::Member(_) => NodeKind::MemberExp,
+            Exp::MethodCall(_) => NodeKind::MethodCallExp,
+            Exp::Nil => NodeKind::NilExp,
+            Exp::Number(_) => NodeKind::NumberExp,
+            Exp::Paren(_) => NodeKind::ParenExp,
+            Exp::Ref(_) => NodeKind::RefExp,
+            Exp::String(_) => NodeKind::StringExp,
+            Exp::Table(_) => NodeKind::TableExp,
+            Exp::Unary(_) => NodeKind::UnaryExp,
+            Exp::VarArgs => NodeKind::VarArgsExp,
+        };
+
+        let exp: &'a Exp<'a> = **v;
+
+        self.node(kind, v.span(), |b| match exp {
+            Exp::Binary(e) => {
+                b.exp(&e.lhs);
+                b.exp(&e.rhs);
+            }
+            Exp::Function(e) => {
+                // The body starts after the `function` keyword
+                let start = b.next_token_start(v.span().start);
+
+                b.function_body(start..v.span().end, e);
+            }
+            Exp::FunctionCall(e) => {
+                b.exp(&e.lhs);
+                b.exps(e.args);
+            }
+            Exp::Index(e) => {
+                b.exp(&e.lhs);
+                b.exp(&e.exp);
+            }
+            Exp::Member(e) => {
+                b.exp(&e.lhs);
+                b.name(&e.name);
+            }
+            Exp::MethodCall(e) => {
+                b.exp(&e.lhs);
+                b.name(&e.name);
+                b.exps(e.args);
+            }
+            Exp::Paren(e) => b.exp(e),
+            Exp::Table(e) => {
+                for field in e.fields {
+                    if let Some(key) = &field.key {
+                        b.exp(key);
+                    }
+
+                    b.exp(&field.value);
+                }
+            }
+            Exp::Unary(e) => b.exp(&e.exp),
+            Exp::Bool(_)
+            | Exp::Nil
+            | Exp::Number(_)
+            | Exp::Ref(_)
+            | Exp::String(_)
+            | Exp::VarArgs => {}
+        });
+    }
+}
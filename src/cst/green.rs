@@ -0,0 +1,83 @@
+use crate::{cst::NodeKind, lexer::Token};
+
+/// An immutable, position independent node of the syntax tree, which can be shared between trees
+#[derive(Debug)]
+pub struct GreenNode<'a> {
+    kind: NodeKind,
+    len: usize,
+    children: &'a [GreenElement<'a>],
+}
+
+/// A token of the syntax tree, including whitespace and comments
+#[derive(Debug)]
+pub struct GreenToken<'a> {
+    text: &'a str,
+    /// `None` for whitespace
+    token: Option<Token<'a>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GreenElement<'a> {
+    Node(&'a GreenNode<'a>),
+    Token(&'a GreenToken<'a>),
+}
+
+impl<'a> GreenNode<'a> {
+    pub fn new(kind: NodeKind, children: &'a [GreenElement<'a>]) -> Self {
+        Self {
+            kind,
+            len: children.iter().map(GreenElement::len).sum(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// Length of the node's text in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &'a [GreenElement<'a>] {
+        self.children
+    }
+}
+
+impl<'a> GreenToken<'a> {
+    pub fn new(text: &'a str, token: Option<Token<'a>>) -> Self {
+        Self { text, token }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The lexed token, `None` for whitespace
+    pub fn token(&self) -> Option<Token<'a>> {
+        self.token
+    }
+
+    /// Whether the token is whitespace or a comment
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.token, None | Some(Token::Comment(_)))
+    }
+}
+
+impl GreenElement<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,79 @@
+//! Lossless concrete syntax tree, keeping every token of the source including whitespace and
+//! comments.
+//!
+//! The tree is split into position independent [`GreenNode`]s, which can be shared, and
+//! [`SyntaxNode`]s which add positions and parents on top of them as the tree is navigated.
+
+use bumpalo::Bump;
+
+pub use self::{
+    green::{GreenElement, GreenNode, GreenToken},
+    red::{SyntaxElement, SyntaxNode, SyntaxToken},
+};
+use crate::parser::{Parser, Result};
+
+mod builder;
+mod green;
+mod red;
+
+/// The kind of a [`GreenNode`], mirroring the variants of [`Stat`](crate::ast::Stat) and
+/// [`Exp`](crate::ast::Exp)
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NodeKind {
+    Chunk,
+
+    // Statements
+    AssignmentStat,
+    BreakStat,
+    ContinueStat,
+    DoStat,
+    ErrorStat,
+    ForStat,
+    ForInStat,
+    FunctionCallStat,
+    FunctionDefStat,
+    GotoStat,
+    IfElseStat,
+    LabelStat,
+    MethodCallStat,
+    RepeatUntilStat,
+    ReturnStat,
+    VarDefStat,
+    WhileStat,
+
+    // Expressions
+    BinaryExp,
+    BoolExp,
+    FunctionExp,
+    FunctionCallExp,
+    IndexExp,
+    MemberExp,
+    MethodCallExp,
+    NilExp,
+    NumberExp,
+    ParenExp,
+    RefExp,
+    StringExp,
+    TableExp,
+    UnaryExp,
+    VarArgsExp,
+
+    // Common
+    /// An identifier, e.g. a parameter, local or field name
+    Name,
+    /// The name of a function definition, e.g. `a.b:c`
+    FunctionName,
+    /// The parameters and body of a function, from `(` to `end`
+    FunctionBody,
+}
+
+/// Parse `source` into a syntax tree which prints back to exactly `source`
+pub fn parse<'a>(source: &'a str, bump: &'a Bump) -> Result<'a, SyntaxNode<'a>> {
+    let (tokens, comments) = Parser::lex_with_comments(source, bump)?;
+
+    let chunk = Parser::new_in(bump.alloc_slice_clone(&tokens), bump).parse_chunk()?;
+
+    let green = builder::build(source, &tokens, &comments, chunk, bump);
+
+    Ok(SyntaxNode::new_root(green))
+}
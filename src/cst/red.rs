@@ -0,0 +1,222 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    rc::Rc,
+};
+
+use bumpalo::Bump;
+use logos::Span;
+
+use crate::{
+    ast::{trivia::Comment, Block},
+    cst::{
+        green::{GreenElement, GreenNode, GreenToken},
+        NodeKind,
+    },
+    lexer::Token,
+    parser::{comment_kind, Parser, Result, SpannedToken},
+};
+
+/// A node of the syntax tree positioned in its source, with access to its parent
+#[derive(Clone, Debug)]
+pub struct SyntaxNode<'a>(Rc<NodeData<'a>>);
+
+#[derive(Debug)]
+struct NodeData<'a> {
+    green: &'a GreenNode<'a>,
+    offset: usize,
+    parent: Option<SyntaxNode<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SyntaxToken<'a> {
+    green: &'a GreenToken<'a>,
+    offset: usize,
+    parent: SyntaxNode<'a>,
+}
+
+#[derive(Clone, Debug)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(SyntaxToken<'a>),
+}
+
+impl<'a> SyntaxNode<'a> {
+    pub fn new_root(green: &'a GreenNode<'a>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            offset: 0,
+            parent: None,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &'a GreenNode<'a> {
+        self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        self.0.offset..self.0.offset + self.0.green.len()
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode<'a>> {
+        self.0.parent.as_ref()
+    }
+
+    /// Enclosing nodes, innermost first
+    pub fn ancestors(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        std::iter::successors(self.parent(), |node| node.parent())
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxElement<'a>> + '_ {
+        let mut offset = self.0.offset;
+
+        self.0.green.children().iter().map(move |child| {
+            let element = match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green,
+                    offset,
+                    parent: Some(self.clone()),
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green,
+                    offset,
+                    parent: self.clone(),
+                }),
+            };
+
+            offset += child.len();
+
+            element
+        })
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = SyntaxNode<'a>> + '_ {
+        self.children().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Every token within the node, in source order
+    pub fn tokens(&self) -> Vec<SyntaxToken<'a>> {
+        let mut tokens = Vec::new();
+
+        self.children().for_each(|child| match child {
+            SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+            SyntaxElement::Token(token) => tokens.push(token),
+        });
+
+        tokens
+    }
+
+    /// The innermost node covering `span`
+    pub fn covering_node(&self, span: Span) -> SyntaxNode<'a> {
+        self.child_nodes()
+            .find(|node| node.span().start <= span.start && span.end <= node.span().end)
+            .map_or_else(|| self.clone(), |node| node.covering_node(span))
+    }
+
+    /// The token containing `offset`
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken<'a>> {
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) if node.span().contains(&offset) => {
+                    return node.token_at(offset)
+                }
+                SyntaxElement::Token(token) if token.span().contains(&offset) => {
+                    return Some(token)
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Parse the node's tokens as a chunk, keeping its comments as trivia
+    pub fn to_ast(&self, bump: &'a Bump) -> Result<'a, Block<'a>> {
+        let mut tokens: Vec<SpannedToken> = Vec::new();
+        let mut comments = Vec::new();
+
+        let mut own_line = true;
+
+        for token in self.tokens() {
+            match token.token() {
+                None => own_line |= token.text().contains('\n'),
+                Some(Token::Comment(text)) => {
+                    let kind = comment_kind(token.text());
+
+                    comments.push(Comment::new(kind, text, own_line, token.span()));
+
+                    own_line = false;
+                }
+                Some(value) => {
+                    tokens.push((value, token.span()));
+
+                    own_line = false;
+                }
+            }
+        }
+
+        let tokens = bump.alloc_slice_clone(&tokens);
+        let comments = bump.alloc_slice_copy(&comments);
+
+        Parser::new_in(tokens, bump)
+            .with_comments(comments)
+            .parse_chunk()
+    }
+}
+
+/// Writes the node's source text, exactly as it was parsed
+impl Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fn write(green: &GreenNode, f: &mut Formatter) -> fmt::Result {
+            green.children().iter().try_for_each(|child| match child {
+                GreenElement::Node(node) => write(node, f),
+                GreenElement::Token(token) => f.write_str(token.text()),
+            })
+        }
+
+        write(self.0.green, f)
+    }
+}
+
+impl<'a> SyntaxToken<'a> {
+    pub fn green(&self) -> &'a GreenToken<'a> {
+        self.green
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.green.text()
+    }
+
+    /// The lexed token, `None` for whitespace
+    pub fn token(&self) -> Option<Token<'a>> {
+        self.green.token()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        self.green.is_trivia()
+    }
+
+    pub fn span(&self) -> Span {
+        self.offset..self.offset + self.green.text().len()
+    }
+
+    pub fn parent(&self) -> &SyntaxNode<'a> {
+        &self.parent
+    }
+}
+
+impl SyntaxElement<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter, Write},
+};
+
+use logos::Span;
+
+use crate::{parser::Error, source_map::SourceMap};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+/// A span of source highlighted by a diagnostic
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+    /// Primary labels point at the cause of the diagnostic, secondary labels provide context
+    pub primary: bool,
+}
+
+/// A message about a span of source, renderable with source snippets
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A machine readable identifier for the kind of diagnostic, e.g. `W211`
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<Option<String>>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<Option<String>>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+
+        self
+    }
+
+    /// The span of the first primary label
+    pub fn span(&self) -> Option<&Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .map(|label| &label.span)
+    }
+
+    /// Render the diagnostic with snippets of the labelled source lines, optionally using ANSI
+    /// colours
+    pub fn render(&self, source_map: &SourceMap, colour: bool) -> String {
+        let mut out = String::new();
+
+        // Writing to a `String` cannot fail
+        let _ = self.write(&mut out, source_map, colour);
+
+        out
+    }
+
+    fn write(&self, out: &mut String, source_map: &SourceMap, colour: bool) -> fmt::Result {
+        let style = Style { colour };
+
+        let severity_colour = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => CYAN,
+        };
+
+        let severity = match self.code {
+            Some(code) => format!("{}[{}]", self.severity, code),
+            None => self.severity.to_string(),
+        };
+
+        writeln!(
+            out,
+            "{}{}",
+            style.paint(&severity, severity_colour),
+            style.paint(&format!(": {}", self.message), BOLD)
+        )?;
+
+        let mut labels = self
+            .labels
+            .iter()
+            .map(|label| (source_map.line(label.span.start), label))
+            .collect::<Vec<_>>();
+
+        labels.sort_by_key(|(line, label)| (*line, label.span.start));
+
+        let gutter = labels
+            .last()
+            .map(|(line, _)| (line + 1).to_string().len())
+            .unwrap_or(1);
+
+        let pad = " ".repeat(gutter);
+
+        if let Some(span) = self.span().or(labels.first().map(|(_, label)| &label.span)) {
+            writeln!(
+                out,
+                "{}{} {}",
+                pad,
+                style.paint("-->", BLUE),
+                source_map.display_span(span)
+            )?;
+        }
+
+        if labels.is_empty() {
+            return self.write_notes(out, &pad, style);
+        }
+
+        writeln!(out, "{} {}", pad, style.paint("|", BLUE))?;
+
+        let mut previous_line = None;
+
+        for (i, (line, label)) in labels.iter().enumerate() {
+            if previous_line != Some(*line) {
+                if matches!(previous_line, Some(previous) if line - previous > 1) {
+                    writeln!(out, "{}", style.paint("...", BLUE))?;
+                }
+
+                writeln!(
+                    out,
+                    "{:>width$} {} {}",
+                    style.paint(&(line + 1).to_string(), BLUE),
+                    style.paint("|", BLUE),
+                    expand_tabs(source_map.line_text(*line)),
+                    width = gutter + style.padding(BLUE),
+                )?;
+            }
+
+            previous_line = Some(*line);
+
+            let line_span = source_map.line_span(*line);
+            let text = source_map.line_text(*line);
+
+            // Underline up to the end of the first line for spans over multiple lines
+            let start = label.span.start.clamp(line_span.start, line_span.end) - line_span.start;
+            let end = label.span.end.clamp(line_span.start, line_span.end) - line_span.start;
+
+            let indent = display_width(&text[..floor_char_boundary(text, start)]);
+            let width = display_width(
+                &text[floor_char_boundary(text, start)..floor_char_boundary(text, end)],
+            )
+            .max(1);
+
+            let (marker, marker_colour) = match label.primary {
+                true => ("^", severity_colour),
+                false => ("-", BLUE),
+            };
+
+            let mut underline = marker.repeat(width);
+
+            if let Some(message) = &label.message {
+                underline.push(' ');
+                underline.push_str(message);
+            }
+
+            writeln!(
+                out,
+                "{} {} {}{}",
+                pad,
+                style.paint("|", BLUE),
+                " ".repeat(indent),
+                style.paint(&underline, marker_colour)
+            )?;
+
+            if i == labels.len() - 1 {
+                writeln!(out, "{} {}", pad, style.paint("|", BLUE))?;
+            }
+        }
+
+        self.write_notes(out, &pad, style)
+    }
+
+    fn write_notes(&self, out: &mut String, pad: &str, style: Style) -> fmt::Result {
+        for note in &self.notes {
+            writeln!(out, "{} {} {}", pad, style.paint("=", BLUE), note)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl Diagnostic {
+    /// Convert a parser error into a diagnostic, labelling the offending token and, for
+    /// unclosed constructs, the token that opened them
+    pub fn from_error(err: &Error, source_map: &SourceMap) -> Self {
+        let mut diagnostic = Diagnostic::error(err.to_string());
+
+        let label = match err {
+            Error::Lexer(_) => Some("unrecognised token".to_owned()),
+            _ => err
+                .expected()
+                .map(|expected| format!("expected {}", expected)),
+        };
+
+        // EOF errors have no span, so point just past the last token
+        let end = source_map.source().trim_end().len();
+
+        let span = err.span().cloned().unwrap_or(end..end);
+
+        diagnostic = diagnostic.with_label(span, label);
+
+        if let Some(opener) = err.opener() {
+            let message = match err.expected() {
+                Some(expected) => format!(
+                    "the {} that needs this {} starts here",
+                    opener.token, expected
+                ),
+                None => format!("the {} starts here", opener.token),
+            };
+
+            diagnostic = diagnostic.with_secondary_label(opener.span.clone(), message);
+        }
+
+        diagnostic
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy)]
+struct Style {
+    colour: bool,
+}
+
+impl Style {
+    fn paint(&self, text: &str, colour: &str) -> String {
+        match self.colour {
+            true => format!("{}{}{}", colour, text, RESET),
+            false => text.to_owned(),
+        }
+    }
+
+    /// Extra width taken up by escape codes, so that they can be accounted for when aligning
+    fn padding(&self, colour: &str) -> usize {
+        match self.colour {
+            true => colour.len() + RESET.len(),
+            false => 0,
+        }
+    }
+}
+
+const TAB_WIDTH: usize = 4;
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
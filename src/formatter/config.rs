@@ -0,0 +1,58 @@
+/// Options controlling the output of the formatter
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Columns per level of indentation
+    pub indent_width: usize,
+    /// Indent with tabs rather than `indent_width` spaces
+    pub use_tabs: bool,
+    /// Width that lines are broken to fit within where possible
+    pub max_width: usize,
+    pub quote_style: QuoteStyle,
+    /// Put spaces around arithmetic and comparison operators, e.g. `a + b` rather than `a+b`.
+    /// Logical and concatenation operators are always spaced.
+    pub operator_spacing: bool,
+    pub trailing_separator: TrailingSeparator,
+    pub operator_style: OperatorStyle,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QuoteStyle {
+    /// Use double quotes, unless single quotes would need fewer escapes
+    PreferDouble,
+    /// Use single quotes, unless double quotes would need fewer escapes
+    PreferSingle,
+    AlwaysDouble,
+    AlwaysSingle,
+}
+
+/// When to put a separator after the last field of a table constructor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrailingSeparator {
+    Always,
+    Never,
+    /// Only when the table is spread over multiple lines
+    MultiLine,
+}
+
+/// Whether to use Lua or GMod specific C-style operators and comments
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OperatorStyle {
+    /// `and`, `or`, `not`, `~=`, `--` and `--[[ ]]`
+    Lua,
+    /// `&&`, `||`, `!`, `!=`, `//` and `/* */`
+    C,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            max_width: 100,
+            quote_style: QuoteStyle::PreferDouble,
+            operator_spacing: true,
+            trailing_separator: TrailingSeparator::MultiLine,
+            operator_style: OperatorStyle::Lua,
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::formatter::Config;
+
+/// Layout document built from the syntax tree and printed to fit within the maximum width, in the
+/// style of Wadler's "A prettier printer"
+#[derive(Clone, Debug)]
+pub enum Doc {
+    Nil,
+    Text(String),
+    Concat(Vec<Doc>),
+    /// Content that is printed flat if it fits on the rest of the line, otherwise broken
+    Group {
+        doc: Box<Doc>,
+        breaks: bool,
+    },
+    Indent(Box<Doc>),
+    /// A space when flat, otherwise a newline
+    Line,
+    /// Nothing when flat, otherwise a newline
+    SoftLine,
+    /// Always a newline, breaking every enclosing group
+    HardLine,
+    /// Content chosen by whether the enclosing group is broken
+    IfBreak {
+        broken: Box<Doc>,
+        flat: Box<Doc>,
+    },
+    /// Content deferred to the end of the line, used for line comments. Breaks every enclosing
+    /// group.
+    LineSuffix(String),
+}
+
+impl Doc {
+    pub fn group(doc: Doc) -> Self {
+        let breaks = doc.breaks();
+
+        Doc::Group {
+            doc: Box::new(doc),
+            breaks,
+        }
+    }
+
+    pub fn indent(doc: Doc) -> Self {
+        Doc::Indent(Box::new(doc))
+    }
+
+    pub fn if_break(broken: Doc, flat: Doc) -> Self {
+        Doc::IfBreak {
+            broken: Box::new(broken),
+            flat: Box::new(flat),
+        }
+    }
+
+    /// Whether the document contains anything that forces its enclosing groups to break
+    fn breaks(&self) -> bool {
+        match self {
+            Doc::HardLine | Doc::LineSuffix(_) => true,
+            Doc::Concat(docs) => docs.iter().any(Doc::breaks),
+            Doc::Group { breaks, .. } => *breaks,
+            Doc::Indent(doc) => doc.breaks(),
+            Doc::IfBreak { broken, .. } => broken.breaks(),
+            _ => false,
+        }
+    }
+}
+
+impl From<&str> for Doc {
+    fn from(value: &str) -> Self {
+        Doc::Text(value.to_owned())
+    }
+}
+
+impl From<String> for Doc {
+    fn from(value: String) -> Self {
+        Doc::Text(value)
+    }
+}
+
+impl From<Vec<Doc>> for Doc {
+    fn from(value: Vec<Doc>) -> Self {
+        Doc::Concat(value)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Command<'d> = (usize, Mode, &'d Doc);
+
+pub fn print(doc: &Doc, config: &Config) -> String {
+    let mut printer = Printer {
+        config,
+        out: String::new(),
+        column: 0,
+        suffixes: Vec::new(),
+    };
+
+    let mut stack: Vec<Command> = vec![(0, Mode::Break, doc)];
+
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Nil => {}
+
+            Doc::Text(text) => printer.text(text),
+
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+
+            Doc::Group { doc, breaks } => {
+                let mode = match mode {
+                    Mode::Flat => Mode::Flat,
+                    _ if *breaks => Mode::Break,
+                    _ => {
+                        let width = config.max_width as isize - printer.column as isize;
+
+                        match fits((indent, Mode::Flat, doc), &stack, width) {
+                            true => Mode::Flat,
+                            false => Mode::Break,
+                        }
+                    }
+                };
+
+                stack.push((indent, mode, doc));
+            }
+
+            Doc::Indent(doc) => stack.push((indent + 1, mode, doc)),
+
+            Doc::Line => match mode {
+                Mode::Flat => printer.text(" "),
+                Mode::Break => printer.newline(indent),
+            },
+
+            Doc::SoftLine => {
+                if mode == Mode::Break {
+                    printer.newline(indent)
+                }
+            }
+
+            Doc::HardLine => printer.newline(indent),
+
+            Doc::IfBreak { broken, flat } => stack.push(match mode {
+                Mode::Flat => (indent, mode, flat),
+                Mode::Break => (indent, mode, broken),
+            }),
+
+            Doc::LineSuffix(text) => printer.suffixes.push(text),
+        }
+    }
+
+    printer.flush_suffixes();
+
+    let mut out = printer.out;
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+
+    out
+}
+
+/// Whether `next` fits in `width` columns when printed flat, along with the rest of the line
+fn fits(next: Command, rest: &[Command], mut width: isize) -> bool {
+    let mut rest = rest.iter().rev();
+
+    let mut stack = vec![(next, false)];
+
+    while width >= 0 {
+        let ((indent, mode, doc), in_rest) = match stack.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some(command) => (*command, true),
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Nil | Doc::LineSuffix(_) => {}
+
+            Doc::Text(text) => {
+                if text.contains('\n') {
+                    return in_rest;
+                }
+
+                width -= text.chars().count() as isize;
+            }
+
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| ((indent, mode, doc), in_rest)))
+            }
+
+            Doc::Group { doc, breaks } => {
+                let mode = match mode {
+                    Mode::Break if *breaks => Mode::Break,
+                    _ => Mode::Flat,
+                };
+
+                stack.push(((indent, mode, doc), in_rest));
+            }
+
+            Doc::Indent(doc) => stack.push(((indent + 1, mode, doc), in_rest)),
+
+            Doc::Line | Doc::SoftLine => match mode {
+                Mode::Flat => width -= matches!(doc, Doc::Line) as isize,
+                Mode::Break => return true,
+            },
+
+            Doc::HardLine => return true,
+
+            Doc::IfBreak { broken, flat } => stack.push((
+                match mode {
+                    Mode::Flat => (indent, mode, flat),
+                    Mode::Break => (indent, mode, broken),
+                },
+                in_rest,
+            )),
+        }
+    }
+
+    false
+}
+
+struct Printer<'c, 'd> {
+    config: &'c Config,
+    out: String,
+    column: usize,
+    suffixes: Vec<&'d String>,
+}
+
+impl Printer<'_, '_> {
+    fn text(&mut self, text: &str) {
+        self.out.push_str(text);
+
+        self.column = match text.rfind('\n') {
+            Some(i) => text[i + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+    }
+
+    fn flush_suffixes(&mut self) {
+        for suffix in std::mem::take(&mut self.suffixes) {
+            self.out.push_str(suffix);
+        }
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.flush_suffixes();
+
+        self.out
+            .truncate(self.out.trim_end_matches([' ', '\t']).len());
+        self.out.push('\n');
+
+        match self.config.use_tabs {
+            true => (0..indent).for_each(|_| self.out.push('\t')),
+            false => (0..indent * self.config.indent_width).for_each(|_| self.out.push(' ')),
+        }
+
+        self.column = indent * self.config.indent_width;
+    }
+}
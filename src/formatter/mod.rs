@@ -0,0 +1,850 @@
+use bumpalo::Bump;
+
+pub use self::config::{Config, OperatorStyle, QuoteStyle, TrailingSeparator};
+use self::doc::Doc;
+use crate::{
+    ast::{
+        exps::{binary::BinOp, table::Field, unary::UnOp, Binary, Function, TableConstructor},
+        node::Node,
+        trivia::{Comment, CommentKind},
+        visitors::renderer::{
+            exp_precedence, is_name, is_negative, is_prefix, quote_string, render_number,
+        },
+        Block, Exp, Name, Stat,
+    },
+    parser::{Parser, Precedence, Result},
+};
+
+mod config;
+mod doc;
+
+/// Format GLua `source`, keeping its comments
+pub fn format<'a>(source: &'a str, bump: &'a Bump, config: &Config) -> Result<'a, String> {
+    let (tokens, comments) = Parser::lex_with_comments(source, bump)?;
+
+    let tokens = bump.alloc_slice_clone(&tokens);
+    let comments = bump.alloc_slice_copy(&comments);
+
+    let chunk = Parser::new_in(tokens, bump)
+        .with_comments(comments)
+        .parse_chunk()?;
+
+    Ok(format_chunk(source, chunk, config))
+}
+
+/// Format `chunk`, which must have been parsed from `source`. Comments are only kept if the
+/// chunk was parsed with them.
+pub fn format_chunk(source: &str, chunk: Block, config: &Config) -> String {
+    let formatter = Formatter { source, config };
+
+    doc::print(&formatter.block(chunk).into(), config)
+}
+
+struct Formatter<'s> {
+    source: &'s str,
+    config: &'s Config,
+}
+
+impl Formatter<'_> {
+    // <Statements>
+    /// The statements of `block` separated by newlines, preserving single blank lines between them
+    fn block(&self, block: Block) -> Vec<Doc> {
+        let mut docs = Vec::new();
+
+        let mut previous: Option<&Node<&Stat>> = None;
+
+        for stat in block.iter() {
+            // Nothing is left of a trailing empty statement holding only comments that belong at
+            // the end of the previous line
+            if matches!(**stat, Stat::None) && stat.leading_comments().iter().all(is_hoisted) {
+                docs.push(self.hoist(stat.leading_comments()));
+
+                continue;
+            }
+
+            if let Some(previous) = previous {
+                docs.push(self.hoist(stat.leading_comments()));
+                docs.push(Doc::HardLine);
+
+                let end = previous
+                    .trailing_comments()
+                    .last()
+                    .map_or(previous.span().end, |comment| comment.span().end);
+
+                if self.blank_line(end, self.start(stat)) {
+                    docs.push(Doc::HardLine);
+                }
+            }
+
+            docs.push(self.stat(stat));
+
+            previous = Some(stat);
+        }
+
+        docs
+    }
+
+    /// A block nested in a statement, up to the start of the line that closes it
+    fn body(&self, block: Block) -> Doc {
+        let hoisted = block
+            .first()
+            .map_or(Doc::Nil, |stat| self.hoist(stat.leading_comments()));
+
+        let block = self.block(block);
+
+        match block.is_empty() {
+            true => vec![hoisted, Doc::HardLine].into(),
+            false => vec![
+                hoisted,
+                Doc::indent(vec![Doc::HardLine, block.into()].into()),
+                Doc::HardLine,
+            ]
+            .into(),
+        }
+    }
+
+    fn stat(&self, stat: &Node<&Stat>) -> Doc {
+        let doc = match **stat {
+            Stat::Assignment(assignment) => vec![
+                self.exps(assignment.vars),
+                " = ".into(),
+                self.exps(assignment.exps),
+            ]
+            .into(),
+
+            Stat::Break => "break".into(),
+
+            Stat::Continue => "continue".into(),
+
+            Stat::Do(do_) => vec!["do".into(), self.body(do_.body), "end".into()].into(),
+
+            Stat::Error => self.source[stat.span()].into(),
+
+            Stat::For(for_) => {
+                let mut docs = vec![
+                    format!("for {} = ", for_.init.0).into(),
+                    self.exp(&for_.init.1, false),
+                    ", ".into(),
+                    self.exp(&for_.test, false),
+                ];
+
+                if let Some(update) = &for_.update {
+                    docs.push(", ".into());
+                    docs.push(self.exp(update, false));
+                }
+
+                docs.extend([" do".into(), self.body(for_.body), "end".into()]);
+
+                docs.into()
+            }
+
+            Stat::ForIn(for_in) => vec![
+                format!("for {} in ", join(for_in.names)).into(),
+                self.exps(for_in.exps),
+                " do".into(),
+                self.body(for_in.body),
+                "end".into(),
+            ]
+            .into(),
+
+            Stat::FunctionCall(call) => vec![self.prefix(&call.lhs), self.args(call.args)].into(),
+
+            Stat::FunctionDef(def) => {
+                let local = if def.local { "local " } else { "" };
+
+                vec![
+                    format!("{}function {}", local, def.name).into(),
+                    self.commented(&def.body, false, self.function(&def.body)),
+                ]
+                .into()
+            }
+
+            Stat::Goto(goto) => format!("goto {}", goto.label).into(),
+
+            Stat::IfElse(if_else) => {
+                let mut docs = vec![
+                    "if ".into(),
+                    self.exp(&if_else.cond, false),
+                    " then".into(),
+                    self.body(if_else.body),
+                ];
+
+                for (cond, body) in if_else.else_ifs {
+                    docs.extend([
+                        "elseif ".into(),
+                        self.exp(cond, false),
+                        " then".into(),
+                        self.body(body),
+                    ]);
+                }
+
+                if let Some(else_block) = if_else.else_block {
+                    docs.extend(["else".into(), self.body(else_block)]);
+                }
+
+                docs.push("end".into());
+
+                docs.into()
+            }
+
+            Stat::Label(label) => format!("::{}::", label.name).into(),
+
+            Stat::MethodCall(call) => vec![
+                self.prefix(&call.lhs),
+                format!(":{}", call.name).into(),
+                self.args(call.args),
+            ]
+            .into(),
+
+            Stat::None => Doc::Nil,
+
+            Stat::RepeatUntil(repeat) => vec![
+                "repeat".into(),
+                self.body(repeat.body),
+                "until ".into(),
+                self.exp(&repeat.cond, false),
+            ]
+            .into(),
+
+            Stat::Return(ret) => match ret.exps.is_empty() {
+                true => "return".into(),
+                false => vec!["return ".into(), self.exps(ret.exps)].into(),
+            },
+
+            Stat::VarDef(def) => {
+                let mut docs = vec![format!("local {}", join(def.names)).into()];
+
+                if let Some(init_exps) = def.init_exps {
+                    docs.push(" = ".into());
+                    docs.push(self.exps(init_exps));
+                }
+
+                docs.into()
+            }
+
+            Stat::While(while_) => vec![
+                "while ".into(),
+                self.exp(&while_.cond, false),
+                " do".into(),
+                self.body(while_.body),
+                "end".into(),
+            ]
+            .into(),
+        };
+
+        // A statement starting with `(` would otherwise be parsed as a call of the end of the
+        // previous statement
+        let doc = match starts_with_parens(stat) {
+            true => vec![";".into(), doc].into(),
+            false => doc,
+        };
+
+        let mut leading = self.leading(stat.leading_comments(), self.start_of(stat), true);
+
+        // Comments on an empty statement aren't followed by anything
+        if matches!(**stat, Stat::None) && matches!(leading.last(), Some(Doc::HardLine)) {
+            leading.pop();
+        }
+
+        vec![leading.into(), doc, self.trailing(stat.trailing_comments())].into()
+    }
+    // </Statements>
+
+    // <Expressions>
+    /// Format `exp` with its comments, `hoisted` if its leading comments have already been placed
+    /// by [`Formatter::hoist`]
+    fn exp(&self, exp: &Node<&Exp>, hoisted: bool) -> Doc {
+        let doc = match **exp {
+            Exp::Binary(binary) => self.binary(binary),
+
+            Exp::Bool(value) => value.to_string().into(),
+
+            Exp::Function(function) => vec!["function".into(), self.function(function)].into(),
+
+            Exp::FunctionCall(call) => vec![self.prefix(&call.lhs), self.args(call.args)].into(),
+
+            Exp::Index(index) => vec![
+                self.prefix(&index.lhs),
+                "[".into(),
+                self.exp(&index.exp, false),
+                "]".into(),
+            ]
+            .into(),
+
+            Exp::Member(member) => {
+                vec![self.prefix(&member.lhs), format!(".{}", member.name).into()].into()
+            }
+
+            Exp::MethodCall(call) => vec![
+                self.prefix(&call.lhs),
+                format!(":{}", call.name).into(),
+                self.args(call.args),
+            ]
+            .into(),
+
+            Exp::Nil => "nil".into(),
+
+            Exp::Number(value) => {
+                let source = &self.source[exp.span()];
+                // Keep the original notation, e.g. hexadecimal, unless the node was rewritten
+                match source.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                    true => source.into(),
+                    false => render_number(*value).into(),
+                }
+            }
+
+            Exp::Paren(inner) => vec!["(".into(), self.exp(inner, false), ")".into()].into(),
+
+            Exp::Ref(name) => (*name).into(),
+
+            Exp::String(value) => self.string(&self.source[exp.span()], value).into(),
+
+            // Comments inside the table are kept inside it
+            Exp::Table(table) => {
+                let leading = self.leading(exp.leading_comments(), exp.span().start, hoisted);
+
+                return vec![leading.into(), self.table(table, exp.trailing_comments())].into();
+            }
+
+            Exp::Unary(unary) => {
+                let op = match (unary.op, self.config.operator_style) {
+                    (UnOp::Not, OperatorStyle::Lua) => "not ",
+                    (UnOp::Not, OperatorStyle::C) => "!",
+                    (UnOp::Neg, _) if is_negative(&unary.exp) => "- ",
+                    (UnOp::Neg, _) => "-",
+                    (UnOp::Len, _) => "#",
+                };
+
+                // Unary operators bind tighter than everything bar exponentiation, and nest
+                // without needing parentheses
+                let operand = match *unary.exp {
+                    Exp::Unary(_) => self.exp(&unary.exp, false),
+                    _ => self.operand(&unary.exp, Precedence::Unary, true),
+                };
+
+                vec![op.into(), operand].into()
+            }
+
+            Exp::VarArgs => "...".into(),
+        };
+
+        self.commented(exp, hoisted, doc)
+    }
+
+    fn exps(&self, exps: &[Node<&Exp>]) -> Doc {
+        let mut docs = Vec::with_capacity(exps.len() * 2);
+
+        for (i, exp) in exps.iter().enumerate() {
+            if i > 0 {
+                docs.push(", ".into());
+            }
+
+            docs.push(self.exp(exp, false));
+        }
+
+        docs.into()
+    }
+
+    fn binary(&self, binary: &Binary) -> Doc {
+        let op = binary.op;
+        let precedence = op.precedence();
+        let right = op.is_right_associative();
+
+        // Flatten chains of the same operator, e.g. `a .. b .. c`, so that they break together
+        let mut operands = vec![(binary.rhs, !right)];
+        let mut first = binary.lhs;
+
+        match right {
+            false => {
+                while let Exp::Binary(inner) = *first {
+                    if inner.op != op || first.trivia().is_some() {
+                        break;
+                    }
+
+                    operands.push((inner.rhs, true));
+                    first = inner.lhs;
+                }
+
+                operands.push((first, false));
+                operands.reverse();
+            }
+            true => {
+                operands.insert(0, (first, true));
+
+                while let (Exp::Binary(inner), None) = (*operands[operands.len() - 1].0, {
+                    operands[operands.len() - 1].0.trivia()
+                }) {
+                    if inner.op != op {
+                        break;
+                    }
+
+                    operands.pop();
+                    operands.push((inner.lhs, true));
+                    operands.push((inner.rhs, false));
+                }
+            }
+        }
+
+        let op_text = match (op, self.config.operator_style) {
+            (BinOp::And, OperatorStyle::C) => "&&".to_owned(),
+            (BinOp::Or, OperatorStyle::C) => "||".to_owned(),
+            (BinOp::Ne, OperatorStyle::C) => "!=".to_owned(),
+            _ => op.to_string(),
+        };
+
+        let spaced =
+            self.config.operator_spacing || matches!(op, BinOp::And | BinOp::Or | BinOp::Concat);
+
+        let operand = |(node, strict): &(Node<&Exp>, bool), hoisted| match **node {
+            // A unary operator on the rhs binds the rest of the operand without parentheses
+            Exp::Unary(_) if *strict != right => self.exp(node, hoisted),
+            _ => self.operand_hoisted(node, precedence, *strict, hoisted),
+        };
+
+        let mut rest = Vec::new();
+
+        for item in &operands[1..] {
+            match spaced {
+                true => {
+                    rest.push(self.hoist_before_line(item.0.leading_comments(), Doc::Line));
+                    rest.push(format!("{} ", op_text).into());
+                    rest.push(operand(item, true));
+                }
+                false => {
+                    rest.push(op_text.clone().into());
+
+                    // `a - -b` would otherwise start a comment
+                    if op == BinOp::Sub && is_negative(&item.0) {
+                        rest.push(" ".into());
+                    }
+
+                    rest.push(operand(item, false));
+                }
+            }
+        }
+
+        let first = operand(&operands[0], false);
+
+        match spaced {
+            true => Doc::group(vec![first, Doc::indent(rest.into())].into()),
+            false => vec![first, rest.into()].into(),
+        }
+    }
+
+    /// Format an operand of an operator with `precedence`, `strict` operands needing parentheses
+    /// even when their precedence is equal, as they would otherwise be re-associated
+    fn operand(&self, exp: &Node<&Exp>, precedence: Precedence, strict: bool) -> Doc {
+        self.operand_hoisted(exp, precedence, strict, false)
+    }
+
+    fn operand_hoisted(
+        &self,
+        exp: &Node<&Exp>,
+        precedence: Precedence,
+        strict: bool,
+        hoisted: bool,
+    ) -> Doc {
+        let parens = match exp_precedence(exp) {
+            Some(operand) if strict => operand <= precedence,
+            Some(operand) => operand < precedence,
+            None => false,
+        };
+
+        match parens {
+            true => vec!["(".into(), self.exp(exp, hoisted), ")".into()].into(),
+            false => self.exp(exp, hoisted),
+        }
+    }
+
+    /// Format the lhs of a call, index or member expression, which must be a prefix expression
+    fn prefix(&self, exp: &Node<&Exp>) -> Doc {
+        match is_prefix(exp) {
+            true => self.exp(exp, false),
+            false => vec!["(".into(), self.exp(exp, false), ")".into()].into(),
+        }
+    }
+
+    fn args(&self, args: &[Node<&Exp>]) -> Doc {
+        if args.is_empty() {
+            return "()".into();
+        }
+
+        // Hug a trailing function or table, e.g. `hook.Add("Think", "id", function() ... end)`,
+        // rather than putting every argument on its own line
+        let (last, init) = args.split_last().unwrap();
+
+        let huggable = |exp: &Node<&Exp>| match **exp {
+            Exp::Function(_) => true,
+            Exp::Table(table) => !table.fields.is_empty(),
+            _ => false,
+        };
+
+        let hug = huggable(last)
+            && !init.iter().any(huggable)
+            && args
+                .iter()
+                .all(|arg| arg.leading_comments().iter().all(|c| !c.own_line));
+
+        if hug {
+            let mut docs = vec!["(".into()];
+
+            for arg in init {
+                docs.push(self.exp(arg, false));
+                docs.push(", ".into());
+            }
+
+            docs.push(self.exp(last, false));
+            docs.push(")".into());
+
+            return docs.into();
+        }
+
+        let items = args
+            .iter()
+            .map(|arg| (arg.leading_comments(), self.exp(arg, true)))
+            .collect();
+
+        Doc::group(
+            vec![
+                "(".into(),
+                self.list(items, Doc::SoftLine),
+                Doc::SoftLine,
+                ")".into(),
+            ]
+            .into(),
+        )
+    }
+
+    fn function(&self, function: &Function) -> Doc {
+        let params = match (function.params.is_empty(), function.is_vararg()) {
+            (true, true) => "(...)".to_owned(),
+            (false, true) => format!("({}, ...)", join(function.params)),
+            (_, false) => format!("({})", join(function.params)),
+        };
+
+        match function.body.is_empty() {
+            true => format!("{} end", params).into(),
+            false => vec![params.into(), self.body(function.body), "end".into()].into(),
+        }
+    }
+
+    fn table(&self, table: &TableConstructor, comments: &[Comment]) -> Doc {
+        if table.fields.is_empty() && comments.is_empty() {
+            return "{}".into();
+        }
+
+        let items = table
+            .fields
+            .iter()
+            .map(|field| {
+                let first = field.key.as_ref().unwrap_or(&field.value);
+
+                (first.leading_comments(), self.field(field))
+            })
+            .collect();
+
+        let separator = match (table.fields.is_empty(), self.config.trailing_separator) {
+            (true, _) | (false, TrailingSeparator::Never) => Doc::Nil,
+            (false, TrailingSeparator::Always) => ",".into(),
+            (false, TrailingSeparator::MultiLine) => Doc::if_break(",".into(), Doc::Nil),
+        };
+
+        // Comments after the last field, which are kept before the closing brace
+        let mut inner = Vec::new();
+
+        for comment in comments {
+            match (comment.own_line, comment.is_line()) {
+                (true, _) => {
+                    inner.push(Doc::HardLine);
+                    inner.push(self.comment(comment).into());
+                }
+                (false, true) => inner.push(Doc::LineSuffix(format!(" {}", self.comment(comment)))),
+                (false, false) => inner.push(format!(" {}", self.comment(comment)).into()),
+            }
+        }
+
+        // Own line comments in an otherwise empty table come straight after the brace
+        if table.fields.is_empty() && matches!(inner.first(), Some(Doc::HardLine)) {
+            inner.remove(0);
+        }
+
+        Doc::group(
+            vec![
+                "{".into(),
+                self.list(items, Doc::Line),
+                separator,
+                Doc::indent(inner.into()),
+                Doc::Line,
+                "}".into(),
+            ]
+            .into(),
+        )
+    }
+
+    /// Format a table field, its leading comments having been hoisted
+    fn field(&self, field: &Field) -> Doc {
+        let Some(key) = &field.key else {
+            return self.exp(&field.value, true);
+        };
+
+        let key = match **key {
+            Exp::String(name) if is_name(name) => {
+                let name = std::str::from_utf8(name).unwrap();
+
+                self.commented(key, true, name.into())
+            }
+            _ => vec!["[".into(), self.exp(key, true), "]".into()].into(),
+        };
+
+        vec![key, " = ".into(), self.exp(&field.value, false)].into()
+    }
+
+    /// Items separated by `,` and a line break, the first preceded by `open`
+    fn list(&self, items: Vec<(&[Comment], Doc)>, open: Doc) -> Doc {
+        let mut docs = Vec::with_capacity(items.len() * 4);
+
+        for (i, (comments, doc)) in items.into_iter().enumerate() {
+            if i > 0 {
+                docs.push(",".into());
+            }
+
+            let line = if i == 0 { open.clone() } else { Doc::Line };
+
+            docs.push(self.hoist_before_line(comments, line));
+            docs.push(doc);
+        }
+
+        Doc::indent(docs.into())
+    }
+
+    fn string(&self, source: &str, value: &[u8]) -> String {
+        // Long strings can't contain escapes, so are kept as they are
+        if source.starts_with('[') {
+            return source.to_owned();
+        }
+
+        let count = |quote| value.iter().filter(|&&byte| byte == quote).count();
+
+        let quote = match self.config.quote_style {
+            QuoteStyle::AlwaysDouble => '"',
+            QuoteStyle::AlwaysSingle => '\'',
+            QuoteStyle::PreferDouble if count(b'"') > count(b'\'') => '\'',
+            QuoteStyle::PreferDouble => '"',
+            QuoteStyle::PreferSingle if count(b'\'') > count(b'"') => '"',
+            QuoteStyle::PreferSingle => '\'',
+        };
+
+        match source.chars().next() {
+            // Keep the original escapes, only changing those of the quotes
+            Some(original @ ('"' | '\'')) => {
+                let body = &source[1..source.len() - 1];
+
+                let mut out = String::with_capacity(source.len());
+
+                out.push(quote);
+
+                let mut chars = body.chars();
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some(c) if c == original && c != quote => out.push(c),
+                            Some(c) => {
+                                out.push('\\');
+                                out.push(c);
+                            }
+                            None => out.push('\\'),
+                        },
+                        c if c == quote => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        c => out.push(c),
+                    }
+                }
+
+                out.push(quote);
+
+                out
+            }
+
+            // A name used as a table key, or a rewritten node
+            _ => quote_string(value, quote),
+        }
+    }
+    // </Expressions>
+
+    // <Comments>
+    /// Surround `doc` with the comments of `node`
+    fn commented<T>(&self, node: &Node<T>, hoisted: bool, doc: Doc) -> Doc {
+        if node.trivia().is_none() {
+            return doc;
+        }
+
+        let leading = self.leading(node.leading_comments(), node.span().start, hoisted);
+
+        vec![leading.into(), doc, self.trailing(node.trailing_comments())].into()
+    }
+
+    /// Comments preceding a node starting at `start`. Comments on their own line are kept on
+    /// their own line, others are kept inline or, for line comments, deferred to the end of the
+    /// line, unless they've already been placed by [`Formatter::hoist`].
+    fn leading(&self, comments: &[Comment], start: usize, hoisted: bool) -> Vec<Doc> {
+        let mut docs = Vec::new();
+
+        for (i, comment) in comments.iter().enumerate() {
+            match (comment.own_line, comment.is_line()) {
+                (true, _) => {
+                    docs.push(self.comment(comment).into());
+                    docs.push(Doc::HardLine);
+
+                    let next = comments
+                        .get(i + 1)
+                        .map_or(start, |comment| comment.span().start);
+
+                    if self.blank_line(comment.span().end, next) {
+                        docs.push(Doc::HardLine);
+                    }
+                }
+                _ if hoisted => {}
+                (false, true) => docs.push(Doc::LineSuffix(format!(" {}", self.comment(comment)))),
+                (false, false) => docs.push(format!("{} ", self.comment(comment)).into()),
+            }
+        }
+
+        docs
+    }
+
+    /// Comments from `comments` that follow other code on their line, placed before the line break
+    /// preceding the node they're attached to so that they stay on that line
+    fn hoist(&self, comments: &[Comment]) -> Doc {
+        comments
+            .iter()
+            .filter(|comment| is_hoisted(comment))
+            .map(|comment| match comment.is_line() {
+                true => Doc::LineSuffix(format!(" {}", self.comment(comment))),
+                false => format!(" {}", self.comment(comment)).into(),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Hoist `comments` before `line` if it's broken, otherwise leave block comments inline after
+    /// it. Line comments always break the line.
+    fn hoist_before_line(&self, comments: &[Comment], line: Doc) -> Doc {
+        let inline = comments
+            .iter()
+            .filter(|comment| is_hoisted(comment) && !comment.is_line())
+            .map(|comment| format!("{} ", self.comment(comment)).into())
+            .collect::<Vec<_>>();
+
+        vec![
+            Doc::if_break(self.hoist(comments), Doc::Nil),
+            line,
+            Doc::if_break(Doc::Nil, inline.into()),
+        ]
+        .into()
+    }
+
+    fn trailing(&self, comments: &[Comment]) -> Doc {
+        comments
+            .iter()
+            .map(|comment| match comment.is_line() {
+                true => Doc::LineSuffix(format!(" {}", self.comment(comment))),
+                false => format!(" {}", self.comment(comment)).into(),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// The text of `comment`, converted to the configured comment style
+    fn comment(&self, comment: &Comment) -> String {
+        let source = &self.source[comment.span()];
+
+        match (self.config.operator_style, comment.kind) {
+            (OperatorStyle::Lua, CommentKind::CLine) => {
+                let text = comment.text.trim_end();
+
+                // Avoid turning the comment into a block comment
+                match text.starts_with('[') && text[1..].starts_with(['[', '=']) {
+                    true => format!("-- {}", text),
+                    false => format!("--{}", text),
+                }
+            }
+
+            (OperatorStyle::Lua, CommentKind::CBlock) => {
+                let level = (0..)
+                    .map(|n| "=".repeat(n))
+                    .find(|level| !comment.text.contains(&format!("]{}]", level)))
+                    .unwrap();
+
+                format!("--[{}[{}]{}]", level, comment.text, level)
+            }
+
+            (OperatorStyle::C, CommentKind::Line) => format!("//{}", comment.text.trim_end()),
+
+            (OperatorStyle::C, CommentKind::Block) if !comment.text.contains("*/") => {
+                format!("/*{}*/", comment.text)
+            }
+
+            (_, CommentKind::Line | CommentKind::CLine) => source.trim_end().to_owned(),
+
+            _ => source.to_owned(),
+        }
+    }
+
+    /// Whether there is an empty line between `start` and `end`
+    fn blank_line(&self, start: usize, end: usize) -> bool {
+        start < end && self.source[start..end].matches('\n').nth(1).is_some()
+    }
+
+    /// Start of `stat` including the comments that precede it on their own line
+    fn start(&self, stat: &Node<&Stat>) -> usize {
+        stat.leading_comments()
+            .iter()
+            .find(|comment| !is_hoisted(comment))
+            .map_or(stat.span().start, |comment| comment.span().start)
+    }
+
+    /// Start of `stat` itself, after its leading comments
+    fn start_of(&self, stat: &Node<&Stat>) -> usize {
+        match **stat {
+            // Empty statements span their comments
+            Stat::None => stat.span().end,
+            _ => stat.span().start,
+        }
+    }
+    // </Comments>
+}
+
+/// Whether `comment` is placed by [`Formatter::hoist`]
+fn is_hoisted(comment: &Comment) -> bool {
+    !comment.own_line
+}
+
+fn join(names: &[Name]) -> String {
+    names
+        .iter()
+        .map(|name| **name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn starts_with_parens(stat: &Stat) -> bool {
+    fn leftmost(exp: &Exp) -> bool {
+        match exp {
+            Exp::FunctionCall(call) => leftmost(&call.lhs),
+            Exp::Index(index) => leftmost(&index.lhs),
+            Exp::Member(member) => leftmost(&member.lhs),
+            Exp::MethodCall(call) => leftmost(&call.lhs),
+            exp => !is_prefix(exp),
+        }
+    }
+
+    match stat {
+        Stat::Assignment(assignment) => leftmost(&assignment.vars[0]),
+        Stat::FunctionCall(call) => leftmost(&call.lhs),
+        Stat::MethodCall(call) => leftmost(&call.lhs),
+        _ => false,
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
 use crate::lexer::Token;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -30,3 +35,27 @@ impl PartialEq<Token<'_>> for Keyword {
         }
     }
 }
+
+impl Display for Keyword {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Keyword::Break => "break",
+            Keyword::Do => "do",
+            Keyword::Else => "else",
+            Keyword::ElseIf => "elseif",
+            Keyword::End => "end",
+            Keyword::For => "for",
+            Keyword::Function => "function",
+            Keyword::If => "if",
+            Keyword::In => "in",
+            Keyword::Local => "local",
+            Keyword::Repeat => "repeat",
+            Keyword::Return => "return",
+            Keyword::Then => "then",
+            Keyword::Until => "until",
+            Keyword::While => "while",
+            Keyword::Continue => "continue",
+            Keyword::Goto => "goto",
+        })
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
 use crate::lexer::Token;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -32,3 +37,30 @@ impl PartialEq<Token<'_>> for Op {
         }
     }
 }
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Op::Add => "+",
+            Op::And => "and",
+            Op::Colon => ":",
+            Op::Div => "/",
+            Op::Dot => ".",
+            Op::DotDot => "..",
+            Op::Eq => "=",
+            Op::EqEq => "==",
+            Op::Exp => "^",
+            Op::Gt => ">",
+            Op::GtEq => ">=",
+            Op::Len => "#",
+            Op::Lt => "<",
+            Op::LtEq => "<=",
+            Op::Mod => "%",
+            Op::Mul => "*",
+            Op::Ne => "~=",
+            Op::Or => "or",
+            Op::Not => "not",
+            Op::Sub => "-",
+        })
+    }
+}
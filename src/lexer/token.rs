@@ -1,3 +1,8 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
 use bumpalo::{collections::Vec as BumpVec, Bump};
 use logos::{Lexer, Logos, Source};
 use memchr::memmem;
@@ -119,6 +124,32 @@ impl From<Op> for Token<'_> {
     }
 }
 
+/// Human-readable description of a token, for use in error messages
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Token::Comma => write!(f, "`,`"),
+            Token::Comment(_) => write!(f, "comment"),
+            Token::Ellipsis => write!(f, "`...`"),
+            Token::Keyword(keyword) => write!(f, "`{}`", keyword),
+            Token::LBrace => write!(f, "`{{`"),
+            Token::LBracket => write!(f, "`[`"),
+            Token::Literal(Literal::Bool(value)) => write!(f, "`{}`", value),
+            Token::Literal(Literal::Nil) => write!(f, "`nil`"),
+            Token::Literal(Literal::Number(_)) => write!(f, "number"),
+            Token::Literal(Literal::String(_)) => write!(f, "string"),
+            Token::LParens => write!(f, "`(`"),
+            Token::Name(name) => write!(f, "name `{}`", name),
+            Token::Label(name) => write!(f, "label `::{}::`", name),
+            Token::Op(op) => write!(f, "`{}`", op),
+            Token::RBrace => write!(f, "`}}`"),
+            Token::RBracket => write!(f, "`]`"),
+            Token::RParens => write!(f, "`)`"),
+            Token::Semicolon => write!(f, "`;`"),
+        }
+    }
+}
+
 fn string_literal<'a>(lexer: &Lexer<'a, Token<'a>>) -> Option<&'a [u8]> {
     let slice = lexer.slice().as_bytes();
 
@@ -252,13 +283,13 @@ fn comment<'a>(lexer: &mut Lexer<'a, Token<'a>>) -> Option<&'a str> {
             Some(end) => {
                 lexer.bump(end + 2);
 
-                Some(&lexer.slice()[2..end + 4])
+                Some(&lexer.slice()[2..end + 2])
             }
         };
     }
 
     let remainder = lexer.remainder();
-    return match memchr::memchr(b'\n', remainder.as_bytes()) {
+    match memchr::memchr(b'\n', remainder.as_bytes()) {
         None => {
             lexer.bump(remainder.len());
 
@@ -272,7 +303,7 @@ fn comment<'a>(lexer: &mut Lexer<'a, Token<'a>>) -> Option<&'a str> {
             // the comment in this branch.
             Some(&lexer.slice()[2..])
         }
-    };
+    }
 }
 
 fn multi_line<'a>(lexer: &mut Lexer<'a, Token<'a>>) -> Option<&'a str> {
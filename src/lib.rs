@@ -1,22 +1,63 @@
-pub use self::parser::Parser;
+pub use self::{parser::Parser, source_map::SourceMap};
 
+pub mod analysis;
+pub mod api;
 pub mod ast;
+pub mod cst;
+pub mod diagnostic;
+pub mod formatter;
 pub mod lexer;
+pub mod lint;
+pub mod lsp;
 pub mod parser;
+pub mod project;
+pub mod source_map;
 
 #[cfg(test)]
 mod tests {
     use std::{
         fs::File,
-        io::{BufWriter, Write},
+        io::{BufWriter, Cursor, Write},
     };
 
     use bumpalo::Bump;
     use pretty_bytes::converter::convert;
+    use serde_json::{json, Value};
 
-    use crate::{parser::Error, Parser};
+    use crate::{
+        analysis::{
+            hooks::{HookKind, Hooks},
+            net::NetUsage,
+            realm::{file_realm, Realms},
+            scope::{Access, BindingKind, Resolution, ScopeTree},
+        },
+        api::{self, Database, Realm},
+        ast::{
+            exps::{binary::BinOp, Binary, FunctionCall},
+            node::Node,
+            trivia::CommentKind,
+            visitors::{
+                fold::{walk_binary_exp, walk_name, Fold},
+                listener::{traverse, Ancestors, Listener},
+                renderer::Renderer,
+            },
+            Exp, Name, Stat,
+        },
+        cst::{self, NodeKind},
+        diagnostic::Diagnostic,
+        formatter::{self, Config, OperatorStyle, QuoteStyle, TrailingSeparator},
+        lint,
+        lsp::{self, Server},
+        parser::{
+            batch::Batch,
+            incremental::{Edit, EditError, Parsed},
+            Error, Span,
+        },
+        project::{DependencyKind, Project},
+        Parser, SourceMap,
+    };
 
-    static CODE: &'static str = include_str!("../test.lua");
+    static CODE: &str = include_str!("../test.lua");
 
     #[test]
     fn it_works() {
@@ -38,6 +79,1809 @@ mod tests {
         // println!("Wasted: {}", convert(parser.waste as f64));
     }
 
+    #[test]
+    fn comments() {
+        let bump = Bump::new();
+
+        let source =
+            "-- leading\nlocal x = 1 // trailing\n--[[ inner ]] f(x, /* arg */ 2)\n-- dangling";
+
+        let (tokens, comments) = unwrap(Parser::lex_with_comments(source, &bump));
+
+        let chunk = unwrap(
+            Parser::new_in(&tokens, &bump)
+                .with_comments(&comments)
+                .parse_chunk(),
+        );
+
+        assert_eq!(chunk.len(), 3);
+
+        let leading = chunk[0].leading_comments();
+        assert_eq!(leading.len(), 1);
+        assert_eq!(
+            (leading[0].kind, leading[0].text),
+            (CommentKind::Line, " leading")
+        );
+
+        let trailing = chunk[0].trailing_comments();
+        assert_eq!(trailing.len(), 1);
+        assert_eq!(
+            (trailing[0].kind, trailing[0].text),
+            (CommentKind::CLine, " trailing")
+        );
+        assert!(!trailing[0].own_line);
+
+        let leading = chunk[1].leading_comments();
+        assert_eq!(
+            (leading[0].kind, leading[0].text),
+            (CommentKind::Block, " inner ")
+        );
+
+        let Stat::FunctionCall(call) = *chunk[1] else {
+            panic!("expected a function call, got {:?}", chunk[1]);
+        };
+        let leading = call.args[1].leading_comments();
+        assert_eq!(
+            (leading[0].kind, leading[0].text),
+            (CommentKind::CBlock, " arg ")
+        );
+
+        assert!(matches!(*chunk[2], Stat::None));
+        assert_eq!(chunk[2].leading_comments()[0].text, " dangling");
+
+        // Without comments nothing is attached, and nodes are no bigger than a span and a pointer
+        // to their value were before trivia
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        assert_eq!(chunk.len(), 2);
+        assert!(chunk.iter().all(|stat| stat.trivia().is_none()));
+        assert_eq!(
+            std::mem::size_of::<Node<&Stat>>(),
+            std::mem::size_of::<(usize, usize, &Stat)>()
+        );
+    }
+
+    #[test]
+    fn source_map() {
+        let source = "local a = 1\r\nlocal б = \"😀\" then\n\nreturn";
+
+        let map = SourceMap::new(source).with_name("file.lua");
+
+        assert_eq!(map.line_count(), 4);
+        assert_eq!(map.line_text(0), "local a = 1");
+        assert_eq!(map.line_text(2), "");
+
+        let offset = source.find("then").unwrap();
+        let location = map.location(offset);
+
+        assert_eq!(location.line, 1);
+        assert_eq!(location.column, 14);
+        assert_eq!(location.utf8_column, 18);
+        assert_eq!(location.utf16_column, 15);
+        assert_eq!(map.offset_utf16(1, 15), Some(offset));
+        // Clamped to before the `\r\n`
+        assert_eq!(map.offset_utf16(0, 50), Some(11));
+        assert_eq!(map.offset_utf16(1, 50), Some(source.find("\n\n").unwrap()));
+        assert_eq!(location.to_string(), "2:15");
+
+        let bump = Bump::new();
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let err = Parser::new_in(&tokens, &bump).parse_chunk().unwrap_err();
+
+        assert_eq!(
+            err.display_with(&map).to_string(),
+            "file.lua:2:15: Unexpected `then`, expecting end of file"
+        );
+        // Without a source map there's no location to give
+        assert_eq!(err.to_string(), "Unexpected `then`, expecting end of file");
+    }
+
+    #[test]
+    fn diagnostic() {
+        let source = "if a then\n\tb()\nelse\n\tc(1 2)\n";
+
+        let map = SourceMap::new(source).with_name("file.lua");
+
+        let bump = Bump::new();
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let err = Parser::new_in(&tokens, &bump).parse_chunk().unwrap_err();
+
+        let diagnostic = Diagnostic::from_error(&err, &map);
+
+        assert_eq!(
+            diagnostic.render(&map, false),
+            "\
+error: Unexpected number, expecting `)`
+ --> file.lua:4:6
+  |
+4 |     c(1 2)
+  |      - the `(` that needs this `)` starts here
+  |         ^ expected `)`
+  |
+"
+        );
+
+        let source = "local function f()\n  g()\n\n  h()\n";
+
+        let map = SourceMap::new(source);
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+
+        // Without recovery, a statement cut short by the end of the file ends the block instead
+        let err = Parser::new_in(&tokens, &bump).parse_chunk().unwrap_err();
+
+        assert_eq!(err.to_string(), "Unexpected `local`, expecting end of file");
+
+        let (_, errors) = Parser::new_in(&tokens, &bump).parse_chunk_recovering();
+
+        assert_eq!(
+            Diagnostic::from_error(&errors[0], &map).render(&map, false),
+            "\
+error: Unexpected EOF, expecting `end`
+ --> 4:6
+  |
+1 | local function f()
+  |       -------- the `function` that needs this `end` starts here
+...
+4 |   h()
+  |      ^ expected `end`
+  |
+"
+        );
+
+        // Errors point at the offending token rather than the one after it
+        for (source, got) in [
+            ("if a b() end", "b"),
+            ("local 1 = 2", "1"),
+            ("f:g 1", "1"),
+            ("x = )", ")"),
+        ] {
+            let tokens = unwrap(Parser::lex(source, &bump));
+            let err = Parser::new_in(&tokens, &bump).parse_chunk().unwrap_err();
+
+            let start = source.find(got).unwrap();
+
+            assert_eq!(err.span(), Some(&(start..start + got.len())), "{}", source);
+        }
+    }
+
+    #[test]
+    fn render() {
+        let bump = Bump::new();
+
+        // Spans differ between the original and rendered source, so they're left out when comparing
+        struct EraseSpans<'b>(&'b Bump);
+
+        impl<'b> Fold<'b> for EraseSpans<'b> {
+            fn bump(&self) -> &'b Bump {
+                self.0
+            }
+
+            fn fold_span(&mut self, _: Span) -> Span {
+                0..0
+            }
+        }
+
+        let tokens = unwrap(Parser::lex(CODE, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+        let rendered = Renderer::render(chunk);
+
+        let tokens = unwrap(Parser::lex(&rendered, &bump));
+        let reparsed = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        assert_eq!(
+            format!("{:?}", EraseSpans(&bump).fold_block(reparsed)),
+            format!("{:?}", EraseSpans(&bump).fold_block(chunk))
+        );
+        assert_eq!(Renderer::render(reparsed), rendered);
+
+        let source = r#"x = (a + b) * c ^ -(d .. e) .. f .. (g .. h) - (i - j)
+y = (-2) ^ 2, - -z, not (a == b), #t[1], a - (b - c), 2 ^ 3 ^ 4, (2 ^ 3) ^ 4
+;("x"):rep(3)
+z = (f()), (...), ((a)).b
+local t = { k = "a\"b\n\0011", ["end"] = 1e-7, [1.5] = 16, {}, ... }
+"#;
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        assert_eq!(Renderer::render(chunk), source);
+
+        // An empty `return` is rendered without its `;`, even at the end of the chunk
+        for source in ["return;", "x = 1 return;"] {
+            let tokens = unwrap(Parser::lex(source, &bump));
+            let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+            let rendered = Renderer::render(chunk);
+
+            let tokens = unwrap(Parser::lex(&rendered, &bump));
+            let reparsed = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+            assert_eq!(
+                format!("{:?}", EraseSpans(&bump).fold_block(reparsed)),
+                format!("{:?}", EraseSpans(&bump).fold_block(chunk))
+            );
+        }
+    }
+
+    #[test]
+    fn cst() {
+        let bump = Bump::new();
+
+        let tree = unwrap(cst::parse(CODE, &bump));
+
+        assert_eq!(tree.to_string(), CODE);
+
+        let source = "  -- leading\nlocal a , b = 1;  f ( a ) --[[ x ]] ::l::\n\nfunction ENT:Think( ... ) return end -- trailing";
+
+        let tree = unwrap(cst::parse(source, &bump));
+
+        assert_eq!(tree.to_string(), source);
+        assert_eq!(tree.span(), 0..source.len());
+
+        let name = tree.covering_node(source.find("Think").unwrap()..source.find("( ...").unwrap());
+
+        assert_eq!(name.kind(), NodeKind::Name);
+        assert_eq!(name.to_string(), "Think");
+
+        let kinds = name.ancestors().map(|node| node.kind()).collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                NodeKind::FunctionName,
+                NodeKind::FunctionDefStat,
+                NodeKind::Chunk
+            ]
+        );
+
+        let token = tree.token_at(source.find("x ]]").unwrap()).unwrap();
+
+        assert_eq!(token.text(), "--[[ x ]]");
+        assert!(token.is_trivia());
+        assert_eq!(token.parent().kind(), NodeKind::Chunk);
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let ast = unwrap(tree.to_ast(&bump));
+
+        assert_eq!(Renderer::render(ast), Renderer::render(chunk));
+        assert_eq!(ast[0].leading_comments()[0].text, " leading");
+    }
+
+    #[test]
+    fn incremental() {
+        let bump = Bump::new();
+
+        let source = "local a = 1 -- one\n\nfunction f(x)\n    return x + a\nend\n\n-- g\nlocal g = f\nprint(g(2)) --[[ done ]]\n";
+
+        let mut parsed = unwrap(Parsed::parse(source, &bump));
+
+        let edits = [
+            // Within a function body
+            ("x + a", "x * a + 1"),
+            // Joining onto the previous statement
+            ("\nprint", "\n(f)print"),
+            // Splitting an identifier
+            ("local g", "local g2 = f g"),
+            // Opening a comment swallowing the following statements
+            ("-- g\n", "--[[ g\n"),
+            // Deleting a statement and its comment
+            ("local a = 1 -- one\n", ""),
+        ];
+
+        for (old, new) in edits {
+            let start = parsed.source().find(old).unwrap();
+
+            let edit = Edit {
+                range: start..start + old.len(),
+                text: new,
+            };
+
+            let edited = parsed.edit(&edit, &bump).unwrap();
+            let expected = unwrap(Parsed::parse(
+                bump.alloc_str(&edit.apply(parsed.source()).unwrap()),
+                &bump,
+            ));
+
+            assert_eq!(edited.source(), expected.source());
+            assert_eq!(edited.tokens(), expected.tokens());
+            assert_eq!(
+                format!("{:?}", edited.comments()),
+                format!("{:?}", expected.comments())
+            );
+            assert_eq!(
+                format!("{:?}", edited.settled(&bump)),
+                format!("{:?}", expected.chunk())
+            );
+
+            parsed = edited;
+        }
+
+        // Statements before the edit are reused
+        let parsed = unwrap(Parsed::parse(source, &bump));
+        let start = source.find("print").unwrap();
+
+        let edit = Edit {
+            range: start..start + 5,
+            text: "log",
+        };
+
+        let edited = parsed.edit(&edit, &bump).unwrap();
+
+        assert!(std::ptr::eq(*parsed.chunk()[0], *edited.chunk()[0]));
+        assert_eq!(edited.source(), source.replace("print", "log"));
+
+        // Statements after the edit are reused too, only moving by its length
+        let edit = Edit {
+            range: 6..7,
+            text: "alpha",
+        };
+
+        let edited = parsed.edit(&edit, &bump).unwrap();
+        let twice = edited.edit(&edit, &bump).unwrap();
+
+        for (i, stat) in parsed.chunk().iter().enumerate().skip(2) {
+            assert!(std::ptr::eq(**stat, *edited.chunk()[i]));
+            assert!(std::ptr::eq(**stat, *twice.chunk()[i]));
+            assert_eq!(edited.offset(i), 4);
+            assert_eq!(twice.offset(i), 8);
+        }
+
+        let settled = twice.settled(&bump);
+
+        assert_eq!(&twice.source()[settled[3].span()], "print(g(2))");
+        assert_eq!(
+            settled[3].trailing_comments()[0].span(),
+            twice.comments().last().unwrap().span()
+        );
+
+        // Edits must lie within the source and on character boundaries
+        let source = "local s = \"é\"";
+        let parsed = unwrap(Parsed::parse(source, &bump));
+
+        for (start, end) in [(3, 2), (0, source.len() + 1), (11, 12)] {
+            let edit = Edit {
+                range: start..end,
+                text: "",
+            };
+
+            assert!(matches!(
+                parsed.edit(&edit, &bump),
+                Err(EditError::Range(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn fold() {
+        struct Rewriter<'b>(&'b Bump);
+
+        impl<'b> Fold<'b> for Rewriter<'b> {
+            fn bump(&self) -> &'b Bump {
+                self.0
+            }
+
+            fn fold_binary_exp(&mut self, v: &Node<&Binary>) -> Exp<'b> {
+                match walk_binary_exp(self, v) {
+                    Exp::Binary(binary) => match (*binary.lhs, binary.op, *binary.rhs) {
+                        (Exp::Number(lhs), BinOp::Add, Exp::Number(rhs)) => Exp::Number(lhs + rhs),
+                        _ => Exp::Binary(binary),
+                    },
+                    exp => exp,
+                }
+            }
+
+            fn fold_name(&mut self, v: &Name) -> Name<'b> {
+                match **v {
+                    "x" => Node::new(v.span(), "renamed"),
+                    _ => walk_name(self, v),
+                }
+            }
+        }
+
+        let bump = Bump::new();
+
+        let chunk = {
+            let source = "local x = 1 + 2 + y\nx.x = function(x) return x + (3 + 4) end";
+
+            let parse_bump = Bump::new();
+
+            let tokens = unwrap(Parser::lex(source, &parse_bump));
+            let chunk = unwrap(Parser::new_in(&tokens, &parse_bump).parse_chunk());
+
+            Rewriter(&bump).fold_block(chunk)
+        };
+
+        assert_eq!(
+            Renderer::render(chunk),
+            "local renamed = 3 + y\nrenamed.renamed = function(renamed)\n    return renamed + 7\nend\n"
+        );
+    }
+
+    #[test]
+    fn listener() {
+        #[derive(Default)]
+        struct Recorder<'a> {
+            refs: Vec<&'a str>,
+            breaks: Vec<(bool, bool)>,
+            calls: Vec<(&'a str, usize)>,
+        }
+
+        impl<'a> Listener<'a> for Recorder<'a> {
+            fn enter_stat(&mut self, v: &Node<'a, &'a Stat<'a>>, ancestors: &Ancestors<'a>) {
+                if let Stat::Break = **v {
+                    self.breaks
+                        .push((ancestors.in_loop(), ancestors.in_function()));
+                }
+            }
+
+            fn enter_ref_exp(&mut self, v: &Node<'a, &'a str>, _ancestors: &Ancestors<'a>) {
+                self.refs.push(**v);
+            }
+
+            fn enter_function_call(
+                &mut self,
+                v: &Node<'a, &'a FunctionCall<'a>>,
+                ancestors: &Ancestors<'a>,
+            ) {
+                if let Exp::Ref(name) = **v.lhs {
+                    self.calls.push((name, ancestors.len()));
+                }
+            }
+        }
+
+        let source = "a = b\nif c then d() elseif e then f{ [g] = h } end\nwhile i do local j = function() break end break end\nfor k = l, m() do repeat break until n end";
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let mut recorder = Recorder::default();
+
+        traverse(&mut recorder, chunk);
+
+        assert_eq!(
+            recorder.refs,
+            ["a", "b", "c", "d", "e", "f", "g", "h", "i", "l", "m", "n"]
+        );
+        assert_eq!(
+            recorder.breaks,
+            [(false, true), (true, false), (true, false)]
+        );
+        assert_eq!(recorder.calls, [("d", 1), ("f", 1), ("m", 1)]);
+    }
+
+    #[test]
+    fn names() {
+        let source =
+            "local a, b = c.d\nfunction e.f:g(h, ...) i:j() end\nfor k = 1, 2 do end\n::l:: goto l";
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let mut names = Vec::new();
+
+        for stat in chunk {
+            match **stat {
+                Stat::VarDef(def) => {
+                    names.extend(def.names);
+
+                    if let Exp::Member(member) = *def.init_exps.unwrap()[0] {
+                        names.push(member.name);
+                    }
+                }
+                Stat::FunctionDef(def) => {
+                    assert_eq!(def.name.to_string(), "e.f:g");
+                    assert_eq!(&source[def.name.span()], "e.f:g");
+
+                    names.push(def.name.base);
+                    names.extend(def.name.fields);
+                    names.extend(def.name.method);
+                    names.extend(def.body.params);
+
+                    assert_eq!(&source[def.body.vararg.unwrap().span()], "...");
+
+                    if let Stat::MethodCall(call) = *def.body.body[0] {
+                        names.push(call.name);
+                    }
+                }
+                Stat::For(for_) => names.push(for_.init.0),
+                Stat::Label(label) => names.push(label.name),
+                Stat::Goto(goto) => names.push(goto.label),
+                _ => unreachable!(),
+            }
+        }
+
+        let names = names
+            .iter()
+            .map(|name| (&source[name.span()], **name))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            [
+                ("a", "a"),
+                ("b", "b"),
+                ("d", "d"),
+                ("e", "e"),
+                ("f", "f"),
+                ("g", "g"),
+                ("h", "h"),
+                ("j", "j"),
+                ("k", "k"),
+                ("l", "l"),
+                ("l", "l"),
+            ]
+        );
+    }
+
+    #[test]
+    fn function_names() {
+        let source = "function a.b.c:d() end\nfunction ENT.Think() end\nlocal function e() end";
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let names = chunk
+            .iter()
+            .map(|stat| match **stat {
+                Stat::FunctionDef(def) => def.name,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        // Each part spans just its name
+        let parts = |names: &[Name]| {
+            names
+                .iter()
+                .map(|name| {
+                    assert_eq!(&source[name.span()], **name);
+
+                    name.to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(parts(&[names[0].base]), ["a"]);
+        assert_eq!(parts(names[0].fields), ["b", "c"]);
+        assert_eq!(parts(&[names[0].method.unwrap()]), ["d"]);
+        assert_eq!(&source[names[0].span()], "a.b.c:d");
+
+        assert_eq!(parts(&[names[1].base]), ["ENT"]);
+        assert_eq!(parts(names[1].fields), ["Think"]);
+        assert!(names[1].method.is_none());
+
+        assert_eq!(parts(&[names[2].base]), ["e"]);
+        assert!(names[2].fields.is_empty() && names[2].method.is_none());
+    }
+
+    #[test]
+    fn varargs() {
+        let source = "f(function(...) end, function(a, ...) end, function(a, b) end)";
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let Stat::FunctionCall(call) = *chunk[0] else {
+            panic!("expected a function call, got {:?}", chunk[0]);
+        };
+
+        let functions = call
+            .args
+            .iter()
+            .map(|arg| match **arg {
+                Exp::Function(function) => function,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        let params = |i: usize| {
+            functions[i]
+                .params
+                .iter()
+                .map(|param| param.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // `...` is never a parameter, only the vararg spanning it
+        assert!(params(0).is_empty());
+        assert_eq!(params(1), ["a"]);
+        assert_eq!(params(2), ["a", "b"]);
+
+        let varargs = functions
+            .iter()
+            .map(|function| function.vararg.map(|vararg| vararg.span()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(varargs, [Some(11..14), Some(33..36), None]);
+        assert_eq!(&source[11..14], "...");
+        assert_eq!(&source[33..36], "...");
+    }
+
+    #[test]
+    fn scope() {
+        let source = r#"local x = 1
+local x = x + 1
+local function f(a, ...)
+    return f(a) + y
+end
+for i = 1, 10 do x = i end
+repeat local z = 1 until z
+function ENT:Think() return self end
+g = function() return x end
+do local x = 2 end
+print(x, a)
+"#;
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let map = SourceMap::new(source);
+        let tree = ScopeTree::build(chunk);
+
+        // Each reference with the line of the local it resolves to
+        let references = tree
+            .references()
+            .map(|reference| {
+                let line = match reference.resolution {
+                    Resolution::Local(binding) => {
+                        Some(map.line(tree.binding(binding).name.span().start))
+                    }
+                    Resolution::Global => None,
+                };
+
+                (*reference.name, reference.access, line)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            references,
+            [
+                ("x", Access::Read, Some(0)),
+                ("f", Access::Read, Some(2)),
+                ("a", Access::Read, Some(2)),
+                ("y", Access::Read, None),
+                ("x", Access::Write, Some(1)),
+                ("i", Access::Read, Some(5)),
+                ("z", Access::Read, Some(6)),
+                ("ENT", Access::Read, None),
+                ("self", Access::Read, Some(7)),
+                ("g", Access::Write, None),
+                ("x", Access::Read, Some(1)),
+                ("print", Access::Read, None),
+                ("x", Access::Read, Some(1)),
+                ("a", Access::Read, None),
+            ]
+        );
+
+        let kinds = tree
+            .bindings()
+            .map(|(_, binding)| (*binding.name, binding.kind, binding.references.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                ("x", BindingKind::Local, 1),
+                ("x", BindingKind::Local, 3),
+                ("f", BindingKind::LocalFunction, 1),
+                ("a", BindingKind::Param, 1),
+                ("i", BindingKind::ForVar, 1),
+                ("z", BindingKind::Local, 1),
+                ("self", BindingKind::SelfParam, 1),
+                ("x", BindingKind::Local, 0),
+            ]
+        );
+
+        assert_eq!(tree.globals().count(), 5);
+    }
+
+    #[test]
+    fn lint() {
+        let source = r#"local used, unused = 1, 2
+local assigned
+assigned = used
+local function helper(a, b, _c) return a end
+local function recurse(n) return recurse(n) end
+for i, v in pairs(t) do print(v) end
+for _, _v in pairs(t) do end
+for j = 1, 2 do end
+function ENT:Think(dt) end
+"#;
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let diagnostics = lint::lint(chunk, None, &lint::Config::default().with_globals(["t"]));
+
+        let warnings = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span().unwrap().clone();
+
+                (
+                    diagnostic.code.unwrap(),
+                    &source[span],
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            warnings,
+            [
+                ("W211", "unused", "unused variable `unused`"),
+                ("W211", "assigned", "unused variable `assigned`"),
+                ("W211", "helper", "unused function `helper`"),
+                ("W212", "b", "unused argument `b`"),
+                ("W213", "i", "unused loop variable `i`"),
+                ("W213", "j", "unused loop variable `j`"),
+                ("W212", "dt", "unused argument `dt`"),
+            ]
+        );
+
+        let map = SourceMap::new(source).with_name("file.lua");
+
+        assert_eq!(
+            diagnostics[1].render(&map, false),
+            "\
+warning[W211]: unused variable `assigned`
+ --> file.lua:2:7
+  |
+2 | local assigned
+  |       ^^^^^^^^ only ever assigned to
+  |
+"
+        );
+    }
+
+    #[test]
+    fn globals() {
+        let source = r#"AddCSLuaFile()
+ENT.Type = "anim"
+function ENT:Think()
+    target = self:GetTarget()
+    hook.Run("Think", target, Helper(MOVETYPE_NONE, MYADDON_ENABLED))
+    net = nil
+    Shared.count = 1
+end
+"#;
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let warnings = |config: &lint::Config| {
+            lint::lint(chunk, None, config)
+                .iter()
+                .map(|diagnostic| {
+                    let span = diagnostic.span().unwrap().clone();
+
+                    (diagnostic.code.unwrap(), &source[span])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let config = lint::Config::default();
+
+        assert_eq!(
+            warnings(&config),
+            [
+                ("W111", "target"),
+                ("W113", "Helper"),
+                ("W113", "MYADDON_ENABLED"),
+                ("W121", "net"),
+                ("W113", "Shared"),
+            ]
+        );
+
+        let config = config
+            .with_globals(["target", "Shared"])
+            .with_read_globals(["Helper", "MYADDON_*"]);
+
+        assert_eq!(warnings(&config), [("W121", "net")]);
+
+        assert_eq!(warnings(&lint::Config::empty()).len(), 10,);
+    }
+
+    #[test]
+    fn api() {
+        let database = Database::bundled();
+
+        let start = database.library("net").unwrap().function("Start").unwrap();
+
+        assert_eq!(start.realm, Realm::Shared);
+        assert_eq!(
+            start.signature("net.Start"),
+            "net.Start(messageName: string, unreliable: boolean = false) -> boolean"
+        );
+
+        let send = database
+            .library("net")
+            .unwrap()
+            .function("SendToServer")
+            .unwrap();
+
+        assert_eq!(send.realm, Realm::Client);
+        assert!(!Realm::Server.can_use(send.realm));
+        assert!(Realm::Client.can_use(start.realm));
+        assert!(!Realm::Shared.can_use(send.realm));
+
+        // Inherited from `Entity`
+        assert_eq!(database.method("Player", "GetPos").unwrap().name, "GetPos");
+        assert!(database.method("Entity", "Nick").is_none());
+
+        assert!(database
+            .hook_table("GM")
+            .unwrap()
+            .function("PlayerSpawn")
+            .is_some());
+
+        let (e, member) = database.enum_member("MOVETYPE_NOCLIP").unwrap();
+
+        assert_eq!((e.name.as_str(), member.value), ("MOVETYPE", 8));
+
+        let globals = database.globals().collect::<Vec<_>>();
+
+        assert!(["AddCSLuaFile", "net", "IN_ATTACK"]
+            .iter()
+            .all(|name| globals.contains(name)));
+
+        let err = Database::from_json(
+            r#"{ "libraries": [{ "name": "net", "functions": [{ "name": "Start", "realm": "both" }] }] }"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, api::Error::Invalid { .. }));
+        assert_eq!(
+            err.to_string(),
+            "libraries[0].functions[0].realm: expected `client`, `server`, `shared` or `menu`"
+        );
+
+        let source = "net.Start(\"ping\")\nlocal net = {}\nnet.Start()\n";
+
+        let document = json!({ "uri": "file:///api.lua" });
+        let hover = |id: u64, line: u32, character: u32| json!({ "jsonrpc": "2.0", "id": id, "method": "textDocument/hover", "params": { "textDocument": document, "position": { "line": line, "character": character } } });
+
+        let mut server = Server::new();
+
+        server.handle(&json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": { "uri": "file:///api.lua", "languageId": "glua", "version": 1, "text": source },
+        } }));
+
+        let reply = server.handle(&hover(1, 0, 6)).remove(0);
+
+        assert_eq!(
+            reply["result"]["contents"]["value"],
+            "```lua\nfunction net.Start(messageName: string, unreliable: boolean = false) -> boolean\n```\nBegins a new net message\n\n*shared*"
+        );
+
+        // Shadowed by a local
+        let reply = server.handle(&hover(2, 2, 6)).remove(0);
+
+        assert_eq!(reply["result"], Value::Null);
+    }
+
+    #[test]
+    fn realm() {
+        assert_eq!(
+            file_realm("lua/autorun/client/hud.lua"),
+            Some(Realm::Client)
+        );
+        assert_eq!(
+            file_realm("lua\\autorun\\server\\data.lua"),
+            Some(Realm::Server)
+        );
+        assert_eq!(file_realm("lua/autorun/loader.lua"), Some(Realm::Shared));
+        assert_eq!(
+            file_realm("file:///addon/lua/entities/crate/init.lua"),
+            Some(Realm::Server)
+        );
+        assert_eq!(
+            file_realm("gamemodes/rp/gamemode/cl_init.lua"),
+            Some(Realm::Client)
+        );
+        assert_eq!(
+            file_realm("lua/weapons/gun/shared.lua"),
+            Some(Realm::Shared)
+        );
+        assert_eq!(file_realm("lua/myaddon/sv_data.lua"), Some(Realm::Server));
+        assert_eq!(file_realm("lua/myaddon/util.lua"), None);
+        assert_eq!(
+            file_realm("gamemodes/rp/entities/effects/spark.lua"),
+            Some(Realm::Client)
+        );
+        // Only directories within `lua` or `gamemodes` count
+        assert_eq!(file_realm("addons/effects/lua/myaddon/util.lua"), None);
+        assert_eq!(file_realm("vgui/menu.lua"), None);
+
+        let source = r#"if SERVER then
+    util.AddNetworkString("ping")
+    surface.DrawRect(0, 0, 1, 1)
+elseif ready then
+    net.SendToServer()
+end
+if not CLIENT and ready then vgui.Create("DFrame") end
+local function draw() end
+if CLIENT then return end
+net.Broadcast()
+draw()
+cam.Start2D()
+"#;
+
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let realms = Realms::infer(chunk, None);
+
+        let at = |text: &str| realms.realm_at(source.find(text).unwrap());
+
+        assert_eq!(at("util"), Some(Realm::Server));
+        assert_eq!(at("net.SendToServer"), Some(Realm::Client));
+        assert_eq!(at("vgui"), Some(Realm::Server));
+        assert_eq!(at("local function"), None);
+        assert_eq!(at("net.Broadcast"), Some(Realm::Server));
+
+        let config = lint::Config::default().with_globals(["ready"]);
+
+        let warnings = |realm: Option<Realm>| {
+            lint::lint(chunk, realm, &config)
+                .iter()
+                .map(|diagnostic| {
+                    let span = diagnostic.span().unwrap().clone();
+
+                    (diagnostic.code.unwrap(), &source[span])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            warnings(None),
+            [
+                ("G101", "DrawRect"),
+                ("G101", "Create"),
+                ("G101", "Start2D")
+            ]
+        );
+
+        // Only the unguarded `draw` is left to the file's realm, and it's a local
+        assert_eq!(
+            warnings(Some(Realm::Client)),
+            [
+                ("G101", "DrawRect"),
+                ("G101", "Create"),
+                ("G101", "Start2D")
+            ]
+        );
+
+        let source = "util.AddNetworkString(\"ping\")\nif SERVER then\n    surface.PlaySound(\"ping.wav\")\nend\n";
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let map = SourceMap::new(source).with_name("cl_init.lua");
+
+        let rendered = lint::lint(chunk, file_realm("cl_init.lua"), &lint::Config::default())
+            .iter()
+            .map(|diagnostic| diagnostic.render(&map, false))
+            .collect::<String>();
+
+        assert_eq!(
+            rendered,
+            "\
+warning[G102]: server-only function `util.AddNetworkString` called in client code
+ --> cl_init.lua:1:6
+  |
+1 | util.AddNetworkString(\"ping\")
+  |      ^^^^^^^^^^^^^^^^
+  |
+  = note: the file runs in the client realm
+warning[G101]: client-only function `surface.PlaySound` called in server code
+ --> cl_init.lua:3:13
+  |
+2 | if SERVER then
+  |    ------ only runs on the server because of this
+3 |     surface.PlaySound(\"ping.wav\")
+  |             ^^^^^^^^^
+  |
+"
+        );
+    }
+
+    #[test]
+    fn net() {
+        let server = r#"util.AddNetworkString("sync")
+local function sync(ply, ent)
+    net.Start("sync")
+    net.WriteEntity(ent)
+    net.WriteUInt(ent:Health(), 8)
+    if ent:IsPlayer() then
+        net.WriteString(ent:Nick())
+    end
+    net.Send(ply)
+end
+net.Receive("request", function(len, ply)
+    sync(ply, net.ReadEntity())
+end)
+net.Start("orphan")
+net.Broadcast()
+"#;
+
+        let client = r#"net.Receive("sync", function()
+    local ent = net.ReadEntity()
+    local health = net.ReadUInt(16)
+end)
+net.Start("request")
+net.WriteEntity(LocalPlayer():GetEyeTrace().Entity)
+net.WriteBool(true)
+net.SendToServer()
+net.Receive("unknown", function() end)
+"#;
+
+        let bump = Bump::new();
+
+        let files = [("sv_net.lua", server), ("cl_net.lua", client)].map(|(name, source)| {
+            let tokens = unwrap(Parser::lex(source, &bump));
+            let chunk =
+                unwrap(Parser::new_in(bump.alloc_slice_clone(&tokens), &bump).parse_chunk());
+
+            let tree = ScopeTree::build(chunk);
+            let realms = Realms::infer(chunk, file_realm(name));
+
+            (
+                SourceMap::new(source).with_name(name),
+                NetUsage::collect(chunk, &tree, &realms),
+            )
+        });
+
+        let (_, usage) = &files[0];
+
+        assert_eq!(usage.registrations.len(), 1);
+        assert_eq!(usage.sends.len(), 2);
+
+        let sync = &usage.sends[0];
+
+        assert_eq!(*sync.name, "sync");
+        assert_eq!(sync.realm, Some(Realm::Server));
+        assert!(!sync.exact);
+        assert_eq!(sync.certain, 2);
+        assert_eq!(
+            sync.values
+                .iter()
+                .map(|value| (value.kind(), value.bits))
+                .collect::<Vec<_>>(),
+            [("Entity", None), ("UInt", Some(8)), ("String", None)]
+        );
+
+        // The read is an argument to a local function called first
+        assert!(!usage.receives[0].exact);
+
+        let (_, usage) = &files[1];
+
+        assert!(usage.receives[0].exact);
+        assert!(usage.sends[0].exact);
+
+        let usages = files
+            .iter()
+            .map(|(map, usage)| (map, usage))
+            .collect::<Vec<_>>();
+
+        let diagnostics = lint::net_messages(&usages);
+
+        let warnings = |file: usize| {
+            diagnostics[file]
+                .iter()
+                .map(|diagnostic| {
+                    let span = diagnostic.span().unwrap().clone();
+
+                    (diagnostic.code.unwrap(), &files[file].0.source()[span])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            warnings(0),
+            [("G201", "\"orphan\""), ("G203", "\"orphan\"")]
+        );
+        assert_eq!(
+            warnings(1),
+            [
+                ("G204", "ReadUInt"),
+                ("G203", "\"request\""),
+                ("G202", "\"unknown\"")
+            ]
+        );
+
+        assert_eq!(
+            diagnostics[1][0].render(&files[1].0, false),
+            "\
+warning[G204]: `net.ReadUInt` reads 16 bits of a value written with 8 in net message `sync`
+ --> cl_net.lua:3:24
+  |
+3 |     local health = net.ReadUInt(16)
+  |                        ^^^^^^^^ value 2 read here
+  |
+  = note: written at sv_net.lua:5:9
+"
+        );
+
+        // Receiving fewer values than are sent
+        let source = "net.Receive(\"request\", function(len, ply) end)";
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let map = SourceMap::new(source).with_name("sv_request.lua");
+        let usage = NetUsage::collect(chunk, &ScopeTree::build(chunk), &Realms::infer(chunk, None));
+
+        let diagnostics = lint::net_messages(&[(&files[1].0, &files[1].1), (&map, &usage)]);
+
+        let unread = diagnostics[0]
+            .iter()
+            .find(|diagnostic| diagnostic.code == Some("G205"))
+            .unwrap();
+
+        assert_eq!(diagnostics[1].len(), 0);
+        assert_eq!(
+            unread.message,
+            "`net.WriteEntity` writes a value that's never read from net message `request`"
+        );
+
+        // Helpers outside of the API might read the rest of the values themselves
+        let usage = |name: &str, source: &'static str| {
+            let tokens = unwrap(Parser::lex(source, &bump));
+            let chunk =
+                unwrap(Parser::new_in(bump.alloc_slice_clone(&tokens), &bump).parse_chunk());
+
+            let realms = Realms::infer(chunk, file_realm(name));
+
+            (
+                SourceMap::new(source).with_name(name),
+                NetUsage::collect(chunk, &ScopeTree::build(chunk), &realms),
+            )
+        };
+
+        let send = usage(
+            "sv_state.lua",
+            "net.Start(\"state\")\nnet.WriteUInt(1, 8)\nnet.WriteString(\"a\")\nnet.Broadcast()",
+        );
+
+        let unread = |source: &'static str| {
+            let receive = usage("cl_state.lua", source);
+
+            assert_eq!(receive.1.receives[0].exact, source.contains("tostring"));
+
+            lint::net_messages(&[(&send.0, &send.1), (&receive.0, &receive.1)])[0]
+                .iter()
+                .any(|diagnostic| diagnostic.code == Some("G205"))
+        };
+
+        assert!(!unread(
+            "net.Receive(\"state\", function() net.ReadUInt(8) ReadState() end)"
+        ));
+        assert!(!unread(
+            "net.Receive(\"state\", function() net.ReadUInt(8) ent:ReadState() end)"
+        ));
+        assert!(!unread(
+            "net.Receive(\"state\", function() net.ReadUInt(8) MyAddon.ReadState() end)"
+        ));
+        assert!(unread(
+            "net.Receive(\"state\", function() net.ReadUInt(8) tostring(1) end)"
+        ));
+    }
+
+    #[test]
+    fn hooks() {
+        let init = r#"hook.Add("PlayerSpawnn", "crate", function(ply) end)
+hook.Add("Think", "crate", function() end)
+hook.Add("Think", "crate", function() end)
+hook.Add("CrateOpened", "crate", function(ply, crate) end)
+local hook = {}
+hook.Add("PlayerSpawnn", "crate", function() end)
+function GM:PlayerSpawn(ply) end
+function GM:playerspawn(ply) end
+function GM:GetCrates() end
+"#;
+
+        let crates = r#"hook.Run("CrateOpened", ply, crate)
+if CLIENT then
+    hook.Add("Tick", "crate", function() end)
+else
+    hook.Add("Tick", "crate", function() end)
+end
+function GAMEMODE:PlayerSpawn(ply) end
+"#;
+
+        let bump = Bump::new();
+
+        let files = [("init.lua", init), ("sh_crates.lua", crates)].map(|(name, source)| {
+            let tokens = unwrap(Parser::lex(source, &bump));
+            let chunk =
+                unwrap(Parser::new_in(bump.alloc_slice_clone(&tokens), &bump).parse_chunk());
+
+            let tree = ScopeTree::build(chunk);
+            let realms = Realms::infer(chunk, file_realm(name));
+
+            (
+                SourceMap::new(source).with_name(name),
+                Hooks::collect(chunk, &tree, &realms),
+            )
+        });
+
+        // The `hook.Add` on a local table isn't a hook
+        assert_eq!(
+            files[0]
+                .1
+                .uses
+                .iter()
+                .map(|hook| (hook.kind, *hook.name, hook.identifier.map(|id| *id)))
+                .collect::<Vec<_>>(),
+            [
+                (HookKind::Add, "PlayerSpawnn", Some("crate")),
+                (HookKind::Add, "Think", Some("crate")),
+                (HookKind::Add, "Think", Some("crate")),
+                (HookKind::Add, "CrateOpened", Some("crate")),
+                (HookKind::Gamemode, "PlayerSpawn", None),
+                (HookKind::Gamemode, "playerspawn", None),
+                (HookKind::Gamemode, "GetCrates", None),
+            ]
+        );
+        assert!(files[0].1.uses.iter().all(|hook| hook.handler.is_some()));
+        assert_eq!(files[1].1.uses[0].kind, HookKind::Run);
+
+        let hooks = files
+            .iter()
+            .map(|(map, hooks)| (map, hooks))
+            .collect::<Vec<_>>();
+
+        let diagnostics = lint::hooks(&hooks);
+
+        let warnings = |file: usize| {
+            diagnostics[file]
+                .iter()
+                .map(|diagnostic| {
+                    let span = diagnostic.span().unwrap().clone();
+
+                    (diagnostic.code.unwrap(), &files[file].0.source()[span])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            warnings(0),
+            [
+                ("G301", "\"PlayerSpawnn\""),
+                ("G302", "\"crate\""),
+                ("G301", "playerspawn")
+            ]
+        );
+        assert_eq!(warnings(1), [("G302", "PlayerSpawn")]);
+
+        assert_eq!(
+            diagnostics[0][0].render(&files[0].0, false),
+            "\
+warning[G301]: unknown hook `PlayerSpawnn`
+ --> init.lua:1:10
+  |
+1 | hook.Add(\"PlayerSpawnn\", \"crate\", function(ply) end)
+  |          ^^^^^^^^^^^^^^ did you mean `PlayerSpawn`?
+  |
+"
+        );
+        assert_eq!(
+            diagnostics[1][0].notes,
+            ["note: first defined at init.lua:7:13"]
+        );
+    }
+
+    #[test]
+    fn project() {
+        let sources = [
+            (
+                "gamemodes/crate/gamemode/init.lua",
+                "AddCSLuaFile(\"cl_init.lua\")\nAddCSLuaFile(\"shared.lua\")\ninclude(\"shared.lua\")\n",
+            ),
+            (
+                "gamemodes/crate/gamemode/cl_init.lua",
+                "include(\"shared.lua\")\ninclude(\"cl_hud.lua\")\n",
+            ),
+            (
+                "gamemodes/crate/gamemode/shared.lua",
+                "include(\"crate/gamemode/sh_items.lua\")\ninclude(file)\n",
+            ),
+            (
+                "gamemodes/crate/gamemode/sh_items.lua",
+                "AddCSLuaFile()\nIncludeCS(\"./sh_crates.lua\")\ninclude(\"sh_missing.lua\")\n",
+            ),
+            (
+                "gamemodes/crate/gamemode/sh_crates.lua",
+                "if SERVER then include(\"init.lua\") end\ninclude(\"../gamemode/sh_items.lua\")\n",
+            ),
+            ("gamemodes/crate/gamemode/cl_hud.lua", "local function include() end\ninclude(\"x.lua\")\n"),
+            (
+                "gamemodes/crate/gamemode/broken.lua",
+                "include(\"cl_hud.lua\")\ninclude(",
+            ),
+        ];
+
+        let bump = Bump::new();
+
+        let project = Project::from_sources(
+            sources.map(|(path, source)| (path.to_owned(), source)),
+            &bump,
+        );
+
+        let files = project.files();
+
+        assert_eq!(
+            project.find("gamemodes/crate/gamemode/./cl_hud.lua"),
+            Some(5)
+        );
+        assert!(files[6].chunk.is_none() && files[6].error.is_some());
+        // Recovered from the rest of the file
+        assert_eq!(files[6].dependencies[0].file, Some(5));
+        assert_eq!(files[0].realm, Some(Realm::Server));
+
+        // Dynamic paths and calls to locals aren't dependencies
+        assert_eq!(files[2].dependencies.len(), 1);
+        assert!(files[5].dependencies.is_empty());
+
+        assert_eq!(
+            files[3]
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.kind, dependency.file))
+                .collect::<Vec<_>>(),
+            [
+                (DependencyKind::AddCSLuaFile, Some(3)),
+                (DependencyKind::IncludeCS, Some(4)),
+                (DependencyKind::Include, None),
+            ]
+        );
+        assert_eq!(files[4].dependencies[0].realm, Some(Realm::Server));
+        assert_eq!(
+            project
+                .dependents(2)
+                .map(|(file, _)| file)
+                .collect::<Vec<_>>(),
+            [0, 0, 1]
+        );
+
+        assert_eq!(project.cycles(), [vec![0, 2, 3, 4], vec![3, 4]]);
+
+        let diagnostics = lint::project(&project);
+
+        let warnings = |file: usize| {
+            diagnostics[file]
+                .iter()
+                .map(|diagnostic| {
+                    let span = diagnostic.span().unwrap().clone();
+
+                    (
+                        diagnostic.code.unwrap(),
+                        &files[file].source_map.source()[span],
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(warnings(1), [("G403", "\"cl_hud.lua\"")]);
+        assert_eq!(warnings(3), [("G401", "\"sh_missing.lua\"")]);
+        assert_eq!(
+            warnings(4),
+            [
+                ("G402", "\"init.lua\""),
+                ("G402", "\"../gamemode/sh_items.lua\"")
+            ]
+        );
+        assert_eq!(
+            diagnostics[4][1].notes,
+            ["note: gamemodes/crate/gamemode/sh_items.lua -> gamemodes/crate/gamemode/sh_crates.lua -> gamemodes/crate/gamemode/sh_items.lua"]
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let mut sources = (0..50)
+            .map(|i| format!("local a{} = {}\nprint(a{})\n", i, i, i))
+            .collect::<Vec<_>>();
+
+        sources[7] = "local = 1".to_owned();
+        sources[30] = "local a = `".to_owned();
+
+        let output =
+            Batch::new()
+                .with_threads(4)
+                .parse(&sources, |index, source, chunk| match chunk {
+                    Ok(chunk) => Ok((index, chunk.len())),
+                    Err(err) => Err(Diagnostic::from_error(&err, &SourceMap::new(source)).message),
+                });
+
+        assert_eq!(output.results.len(), 50);
+        assert_eq!(output.results[0], Ok((0, 2)));
+        assert_eq!(output.results[49], Ok((49, 2)));
+        assert!(output.results[7].is_err() && output.results[30].is_err());
+
+        let stats = &output.stats;
+
+        assert_eq!(stats.threads, 4);
+        assert_eq!((stats.files, stats.failed), (50, 2));
+        assert_eq!(stats.bytes, sources.iter().map(String::len).sum::<usize>());
+        assert_eq!(
+            stats.tokens,
+            output.files.iter().map(|file| file.tokens).sum::<usize>()
+        );
+        assert_eq!(output.files[1].tokens, 8);
+        assert!(!output.files[30].parsed && output.files[30].tokens == 0);
+        assert!(output.files.iter().all(|file| file.worker < 4));
+        assert!(stats.peak_arena_bytes > 0);
+        assert!(output
+            .files
+            .iter()
+            .all(|file| (file.tokens > 0) == (file.token_bytes > 0)));
+        assert!(stats.peak_bytes > stats.peak_arena_bytes);
+
+        // More threads than files
+        let output = Batch::new()
+            .with_threads(8)
+            .parse(&sources[..3], |_, _, chunk| chunk.is_ok());
+
+        assert_eq!(output.stats.threads, 3);
+        assert_eq!(output.results, [true, true, true]);
+    }
+
+    #[test]
+    fn stream() {
+        let bump = Bump::new();
+
+        let tokens = unwrap(Parser::lex(CODE, &bump));
+        let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+        let streamed = unwrap(Parser::stream_in(CODE, &bump).parse_chunk());
+
+        assert_eq!(format!("{:?}", streamed), format!("{:?}", chunk));
+
+        // Only the tokens around the one being parsed are kept
+        let source = "local a = { 1, 2, 3 }\n".repeat(1000);
+
+        let mut parser = Parser::stream_in(&source, &bump);
+
+        assert_eq!(unwrap(parser.parse_chunk()).len(), 1000);
+        assert!(parser.peak_buffered_tokens() <= 12);
+
+        let mut parser = Parser::new_in(&tokens, &bump);
+
+        unwrap(parser.parse_chunk());
+
+        assert_eq!(parser.peak_buffered_tokens(), tokens.len());
+
+        // Even within a single statement, including ones that are rewound
+        let table = (0..1000)
+            .map(|i| format!("[{}] = {{ {} }}", i, i))
+            .collect::<Vec<_>>();
+
+        for source in [
+            format!("return {{ {} }}", table.join(", ")),
+            format!(
+                "f({{ {} }}).a = 1\nf({{ {} }})",
+                table.join(", "),
+                table.join(", ")
+            ),
+        ] {
+            let tokens = unwrap(Parser::lex(&source, &bump));
+            let chunk = unwrap(Parser::new_in(&tokens, &bump).parse_chunk());
+
+            let mut parser = Parser::stream_in(&source, &bump);
+
+            assert_eq!(
+                format!("{:?}", unwrap(parser.parse_chunk())),
+                format!("{:?}", chunk)
+            );
+            assert!(parser.peak_buffered_tokens() <= 12);
+        }
+
+        // An unrecognised token ends the stream, but is still reported
+        let source = "local a = 1\nlocal b = `\n";
+
+        assert!(matches!(
+            Parser::stream_in(source, &bump).parse_chunk(),
+            Err(Error::Lexer(span)) if span == (22..23)
+        ));
+
+        let (chunk, errors) = Parser::stream_in(source, &bump).parse_chunk_recovering();
+
+        assert_eq!(chunk.len(), 2);
+        assert!(matches!(errors.last(), Some(Error::Lexer(_))));
+
+        // Syntax errors are the same as when lexing up front
+        let source = "if a then\n    b(\nend";
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+
+        let err = Parser::new_in(&tokens, &bump).parse_chunk().unwrap_err();
+        let streamed = Parser::stream_in(source, &bump).parse_chunk().unwrap_err();
+
+        assert_eq!(format!("{:?}", streamed), format!("{:?}", err));
+    }
+
+    #[test]
+    fn lsp() {
+        let source =
+            "local a = 1\nfunction f(x)\n    local y = x + a\n    return y\nend\nprint(f(a), g)\n";
+
+        let document = json!({ "uri": "file:///test.lua" });
+        let at = |line: u32, character: u32| json!({ "textDocument": document, "position": { "line": line, "character": character } });
+
+        let messages = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+                "initializationOptions": { "globals": ["f", "g"] },
+            } }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+                "textDocument": { "uri": "file:///test.lua", "languageId": "glua", "version": 1, "text": source },
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/documentSymbol", "params": { "textDocument": document } }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "textDocument/foldingRange", "params": { "textDocument": document } }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/definition", "params": at(5, 8) }),
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "textDocument/hover", "params": at(2, 18) }),
+            json!({ "jsonrpc": "2.0", "id": 6, "method": "textDocument/hover", "params": at(5, 12) }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": { "uri": "file:///test.lua", "version": 2 },
+                "contentChanges": [{
+                    "range": { "start": { "line": 4, "character": 0 }, "end": { "line": 4, "character": 3 } },
+                    "text": "",
+                }],
+            } }),
+            // Putting the `end` back, then moving everything after the first line down one
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": { "uri": "file:///test.lua", "version": 3 },
+                "contentChanges": [{
+                    "range": { "start": { "line": 4, "character": 0 }, "end": { "line": 4, "character": 0 } },
+                    "text": "end",
+                }, {
+                    "range": { "start": { "line": 0, "character": 11 }, "end": { "line": 0, "character": 11 } },
+                    "text": "\n",
+                }],
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 9, "method": "textDocument/documentSymbol", "params": { "textDocument": document } }),
+            json!({ "jsonrpc": "2.0", "id": 10, "method": "textDocument/hover", "params": at(6, 12) }),
+            // An unrecognised token is reported without losing the rest of the document
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": { "uri": "file:///test.lua", "version": 4 },
+                "contentChanges": [{
+                    "range": { "start": { "line": 6, "character": 0 }, "end": { "line": 6, "character": 0 } },
+                    "text": "$",
+                }],
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 11, "method": "textDocument/documentSymbol", "params": { "textDocument": document } }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": { "uri": "file:///test.lua", "version": 5 },
+                "contentChanges": [{
+                    "range": { "start": { "line": 7, "character": 0 }, "end": { "line": 7, "character": 0 } },
+                    "text": "net.S",
+                }],
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 12, "method": "textDocument/completion", "params": at(7, 5) }),
+            json!({ "jsonrpc": "2.0", "id": 13, "method": "textDocument/completion", "params": at(7, 2) }),
+            json!({ "jsonrpc": "2.0", "id": 7, "method": "textDocument/unknown", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 8, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ];
+
+        let mut input = Vec::new();
+
+        for message in &messages {
+            lsp::write_message(&mut input, message).unwrap();
+        }
+
+        let mut output = Vec::new();
+
+        assert!(Server::new().run(Cursor::new(input), &mut output).unwrap());
+
+        let mut output = Cursor::new(output);
+        let mut replies = Vec::new();
+
+        while let Some(reply) = lsp::read_message(&mut output).unwrap() {
+            replies.push(reply);
+        }
+
+        let result = |id: u64| {
+            let reply = replies.iter().find(|reply| reply["id"] == id).unwrap();
+
+            reply.get("result").unwrap_or(&reply["error"]).clone()
+        };
+
+        let diagnostics = replies
+            .iter()
+            .filter(|reply| reply["method"] == "textDocument/publishDiagnostics")
+            .map(|reply| {
+                reply["params"]["diagnostics"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|diagnostic| diagnostic["message"].as_str().unwrap().to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // Removing the `end` leaves `function` unclosed
+        assert_eq!(diagnostics.len(), 5);
+        assert!(diagnostics[0].is_empty());
+        assert!(!diagnostics[1].is_empty());
+        assert!(diagnostics[2].is_empty());
+        assert_eq!(diagnostics[3], ["Unrecognised token in input"]);
+
+        assert_eq!(result(1)["capabilities"]["hoverProvider"], true);
+
+        let symbols = result(2);
+        let names = |symbols: &Value| {
+            symbols
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|symbol| symbol["name"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&symbols), ["a", "f"]);
+        assert_eq!(names(&symbols[1]["children"]), ["y"]);
+
+        assert_eq!(result(3), json!([{ "startLine": 1, "endLine": 3 }]));
+
+        assert_eq!(
+            result(4),
+            json!({
+                "uri": "file:///test.lua",
+                "range": { "start": { "line": 0, "character": 6 }, "end": { "line": 0, "character": 7 } },
+            })
+        );
+
+        assert_eq!(result(5)["contents"]["value"], "```lua\nlocal a = 1\n```");
+        assert_eq!(result(6)["contents"]["value"], "global `g`");
+
+        let symbols = result(9);
+
+        assert_eq!(names(&symbols), ["a", "f"]);
+        assert_eq!(symbols[1]["range"]["start"]["line"], 2);
+        assert_eq!(result(10)["contents"]["value"], "global `g`");
+
+        assert_eq!(names(&result(11)), ["a", "f"]);
+
+        let labels = |id: u64| {
+            result(id)
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["label"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(labels(12).iter().any(|label| label == "SendToServer"));
+        assert!(!labels(12).iter().any(|label| label == "net"));
+        assert!(["AddCSLuaFile", "net", "MOVETYPE_NOCLIP"]
+            .iter()
+            .all(|name| labels(13).iter().any(|label| label == name)));
+        assert_eq!(
+            result(1)["capabilities"]["completionProvider"]["triggerCharacters"],
+            json!(["."])
+        );
+
+        assert_eq!(result(7)["code"], -32601);
+        assert_eq!(result(8), Value::Null);
+    }
+
+    #[test]
+    fn recovery() {
+        let bump = Bump::new();
+
+        let source = "local a = \nlocal function f()\n  x = )\n  local y = 1\nend\nif a then b() end end\nlocal c = {";
+
+        let tokens = unwrap(Parser::lex(source, &bump));
+
+        let (chunk, errors) = Parser::new_in(&tokens, &bump).parse_chunk_recovering();
+
+        let map = SourceMap::new(source);
+
+        let errors = errors
+            .iter()
+            .map(|e| e.display_with(&map).to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            [
+                "2:1: Unexpected `local`, expecting expression",
+                "3:7: Unexpected `)`, expecting expression",
+                "6:19: Unexpected `end`, expecting end of file",
+                "7:12: Unexpected EOF",
+            ]
+        );
+
+        let stats = chunk.iter().map(|s| &source[s.span()]).collect::<Vec<_>>();
+
+        assert_eq!(
+            stats,
+            [
+                "local a =",
+                "local function f()\n  x = )\n  local y = 1\nend",
+                "if a then b() end",
+                "end",
+                "local c = {"
+            ]
+        );
+
+        assert!(matches!(*chunk[0], Stat::Error));
+
+        let Stat::FunctionDef(def) = *chunk[1] else {
+            panic!("expected a function definition, got {:?}", chunk[1]);
+        };
+
+        assert!(matches!(*def.body.body[0], Stat::Error));
+        assert!(matches!(*def.body.body[1], Stat::VarDef(_)));
+    }
+
+    #[test]
+    fn format() {
+        let source =
+            "-- header\n\n\nlocal t = {a=1, -- first\n  'b' ; }\nif x then /* c */ f( a,b ) end\n";
+
+        let bump = Bump::new();
+
+        let formatted = unwrap(formatter::format(source, &bump, &Config::default()));
+
+        assert_eq!(
+            formatted,
+            "-- header\n\nlocal t = {\n    a = 1, -- first\n    \"b\",\n}\nif x then --[[ c ]]\n    f(a, b)\nend\n"
+        );
+
+        let config = Config {
+            quote_style: QuoteStyle::AlwaysSingle,
+            operator_style: OperatorStyle::C,
+            operator_spacing: false,
+            trailing_separator: TrailingSeparator::Never,
+            ..Config::default()
+        };
+
+        for config in [Config::default(), config] {
+            let formatted = unwrap(formatter::format(CODE, &bump, &config));
+
+            assert_eq!(
+                unwrap(formatter::format(&formatted, &bump, &config)),
+                formatted
+            );
+
+            let comments = |source| unwrap(Parser::lex_with_comments(source, &bump)).1.len();
+
+            assert_eq!(comments(&formatted), comments(CODE));
+        }
+
+        // An empty `return` loses its `;`, so has to parse at the end of the chunk
+        for source in ["return;", "x = 1 return;"] {
+            let formatted = unwrap(formatter::format(source, &bump, &Config::default()));
+
+            assert_eq!(
+                unwrap(formatter::format(&formatted, &bump, &Config::default())),
+                formatted
+            );
+        }
+    }
+
     fn unwrap<T>(res: Result<T, Error>) -> T {
         match res {
             Err(err) => match err {
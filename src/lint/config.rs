@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use crate::{api::Database, lint::stdlib};
+
+/// Options controlling what the linter reports.
+///
+/// Names ending in `*` allow every global starting with the rest, e.g. `MOVETYPE_*`.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Globals that can be read and assigned, e.g. ones shared between the files of an addon
+    pub globals: HashSet<String>,
+    /// Globals that can be read but not assigned, by default the Lua standard library along with
+    /// everything in [`stdlib::GMOD`] and the bundled API definitions
+    pub read_globals: HashSet<String>,
+}
+
+impl Config {
+    /// A config allowing no globals at all, not even the standard library
+    pub fn empty() -> Self {
+        Self {
+            globals: HashSet::new(),
+            read_globals: HashSet::new(),
+        }
+    }
+
+    pub fn with_globals<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.globals.extend(names.into_iter().map(Into::into));
+
+        self
+    }
+
+    pub fn with_read_globals<S: Into<String>>(
+        mut self,
+        names: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.read_globals.extend(names.into_iter().map(Into::into));
+
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let builtin = stdlib::LUA.iter().chain(stdlib::GMOD).copied();
+        let database = Database::bundled();
+
+        // Enums are only partly defined, so allow every member sharing their prefix
+        let enums = database.enums.iter().map(|e| format!("{}_*", e.name));
+
+        Self::empty()
+            .with_read_globals(builtin)
+            .with_read_globals(database.globals())
+            .with_read_globals(enums)
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+
+use crate::{
+    analysis::scope::{Access, ScopeTree},
+    diagnostic::Diagnostic,
+    lint::Config,
+};
+
+/// Report assignments to globals that aren't in `config.globals` and reads of globals that aren't
+/// allowed at all. Reads of globals the chunk assigns itself are left to the warning on the
+/// assignment.
+pub(super) fn check(tree: &ScopeTree, config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let globals = Matcher::new(&config.globals);
+    let read_globals = Matcher::new(&config.read_globals);
+
+    let assigned = tree
+        .globals()
+        .filter(|reference| reference.access == Access::Write)
+        .map(|reference| *reference.name)
+        .collect::<HashSet<_>>();
+
+    for reference in tree.globals() {
+        let name = *reference.name;
+
+        if globals.matches(name) {
+            continue;
+        }
+
+        let (code, message, label) = match reference.access {
+            Access::Write if read_globals.matches(name) => (
+                "W121",
+                format!("setting read-only global variable `{}`", name),
+                None,
+            ),
+            Access::Write => (
+                "W111",
+                format!("setting non-standard global variable `{}`", name),
+                Some(format!("did you mean `local {}`?", name)),
+            ),
+            Access::Read if read_globals.matches(name) || assigned.contains(name) => continue,
+            Access::Read => (
+                "W113",
+                format!("accessing undefined variable `{}`", name),
+                None,
+            ),
+        };
+
+        diagnostics.push(
+            Diagnostic::warning(message)
+                .with_code(code)
+                .with_label(reference.name.span(), label),
+        );
+    }
+}
+
+/// A set of global names, some of which may be prefixes ending in `*`
+struct Matcher<'c> {
+    names: &'c HashSet<String>,
+    prefixes: Vec<&'c str>,
+}
+
+impl<'c> Matcher<'c> {
+    fn new(names: &'c HashSet<String>) -> Self {
+        Self {
+            names,
+            prefixes: names
+                .iter()
+                .filter_map(|name| name.strip_suffix('*'))
+                .collect(),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.names.contains(name) || self.prefixes.iter().any(|prefix| name.starts_with(prefix))
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use crate::{
+    analysis::hooks::{HookKind, HookUse, Hooks},
+    api::Database,
+    diagnostic::Diagnostic,
+    source_map::SourceMap,
+};
+
+/// Check the hooks of a set of files against the API database and each other, returning the
+/// warnings for each file in the same order
+pub(super) fn check(files: &[(&SourceMap, &Hooks)]) -> Vec<Vec<Diagnostic>> {
+    let mut diagnostics = vec![Vec::new(); files.len()];
+
+    let known = Database::bundled()
+        .hook_table("GM")
+        .map(|table| &table.functions[..])
+        .unwrap_or_default();
+
+    // Hooks run by the files themselves, which can be added without being in the database
+    let custom = files
+        .iter()
+        .flat_map(|(_, hooks)| &hooks.uses)
+        .filter(|hook| hook.kind == HookKind::Run)
+        .map(|hook| *hook.name)
+        .collect::<HashSet<_>>();
+
+    let uses = files
+        .iter()
+        .enumerate()
+        .flat_map(|(file, (_, hooks))| hooks.uses.iter().map(move |hook| (file, hook)))
+        .collect::<Vec<_>>();
+
+    for &(file, hook) in &uses {
+        let name = *hook.name;
+
+        if hook.kind == HookKind::Run
+            || custom.contains(name)
+            || known.iter().any(|function| function.name == name)
+        {
+            continue;
+        }
+
+        // Other names are likely hooks from another addon, or helper methods on the gamemode
+        let Some(suggestion) = closest(name, known.iter().map(|function| &*function.name)) else {
+            continue;
+        };
+
+        diagnostics[file].push(
+            Diagnostic::warning(format!("unknown hook `{}`", name))
+                .with_code("G301")
+                .with_label(hook.name.span(), format!("did you mean `{}`?", suggestion)),
+        );
+    }
+
+    for (i, &(file, hook)) in uses.iter().enumerate() {
+        let Some(&(first_file, first)) = uses[..i]
+            .iter()
+            .find(|(_, earlier)| replaces(earlier, hook))
+        else {
+            continue;
+        };
+
+        let message = match hook.identifier {
+            Some(identifier) => format!(
+                "hook `{}` is added again with the identifier `{}`, replacing the earlier handler",
+                *hook.name, *identifier
+            ),
+            None => format!(
+                "gamemode method `{}` is defined again, replacing the earlier definition",
+                *hook.name
+            ),
+        };
+
+        let span = hook.identifier.unwrap_or(hook.name).span();
+        let first_span = first.identifier.unwrap_or(first.name).span();
+
+        let map = files[first_file].0;
+        let location = map.location(first_span.start);
+
+        let verb = match hook.kind {
+            HookKind::Gamemode => "defined",
+            _ => "added",
+        };
+
+        let note = match map.name() {
+            Some(name) => format!("note: first {} at {}:{}", verb, name, location),
+            None => format!("note: first {} at {}", verb, location),
+        };
+
+        diagnostics[file].push(
+            Diagnostic::warning(message)
+                .with_code("G302")
+                .with_label(span, None)
+                .with_note(note),
+        );
+    }
+
+    for diagnostics in &mut diagnostics {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span().map(|span| span.start));
+    }
+
+    diagnostics
+}
+
+/// Whether `later` silently replaces the handler of `earlier`: both add the same hook with the
+/// same identifier, or both define the same gamemode method, and can run in the same realm
+fn replaces(earlier: &HookUse, later: &HookUse) -> bool {
+    let same = match (earlier.kind, later.kind) {
+        (HookKind::Add, HookKind::Add) => {
+            matches!((earlier.identifier, later.identifier), (Some(a), Some(b)) if *a == *b)
+        }
+        (HookKind::Gamemode, HookKind::Gamemode) => true,
+        _ => false,
+    };
+
+    let overlap = match (earlier.realm, later.realm) {
+        (Some(a), Some(b)) => a.can_use(b) || b.can_use(a),
+        _ => true,
+    };
+
+    same && overlap && *earlier.name == *later.name
+}
+
+/// The candidate most similar to `name`, if any is close enough to be a typo of it
+fn closest<'c>(name: &str, candidates: impl Iterator<Item = &'c str>) -> Option<&'c str> {
+    let threshold = (name.len() / 4).max(1);
+
+    candidates
+        .map(|candidate| {
+            let distance = match candidate.eq_ignore_ascii_case(name) {
+                true => 0,
+                false => distance(name, candidate),
+            };
+
+            (distance, candidate)
+        })
+        .filter(|&(distance, _)| distance <= threshold)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
@@ -0,0 +1,92 @@
+use crate::{
+    api::Realm,
+    diagnostic::Diagnostic,
+    project::{DependencyKind, Project},
+};
+
+/// Check the includes of a project, returning the warnings for each file in the same order
+pub(super) fn check(project: &Project) -> Vec<Vec<Diagnostic>> {
+    let files = project.files();
+
+    let mut diagnostics = vec![Vec::new(); files.len()];
+
+    for (index, file) in files.iter().enumerate() {
+        for dependency in &file.dependencies {
+            let Some(path) = dependency.path else {
+                continue;
+            };
+
+            let Some(target) = dependency.file else {
+                diagnostics[index].push(
+                    Diagnostic::warning(format!("`{}` isn't in the project", *path))
+                        .with_code("G401")
+                        .with_label(path.span(), "file not found".to_owned()),
+                );
+
+                continue;
+            };
+
+            if dependency.kind != DependencyKind::Include {
+                continue;
+            }
+
+            // Run by clients if the include is, or if the included file runs there when that's
+            // unknown
+            let client = match dependency.realm.or(files[target].realm) {
+                Some(realm) => Realm::Client.can_use(realm),
+                None => false,
+            };
+
+            let sent = project
+                .dependents(target)
+                .any(|(_, dependent)| dependent.kind.sends());
+
+            if client && !sent {
+                diagnostics[index].push(
+                    Diagnostic::warning(format!(
+                        "`{}` is included on the client but never sent with `AddCSLuaFile`",
+                        *path
+                    ))
+                    .with_code("G403")
+                    .with_label(path.span(), None),
+                );
+            }
+        }
+    }
+
+    for cycle in project.cycles() {
+        // Reported at the include that closes the cycle
+        let (&last, &first) = (cycle.last().unwrap(), cycle.first().unwrap());
+
+        let Some(dependency) = files[last]
+            .dependencies
+            .iter()
+            .find(|dependency| dependency.kind.includes() && dependency.file == Some(first))
+        else {
+            continue;
+        };
+
+        let chain = cycle
+            .iter()
+            .chain([&first])
+            .map(|&index| files[index].path.as_str())
+            .collect::<Vec<_>>();
+
+        let span = dependency
+            .path
+            .map_or(dependency.span.clone(), |path| path.span());
+
+        diagnostics[last].push(
+            Diagnostic::warning("files include each other in a cycle")
+                .with_code("G402")
+                .with_label(span, None)
+                .with_note(format!("note: {}", chain.join(" -> "))),
+        );
+    }
+
+    for diagnostics in &mut diagnostics {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span().map(|span| span.start));
+    }
+
+    diagnostics
+}
@@ -0,0 +1,98 @@
+//! Checks for likely mistakes, reported as warnings with the same codes as luacheck where it has
+//! an equivalent:
+//!
+//! - `W111` setting a global that isn't allowed by the [`Config`]
+//! - `W113` reading a global that isn't allowed by the [`Config`]
+//! - `W121` setting a read-only global, e.g. a standard library
+//! - `W211` unused local variable or function
+//! - `W212` unused argument
+//! - `W213` unused loop variable
+//!
+//! And Garry's Mod specific warnings:
+//!
+//! - `G101` client-only function called in code that runs on the server
+//! - `G102` server-only function called in code that runs on the client
+//! - `G103` menu-only function called outside of the menu
+//!
+//! And warnings about the net messages of a set of files, from [`net_messages`]:
+//!
+//! - `G201` net message sent but never received
+//! - `G202` net message received but never sent
+//! - `G203` net message never registered with `util.AddNetworkString`
+//! - `G204` value read as a different type or size than it was written
+//! - `G205` more values read than written, or written than read
+//!
+//! And warnings about the hooks of a set of files, from [`hooks`]:
+//!
+//! - `G301` hook name that isn't in the API database but is close to one that is
+//! - `G302` hook handler added with the same identifier as an earlier one, or gamemode method
+//!   defined again, replacing it
+//!
+//! And warnings about the includes of a [`Project`], from [`project`]:
+//!
+//! - `G401` file included or sent with `AddCSLuaFile` that isn't in the project
+//! - `G402` files including each other in a cycle
+//! - `G403` file included on the client but never sent with `AddCSLuaFile`
+
+pub use self::config::Config;
+
+use crate::{
+    analysis::{hooks::Hooks, net::NetUsage, realm::Realms, scope::ScopeTree},
+    api::Realm,
+    ast::Block,
+    diagnostic::Diagnostic,
+    project::Project,
+    source_map::SourceMap,
+};
+
+mod config;
+mod globals;
+mod hooks;
+mod includes;
+mod net;
+mod realm;
+pub mod stdlib;
+mod unused;
+
+/// Lint `chunk`, which runs in `realm` if known, returning warnings in source order.
+///
+/// The realm of a file can usually be found from its path with
+/// [`file_realm`](crate::analysis::realm::file_realm).
+pub fn lint(chunk: Block, realm: Option<Realm>, config: &Config) -> Vec<Diagnostic> {
+    let tree = ScopeTree::build(chunk);
+    let realms = Realms::infer(chunk, realm);
+
+    let mut diagnostics = Vec::new();
+
+    globals::check(&tree, config, &mut diagnostics);
+    realm::check(chunk, &tree, &realms, &mut diagnostics);
+    unused::check(&tree, &mut diagnostics);
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span().map(|span| span.start));
+
+    diagnostics
+}
+
+/// Check the net messages sent and received by a set of files against each other, returning the
+/// warnings for each file in the same order.
+///
+/// Messages sent with no receiver in any of the files are reported, so the set should be a whole
+/// project.
+pub fn net_messages(files: &[(&SourceMap, &NetUsage)]) -> Vec<Vec<Diagnostic>> {
+    net::check(files)
+}
+
+/// Check the hooks used by a set of files against the API database and each other, returning the
+/// warnings for each file in the same order.
+///
+/// Hooks run with `hook.Run` or `hook.Call` by any of the files are known, and only unknown names
+/// close to a known one are reported, since the rest are likely run by another addon.
+pub fn hooks(files: &[(&SourceMap, &Hooks)]) -> Vec<Vec<Diagnostic>> {
+    hooks::check(files)
+}
+
+/// Check the files included and sent by a project, returning the warnings for each file in the
+/// same order as [`Project::files`]
+pub fn project(project: &Project) -> Vec<Vec<Diagnostic>> {
+    includes::check(project)
+}
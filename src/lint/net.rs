@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+
+use crate::{
+    analysis::net::{NetMessage, NetUsage, NetValue},
+    api::Realm,
+    diagnostic::Diagnostic,
+    source_map::SourceMap,
+};
+
+/// A message in one of the files being checked
+type Located<'f, 'a> = (usize, &'f NetMessage<'a>);
+
+/// Check the net messages of a set of files against each other, returning the warnings for each
+/// file in the same order
+pub(super) fn check(files: &[(&SourceMap, &NetUsage)]) -> Vec<Vec<Diagnostic>> {
+    let mut diagnostics = vec![Vec::new(); files.len()];
+
+    let registered = files
+        .iter()
+        .flat_map(|(_, usage)| &usage.registrations)
+        .map(|name| **name)
+        .collect::<HashSet<_>>();
+
+    let sends = located(files, |usage| &usage.sends);
+    let receives = located(files, |usage| &usage.receives);
+
+    for &(file, send) in &sends {
+        let name = *send.name;
+
+        if !receives.iter().any(|(_, receive)| *receive.name == name) {
+            diagnostics[file].push(
+                Diagnostic::warning(format!("net message `{}` is sent but never received", name))
+                    .with_code("G201")
+                    .with_label(send.name.span(), None),
+            );
+        }
+
+        if !registered.contains(name) {
+            diagnostics[file].push(
+                Diagnostic::warning(format!(
+                    "net message `{}` is never registered with `util.AddNetworkString`",
+                    name
+                ))
+                .with_code("G203")
+                .with_label(send.name.span(), None),
+            );
+        }
+    }
+
+    for &(file, receive) in &receives {
+        let name = *receive.name;
+
+        if !sends.iter().any(|(_, send)| *send.name == name) {
+            diagnostics[file].push(
+                Diagnostic::warning(format!("net message `{}` is received but never sent", name))
+                    .with_code("G202")
+                    .with_label(receive.name.span(), None),
+            );
+        }
+    }
+
+    // Several senders of a message often write the same values, so only report each mismatch once
+    let mut reported = HashSet::new();
+
+    for &send in &sends {
+        for &receive in &receives {
+            if *send.1.name != *receive.1.name || !can_pair(send.1, receive.1) {
+                continue;
+            }
+
+            if let Some((file, diagnostic)) = compare(files, send, receive) {
+                let span = diagnostic.span().cloned();
+
+                if reported.insert((file, span, diagnostic.code)) {
+                    diagnostics[file].push(diagnostic);
+                }
+            }
+        }
+    }
+
+    for diagnostics in &mut diagnostics {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span().map(|span| span.start));
+    }
+
+    diagnostics
+}
+
+fn located<'f, 'a>(
+    files: &'f [(&SourceMap, &'f NetUsage<'a>)],
+    messages: impl Fn(&'f NetUsage<'a>) -> &'f Vec<NetMessage<'a>>,
+) -> Vec<Located<'f, 'a>> {
+    files
+        .iter()
+        .enumerate()
+        .flat_map(|(file, (_, usage))| messages(usage).iter().map(move |message| (file, message)))
+        .collect()
+}
+
+/// Whether a message sent by `send` could be received by `receive`, i.e. they aren't known to be
+/// on the same side
+fn can_pair(send: &NetMessage, receive: &NetMessage) -> bool {
+    !matches!(
+        (send.realm, receive.realm),
+        (Some(Realm::Client), Some(Realm::Client)) | (Some(Realm::Server), Some(Realm::Server))
+    )
+}
+
+/// Compare the values written by `send` with those read by `receive`, returning the first
+/// mismatch. Only the values certain on both sides are compared, and their counts only if every
+/// value is.
+fn compare(
+    files: &[(&SourceMap, &NetUsage)],
+    (send_file, send): Located,
+    (receive_file, receive): Located,
+) -> Option<(usize, Diagnostic)> {
+    let written_at = |value: &NetValue| {
+        let map = files[send_file].0;
+        let location = map.location(value.function.span().start);
+
+        match map.name() {
+            Some(name) => format!("note: written at {}:{}", name, location),
+            None => format!("note: written at {}", location),
+        }
+    };
+
+    let name = *receive.name;
+
+    let certain = receive.values.iter().take(receive.certain);
+
+    for (i, read) in certain.enumerate() {
+        let Some(write) = send.values.get(i) else {
+            if !send.exact {
+                return None;
+            }
+
+            let diagnostic = Diagnostic::warning(format!(
+                "`net.{}` reads past the end of net message `{}`",
+                *read.function, name
+            ))
+            .with_code("G205")
+            .with_label(
+                read.function.span(),
+                format!("only {} values are written", send.values.len()),
+            );
+
+            return Some((receive_file, diagnostic));
+        };
+
+        if i >= send.certain {
+            return None;
+        }
+
+        let message = if read.kind() != write.kind() {
+            format!(
+                "`net.{}` reads a value written by `net.{}` in net message `{}`",
+                *read.function, *write.function, name
+            )
+        } else {
+            match (read.bits, write.bits) {
+                (Some(read_bits), Some(write_bits)) if read_bits != write_bits => format!(
+                    "`net.{}` reads {} bits of a value written with {} in net message `{}`",
+                    *read.function, read_bits, write_bits, name
+                ),
+                _ => continue,
+            }
+        };
+
+        let diagnostic = Diagnostic::warning(message)
+            .with_code("G204")
+            .with_label(read.function.span(), format!("value {} read here", i + 1))
+            .with_note(written_at(write));
+
+        return Some((receive_file, diagnostic));
+    }
+
+    if !send.exact || !receive.exact {
+        return None;
+    }
+
+    let unread = send.values.get(receive.values.len())?;
+
+    let diagnostic = Diagnostic::warning(format!(
+        "`net.{}` writes a value that's never read from net message `{}`",
+        *unread.function, name
+    ))
+    .with_code("G205")
+    .with_label(
+        unread.function.span(),
+        format!("only {} values are read", receive.values.len()),
+    );
+
+    Some((send_file, diagnostic))
+}
@@ -0,0 +1,123 @@
+use crate::{
+    analysis::{
+        realm::Realms,
+        scope::{Resolution, ScopeTree},
+    },
+    api::{Database, Function, Realm},
+    ast::{
+        exps::FunctionCall,
+        node::Node,
+        visitors::listener::{traverse, Ancestors, Listener},
+        Block, Exp, Name,
+    },
+    diagnostic::Diagnostic,
+};
+
+/// Report calls to API functions that aren't available in the realm the call runs in
+pub(super) fn check(
+    chunk: Block,
+    tree: &ScopeTree,
+    realms: &Realms,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut calls = Calls {
+        tree,
+        database: Database::bundled(),
+        found: Vec::new(),
+    };
+
+    traverse(&mut calls, chunk);
+
+    for (name, qualified, function) in calls.found {
+        let span = name.span();
+
+        let Some(realm) = realms.realm_at(span.start) else {
+            continue;
+        };
+
+        if realm.can_use(function.realm) {
+            continue;
+        }
+
+        let code = match function.realm {
+            Realm::Client => "G101",
+            Realm::Server => "G102",
+            Realm::Menu | Realm::Shared => "G103",
+        };
+
+        let mut diagnostic = Diagnostic::warning(format!(
+            "{}-only function `{}` called in {} code",
+            function.realm, qualified, realm
+        ))
+        .with_code(code)
+        .with_label(span, None);
+
+        diagnostic = match realms.region_at(name.span().start) {
+            Some(region) => diagnostic.with_secondary_label(
+                region.guard.clone(),
+                format!("only runs on the {} because of this", region.realm),
+            ),
+            None => diagnostic.with_note(format!("note: the file runs in the {} realm", realm)),
+        };
+
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Calls to global and library functions in the API database, with the name of the function
+/// called, its qualified name and its definition
+struct Calls<'a, 't> {
+    tree: &'t ScopeTree<'a>,
+    database: &'static Database,
+    found: Vec<(Name<'a>, String, &'static Function)>,
+}
+
+impl<'a> Calls<'a, '_> {
+    /// Whether `name` refers to a global rather than a local shadowing it
+    fn is_global(&self, name: &Name) -> bool {
+        self.tree
+            .reference_at(name.span().start)
+            .is_some_and(|reference| reference.resolution == Resolution::Global)
+    }
+}
+
+impl<'a> Listener<'a> for Calls<'a, '_> {
+    fn enter_function_call(
+        &mut self,
+        call: &Node<'a, &'a FunctionCall<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        match **call.lhs {
+            Exp::Ref(name) => {
+                let name = Node::morph(&call.lhs, name);
+
+                if let Some(function) = self.database.function(*name) {
+                    if self.is_global(&name) {
+                        self.found.push((name, name.to_string(), function));
+                    }
+                }
+            }
+            Exp::Member(member) => {
+                let Exp::Ref(library) = **member.lhs else {
+                    return;
+                };
+
+                let library = Node::morph(&member.lhs, library);
+
+                let function = self
+                    .database
+                    .library(*library)
+                    .and_then(|library| library.function(*member.name));
+
+                if let Some(function) = function {
+                    if self.is_global(&library) {
+                        let qualified = format!("{}.{}", *library, *member.name);
+
+                        self.found.push((member.name, qualified, function));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Built-in definitions of the globals provided by the environment that aren't part of the API
+//! definitions in [`crate::api`].
+
+/// The Lua 5.1 standard library, as available in LuaJIT
+pub const LUA: &[&str] = &[
+    "_G",
+    "_VERSION",
+    "assert",
+    "bit",
+    "collectgarbage",
+    "coroutine",
+    "debug",
+    "dofile",
+    "error",
+    "gcinfo",
+    "getfenv",
+    "getmetatable",
+    "io",
+    "ipairs",
+    "jit",
+    "load",
+    "loadfile",
+    "loadstring",
+    "math",
+    "module",
+    "newproxy",
+    "next",
+    "os",
+    "package",
+    "pairs",
+    "pcall",
+    "print",
+    "rawequal",
+    "rawget",
+    "rawlen",
+    "rawset",
+    "require",
+    "select",
+    "setfenv",
+    "setmetatable",
+    "string",
+    "table",
+    "tonumber",
+    "tostring",
+    "type",
+    "unpack",
+    "xpcall",
+];
+
+/// Globals added by Garry's Mod that aren't functions, libraries or enums, so can't be expressed in
+/// the API definitions
+pub const GMOD: &[&str] = &[
+    // Realms
+    "CLIENT",
+    "SERVER",
+    "MENU_DLL",
+    "GAMEMODE_NAME",
+    "VERSION",
+    "VERSIONSTR",
+    "BRANCH",
+    "NULL",
+    // Scripted classes being defined by the file
+    "ENT",
+    "SWEP",
+    "EFFECT",
+    "TOOL",
+    "GM",
+    "GAMEMODE",
+];
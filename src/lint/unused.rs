@@ -0,0 +1,45 @@
+use crate::{
+    analysis::scope::{Access, BindingKind, ScopeTree},
+    diagnostic::Diagnostic,
+};
+
+/// Report locals, parameters and loop variables that are never read, ignoring any starting with
+/// `_`
+pub(super) fn check(tree: &ScopeTree, diagnostics: &mut Vec<Diagnostic>) {
+    for (_, binding) in tree.bindings() {
+        let name = *binding.name;
+
+        if name.starts_with('_') {
+            continue;
+        }
+
+        let read = binding
+            .references
+            .iter()
+            .any(|&reference| tree.reference(reference).access == Access::Read);
+
+        if read {
+            continue;
+        }
+
+        let (code, message) = match binding.kind {
+            BindingKind::Local => ("W211", format!("unused variable `{}`", name)),
+            BindingKind::LocalFunction => ("W211", format!("unused function `{}`", name)),
+            BindingKind::Param => ("W212", format!("unused argument `{}`", name)),
+            BindingKind::ForVar | BindingKind::ForInVar => {
+                ("W213", format!("unused loop variable `{}`", name))
+            }
+            // Implicit, so there's nothing to rename or remove
+            BindingKind::SelfParam => continue,
+        };
+
+        // Any references are assignments
+        let label = (!binding.references.is_empty()).then(|| "only ever assigned to".to_owned());
+
+        diagnostics.push(
+            Diagnostic::warning(message)
+                .with_code(code)
+                .with_label(binding.name.span(), label),
+        );
+    }
+}
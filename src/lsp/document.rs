@@ -0,0 +1,132 @@
+use bumpalo::Bump;
+use logos::Span;
+
+use crate::{
+    ast::Block,
+    parser::{
+        incremental::{Edit, EditError, Parsed},
+        Error, Parser,
+    },
+};
+
+/// How many times the size of its text a document's arena can grow to before it's rebuilt,
+/// dropping the trees and tokens left behind by edits
+const COMPACT_RATIO: usize = 64;
+
+/// An open document, kept parsed so that each edit only reparses the statements it touches
+#[derive(Debug)]
+pub(super) struct Document {
+    tree: Tree<'static>,
+    /// Holds the text and everything in `tree`, boxed so that it stays put when the document
+    /// moves. It's never reset, and is dropped last, along with the document.
+    bump: Box<Bump>,
+}
+
+#[derive(Debug)]
+enum Tree<'a> {
+    /// The document parses without errors, so can be reparsed incrementally
+    Parsed(Parsed<'a>),
+    /// The best-effort chunk of a document that doesn't parse, along with its errors
+    Recovered {
+        source: &'a str,
+        chunk: Block<'a>,
+        errors: Vec<Error<'a>>,
+    },
+}
+
+impl Document {
+    pub(super) fn new(text: &str) -> Self {
+        let bump = Box::new(Bump::new());
+        let arena = arena(&bump);
+
+        Self {
+            tree: Tree::new(arena.alloc_str(text), arena),
+            bump,
+        }
+    }
+
+    pub(super) fn text(&self) -> &str {
+        match &self.tree {
+            Tree::Parsed(parsed) => parsed.source(),
+            Tree::Recovered { source, .. } => source,
+        }
+    }
+
+    /// Apply `edit` to the document, failing with its range if that isn't within the document
+    pub(super) fn edit(&mut self, edit: &Edit) -> Result<(), Span> {
+        let arena = arena(&self.bump);
+
+        self.tree = match &self.tree {
+            Tree::Parsed(parsed) => match parsed.edit(edit, arena) {
+                Ok(parsed) => Tree::Parsed(parsed),
+                Err(EditError::Range(range)) => return Err(range),
+                // A whole parse recovers from the errors an incremental one stops at
+                Err(EditError::Parse(_)) => {
+                    let text = edit
+                        .apply(parsed.source())
+                        .map_err(|_| edit.range.clone())?;
+
+                    Tree::new(arena.alloc_str(&text), arena)
+                }
+            },
+            Tree::Recovered { source, .. } => {
+                let text = edit.apply(source).map_err(|_| edit.range.clone())?;
+
+                Tree::new(arena.alloc_str(&text), arena)
+            }
+        };
+
+        if self.bump.allocated_bytes() > self.text().len().max(1024) * COMPACT_RATIO {
+            *self = Self::new(self.text());
+        }
+
+        Ok(())
+    }
+
+    /// Pass the document's chunk to `f`, along with the errors in it
+    pub(super) fn with_chunk<R>(&self, f: impl FnOnce(Block, &[Error]) -> R) -> R {
+        match &self.tree {
+            Tree::Parsed(parsed) => {
+                // Statements that have moved since they were parsed are only settled into their
+                // new positions for the duration of the call
+                let bump = Bump::new();
+                let parsed: Parsed = *parsed;
+
+                f(parsed.settled(&bump), &[])
+            }
+            Tree::Recovered { chunk, errors, .. } => f(chunk, errors),
+        }
+    }
+}
+
+impl<'a> Tree<'a> {
+    fn new(source: &'a str, bump: &'a Bump) -> Self {
+        if let Ok(parsed) = Parsed::parse(source, bump) {
+            return Self::Parsed(parsed);
+        }
+
+        // Report every unrecognised token, rather than losing the rest of the document to the
+        // first
+        let (tokens, mut errors) = Parser::lex_recovering(source, bump);
+
+        let (chunk, parse_errors) =
+            Parser::new_in(bump.alloc_slice_clone(&tokens), bump).parse_chunk_recovering();
+
+        errors.extend(parse_errors);
+        errors.sort_by_key(|err| err.span().map_or(source.len(), |span| span.start));
+
+        Self::Recovered {
+            source,
+            chunk,
+            errors,
+        }
+    }
+}
+
+/// A document's arena, with its lifetime extended to that of the document
+fn arena(bump: &Bump) -> &'static Bump {
+    // SAFETY: the arena is boxed, so it isn't moved along with the document, and it's never reset
+    // or dropped before the document is. Anything allocated in it is only reachable through the
+    // document's tree, which is only handed out borrowed from the document.
+    unsafe { &*(bump as *const Bump) }
+}
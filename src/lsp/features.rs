@@ -0,0 +1,396 @@
+use logos::Span;
+use serde_json::{json, Value};
+
+use crate::{
+    analysis::scope::{Resolution, ScopeTree},
+    api::{self, Database},
+    ast::{
+        exps::{Function, Member, TableConstructor},
+        node::Node,
+        stats::{Do, For, ForIn, FunctionDef, IfElse, RepeatUntil, VarDef, While},
+        visitors::listener::{traverse, Ancestors, Listener},
+        Block, Exp, Name, Stat,
+    },
+    diagnostic::{Diagnostic, Severity},
+    source_map::SourceMap,
+};
+
+// `SymbolKind`s
+const METHOD: u32 = 6;
+const FUNCTION: u32 = 12;
+const VARIABLE: u32 = 13;
+
+// `CompletionItemKind`s
+const FUNCTION_ITEM: u32 = 3;
+const MODULE_ITEM: u32 = 9;
+const ENUM_MEMBER_ITEM: u32 = 20;
+
+pub(super) fn position(map: &SourceMap, offset: usize) -> Value {
+    let location = map.location(offset);
+
+    json!({ "line": location.line, "character": location.utf16_column })
+}
+
+pub(super) fn range(map: &SourceMap, span: &Span) -> Value {
+    json!({ "start": position(map, span.start), "end": position(map, span.end) })
+}
+
+/// The byte offset of an LSP position, clamped to the end of the source
+pub(super) fn offset(map: &SourceMap, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+
+    Some(
+        map.offset_utf16(line, character)
+            .unwrap_or(map.source().len()),
+    )
+}
+
+pub(super) fn diagnostics(map: &SourceMap, diagnostics: &[Diagnostic]) -> Value {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span().cloned().unwrap_or_default();
+
+            let severity = match diagnostic.severity {
+                Severity::Error => 1,
+                Severity::Warning => 2,
+                Severity::Note => 3,
+            };
+
+            json!({
+                "range": range(map, &span),
+                "severity": severity,
+                "code": diagnostic.code,
+                "source": "glua",
+                "message": diagnostic.message,
+            })
+        })
+        .collect()
+}
+
+/// Functions and locals, nested under the functions they're declared in
+pub(super) fn document_symbols(map: &SourceMap, chunk: Block) -> Value {
+    symbols(map, chunk).into()
+}
+
+fn symbols(map: &SourceMap, block: Block) -> Vec<Value> {
+    let mut symbols = Vec::new();
+
+    for stat in block {
+        match **stat {
+            Stat::FunctionDef(FunctionDef { name, body, .. }) => {
+                let kind = match name.method {
+                    Some(_) => METHOD,
+                    None => FUNCTION,
+                };
+
+                let children = self::symbols(map, body.body);
+
+                symbols.push(symbol(
+                    map,
+                    stat.span(),
+                    &name.to_string(),
+                    name.span(),
+                    kind,
+                    children,
+                ));
+            }
+            Stat::VarDef(VarDef { names, init_exps }) => {
+                for (i, name) in names.iter().enumerate() {
+                    let function = init_exps.and_then(|exps| match **exps.get(i)? {
+                        Exp::Function(function) => Some(function),
+                        _ => None,
+                    });
+
+                    let (kind, children) = match function {
+                        Some(function) => (FUNCTION, self::symbols(map, function.body)),
+                        None => (VARIABLE, Vec::new()),
+                    };
+
+                    symbols.push(symbol(map, stat.span(), name, name.span(), kind, children));
+                }
+            }
+            // Symbols in nested blocks belong to the enclosing function
+            Stat::Do(Do { body })
+            | Stat::For(For { body, .. })
+            | Stat::ForIn(ForIn { body, .. })
+            | Stat::RepeatUntil(RepeatUntil { body, .. })
+            | Stat::While(While { body, .. }) => symbols.extend(self::symbols(map, body)),
+            Stat::IfElse(IfElse {
+                body,
+                else_ifs,
+                else_block,
+                ..
+            }) => {
+                symbols.extend(self::symbols(map, body));
+
+                for (_, block) in else_ifs.iter() {
+                    symbols.extend(self::symbols(map, block));
+                }
+
+                if let Some(else_block) = else_block {
+                    symbols.extend(self::symbols(map, else_block));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    symbols
+}
+
+fn symbol(
+    map: &SourceMap,
+    span: Span,
+    name: &str,
+    name_span: Span,
+    kind: u32,
+    children: Vec<Value>,
+) -> Value {
+    json!({
+        "name": name,
+        "kind": kind,
+        "range": range(map, &span),
+        "selectionRange": range(map, &name_span),
+        "children": children,
+    })
+}
+
+/// Ranges of functions, tables and statements holding blocks which span multiple lines
+pub(super) fn folding_ranges(map: &SourceMap, chunk: Block) -> Value {
+    struct FoldingRanges<'m> {
+        map: &'m SourceMap<'m>,
+        ranges: Vec<Value>,
+    }
+
+    impl FoldingRanges<'_> {
+        /// Fold up to the line before the end of `span`, leaving its closing token visible
+        fn push(&mut self, span: Span) {
+            let start = self.map.line(span.start);
+            let end = self.map.line(span.end).saturating_sub(1);
+
+            if end > start {
+                self.ranges
+                    .push(json!({ "startLine": start, "endLine": end }));
+            }
+        }
+    }
+
+    impl<'a> Listener<'a> for FoldingRanges<'_> {
+        fn enter_stat(&mut self, v: &Node<'a, &'a Stat<'a>>, _ancestors: &Ancestors<'a>) {
+            if let Stat::Do(_)
+            | Stat::For(_)
+            | Stat::ForIn(_)
+            | Stat::IfElse(_)
+            | Stat::RepeatUntil(_)
+            | Stat::While(_) = **v
+            {
+                self.push(v.span());
+            }
+        }
+
+        fn enter_table_exp(
+            &mut self,
+            v: &Node<'a, &'a TableConstructor<'a>>,
+            _ancestors: &Ancestors<'a>,
+        ) {
+            self.push(v.span());
+        }
+
+        fn enter_function(&mut self, v: &Node<'a, &'a Function<'a>>, _ancestors: &Ancestors<'a>) {
+            self.push(v.span());
+        }
+    }
+
+    let mut folding_ranges = FoldingRanges {
+        map,
+        ranges: Vec::new(),
+    };
+
+    traverse(&mut folding_ranges, chunk);
+
+    folding_ranges.ranges.into()
+}
+
+/// The variable at `offset`, along with its declaration if it's a local
+fn variable_at<'a>(tree: &ScopeTree<'a>, offset: usize) -> Option<(Name<'a>, Option<Name<'a>>)> {
+    if let Some((_, binding)) = tree.binding_at(offset) {
+        return Some((binding.name, Some(binding.name)));
+    }
+
+    let reference = tree.reference_at(offset)?;
+
+    let declaration = match reference.resolution {
+        Resolution::Local(binding) => Some(tree.binding(binding).name),
+        Resolution::Global => None,
+    };
+
+    Some((reference.name, declaration))
+}
+
+/// The declaration of the local at `offset`
+pub(super) fn definition(map: &SourceMap, chunk: Block, uri: &str, offset: usize) -> Value {
+    let tree = ScopeTree::build(chunk);
+
+    match variable_at(&tree, offset).and_then(|(_, declaration)| declaration) {
+        Some(declaration) => json!({ "uri": uri, "range": range(map, &declaration.span()) }),
+        None => Value::Null,
+    }
+}
+
+/// The line declaring the variable at `offset`, or the documentation of the part of the GMod API
+/// it refers to
+pub(super) fn hover(map: &SourceMap, chunk: Block, offset: usize) -> Value {
+    let tree = ScopeTree::build(chunk);
+
+    let (span, contents) = match variable_at(&tree, offset) {
+        Some((name, Some(declaration))) => {
+            let line = map.line_text(map.line(declaration.span().start)).trim();
+
+            (name.span(), format!("```lua\n{}\n```", line))
+        }
+        Some((name, None)) => (name.span(), global_docs(*name)),
+        None => match library_function_at(chunk, &tree, offset) {
+            Some((name, docs)) => (name.span(), docs),
+            None => return Value::Null,
+        },
+    };
+
+    json!({
+        "contents": { "kind": "markdown", "value": contents },
+        "range": range(map, &span),
+    })
+}
+
+fn global_docs(name: &str) -> String {
+    let database = Database::bundled();
+
+    if let Some(function) = database.function(name) {
+        return function_docs(function, name);
+    }
+
+    if database.library(name).is_some() {
+        return format!("library `{}`", name);
+    }
+
+    match database.enum_member(name) {
+        Some((e, member)) => format!(
+            "```lua\n{} = {}\n```\n`{}_*` enum\n\n*{}*",
+            member.name, member.value, e.name, e.realm
+        ),
+        None => format!("global `{}`", name),
+    }
+}
+
+fn function_docs(function: &api::Function, name: &str) -> String {
+    format!(
+        "```lua\nfunction {}\n```\n{}\n\n*{}*",
+        function.signature(name),
+        function.description,
+        function.realm
+    )
+}
+
+/// The name and documentation of a library function like `net.Start` whose name is at `offset`
+fn library_function_at<'a>(
+    chunk: Block<'a>,
+    tree: &ScopeTree<'a>,
+    offset: usize,
+) -> Option<(Name<'a>, String)> {
+    struct Members<'a> {
+        offset: usize,
+        found: Option<(Name<'a>, Name<'a>)>,
+    }
+
+    impl<'a> Listener<'a> for Members<'a> {
+        fn enter_member_exp(&mut self, v: &Node<'a, &'a Member<'a>>, _ancestors: &Ancestors<'a>) {
+            let span = v.name.span();
+
+            if let Exp::Ref(library) = **v.lhs {
+                if span.start <= self.offset && self.offset <= span.end {
+                    self.found = Some((Node::morph(&v.lhs, library), v.name));
+                }
+            }
+        }
+    }
+
+    let mut members = Members {
+        offset,
+        found: None,
+    };
+
+    traverse(&mut members, chunk);
+
+    let (library, name) = members.found?;
+
+    // A local shadowing the library
+    if tree.reference_at(library.span().start)?.resolution != Resolution::Global {
+        return None;
+    }
+
+    let function = Database::bundled().library(*library)?.function(*name)?;
+
+    Some((
+        name,
+        function_docs(function, &format!("{}.{}", *library, *name)),
+    ))
+}
+
+/// The parts of the GMod API that can be completed at `offset`: the functions of a library after
+/// `library.`, otherwise every global function, library and enum member.
+///
+/// The text before `offset` is used rather than the chunk, as what's being typed rarely parses.
+pub(super) fn completion(map: &SourceMap, offset: usize) -> Value {
+    let database = Database::bundled();
+    let before = &map.source()[..offset];
+
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let before = before.trim_end_matches(is_name);
+
+    let function = |function: &api::Function, name: &str| {
+        json!({
+            "label": function.name,
+            "kind": FUNCTION_ITEM,
+            "detail": function.signature(name),
+            "documentation": function.description,
+        })
+    };
+
+    if let Some(before) = before.strip_suffix('.') {
+        let library = &before[before.trim_end_matches(is_name).len()..];
+
+        return match database.library(library) {
+            Some(library) => library
+                .functions
+                .iter()
+                .map(|f| function(f, &format!("{}.{}", library.name, f.name)))
+                .collect(),
+            None => json!([]),
+        };
+    }
+
+    // Methods aren't completed, as the type of the object isn't known
+    if before.ends_with(':') {
+        return json!([]);
+    }
+
+    let functions = database.functions.iter().map(|f| function(f, &f.name));
+
+    let libraries = database
+        .libraries
+        .iter()
+        .map(|library| json!({ "label": library.name, "kind": MODULE_ITEM }));
+
+    let members = database.enums.iter().flat_map(|e| {
+        e.members.iter().map(|member| {
+            json!({
+                "label": member.name,
+                "kind": ENUM_MEMBER_ITEM,
+                "detail": format!("{} = {}", member.name, member.value),
+            })
+        })
+    });
+
+    functions.chain(libraries).chain(members).collect()
+}
@@ -0,0 +1,14 @@
+//! A language server for GLua, speaking the Language Server Protocol over JSON-RPC.
+//!
+//! Offers diagnostics, document symbols, folding ranges, go to definition for locals, hover and
+//! completion of the GMod API.
+
+pub use self::{
+    server::Server,
+    transport::{read_message, write_message},
+};
+
+mod document;
+mod features;
+mod server;
+mod transport;
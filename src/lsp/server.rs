@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    analysis::realm::file_realm,
+    ast::Block,
+    diagnostic::Diagnostic,
+    lint,
+    lsp::{
+        document::Document,
+        features,
+        transport::{read_message, write_message},
+    },
+    parser::incremental::Edit,
+    source_map::SourceMap,
+};
+
+// JSON-RPC error codes
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type Response = Result<Value, (i64, String)>;
+
+/// A language server holding every open document, parsed.
+///
+/// Extra globals for linting can be given in the `globals` and `readGlobals` arrays of the
+/// client's `initializationOptions`.
+///
+/// Messages are handled one at a time by [`Server::handle`], which [`Server::run`] drives over a
+/// pair of streams, e.g. stdin and stdout.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    lint_config: lint::Config,
+    shut_down: bool,
+    exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve messages from `reader` until the client exits or closes the stream, returning
+    /// whether the client asked the server to shut down first
+    pub fn run(&mut self, mut reader: impl BufRead, mut writer: impl Write) -> io::Result<bool> {
+        while let Some(message) = read_message(&mut reader)? {
+            for reply in self.handle(&message) {
+                write_message(&mut writer, &reply)?;
+            }
+
+            if self.exited {
+                break;
+            }
+        }
+
+        Ok(self.shut_down)
+    }
+
+    /// Handle a message from the client, returning the messages to send back to it
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // Responses to requests we never make
+            return Vec::new();
+        };
+
+        let params = &message["params"];
+
+        match message.get("id") {
+            Some(id) => {
+                let reply = match self.shut_down {
+                    true => Err((INVALID_REQUEST, "the server has shut down".to_owned())),
+                    false => self.request(method, params),
+                };
+
+                vec![match reply {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                }]
+            }
+            None => self.notify(method, params),
+        }
+    }
+
+    pub fn is_exited(&self) -> bool {
+        self.exited
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Response {
+        match method {
+            "initialize" => {
+                let options = &params["initializationOptions"];
+
+                let names = |key: &str| {
+                    options[key]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_owned)
+                        .collect::<Vec<_>>()
+                };
+
+                self.lint_config = lint::Config::default()
+                    .with_globals(names("globals"))
+                    .with_read_globals(names("readGlobals"));
+
+                Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 2 },
+                    "documentSymbolProvider": true,
+                    "foldingRangeProvider": true,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                },
+                "serverInfo": { "name": "glua-lsp", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "shutdown" => {
+                self.shut_down = true;
+
+                Ok(Value::Null)
+            }
+            "textDocument/documentSymbol" => self.with_chunk(params, features::document_symbols),
+            "textDocument/foldingRange" => self.with_chunk(params, features::folding_ranges),
+            "textDocument/definition" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+                self.with_position(params, |map, chunk, offset| {
+                    features::definition(map, chunk, uri, offset)
+                })
+            }
+            "textDocument/hover" => self.with_position(params, features::hover),
+            "textDocument/completion" => {
+                self.with_position(params, |map, _, offset| features::completion(map, offset))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "exit" => self.exited = true,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+
+                self.documents.insert(uri.to_owned(), Document::new(text));
+            }
+            "textDocument/didChange" => {
+                let Some(document) = self.documents.get_mut(uri) else {
+                    return Vec::new();
+                };
+
+                let changes = params["contentChanges"].as_array().into_iter().flatten();
+
+                for change in changes {
+                    let new = change["text"].as_str().unwrap_or_default();
+
+                    let Some(range) = change.get("range") else {
+                        *document = Document::new(new);
+
+                        continue;
+                    };
+
+                    let map = SourceMap::new(document.text());
+
+                    let start = features::offset(&map, &range["start"]).unwrap_or(0);
+                    let end = features::offset(&map, &range["end"]).unwrap_or(start);
+
+                    let edit = Edit {
+                        range: start..end.max(start),
+                        text: new,
+                    };
+
+                    // Positions are clamped to the document, so this only fails if the client
+                    // and server disagree on its contents, in which case it's skipped
+                    let _ = document.edit(&edit);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+
+                return vec![publish_diagnostics(uri, json!([]))];
+            }
+            _ => {}
+        }
+
+        match self.documents.get(uri) {
+            Some(document) if method.starts_with("textDocument/did") => {
+                vec![publish_diagnostics(
+                    uri,
+                    diagnostics(uri, document, &self.lint_config),
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Pass the chunk of the document named in `params` to `f`, which is the best effort at it if
+    /// the document has errors
+    fn with_chunk(&self, params: &Value, f: impl FnOnce(&SourceMap, Block) -> Value) -> Response {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document `{}`", uri)))?;
+
+        let map = SourceMap::new(document.text());
+
+        Ok(document.with_chunk(|chunk, _| f(&map, chunk)))
+    }
+
+    /// As [`Server::with_chunk`], also passing the offset of the position in `params`
+    fn with_position(
+        &self,
+        params: &Value,
+        f: impl FnOnce(&SourceMap, Block, usize) -> Value,
+    ) -> Response {
+        self.with_chunk(params, |map, chunk| {
+            match features::offset(map, &params["position"]) {
+                Some(offset) => f(map, chunk, offset),
+                None => Value::Null,
+            }
+        })
+    }
+}
+
+fn diagnostics(uri: &str, document: &Document, config: &lint::Config) -> Value {
+    let map = SourceMap::new(document.text());
+
+    let diagnostics = document.with_chunk(|chunk, errors| match errors {
+        // Lints would be misled by the holes left by errors
+        [] => lint::lint(chunk, file_realm(uri), config),
+        _ => errors
+            .iter()
+            .map(|err| Diagnostic::from_error(err, &map))
+            .collect(),
+    });
+
+    features::diagnostics(&map, &diagnostics)
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
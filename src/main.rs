@@ -1,16 +1,34 @@
-use std::hint::black_box;
+use std::{hint::black_box, process::ExitCode};
 
 use bumpalo::Bump;
-use glua::Parser;
+use glua::{parser::Error, Parser, SourceMap};
+
+static CODE: &str = include_str!("../test.lua");
+
+fn main() -> ExitCode {
+    let source_map = SourceMap::new(CODE).with_name("test.lua");
 
-fn main() {
     for _ in 0..8192 {
         let bump = Bump::new();
 
-        let tokens = Parser::lex(include_str!("../test.lua"), &bump).unwrap();
+        let tokens = match Parser::lex(CODE, &bump) {
+            Ok(tokens) => tokens,
+            Err(err) => return fail(&err, &source_map),
+        };
 
         let mut parser = Parser::new_in(&tokens, &bump);
 
-        black_box(parser.parse_chunk().unwrap());
+        match parser.parse_chunk() {
+            Ok(chunk) => black_box(chunk),
+            Err(err) => return fail(&err, &source_map),
+        };
     }
+
+    ExitCode::SUCCESS
+}
+
+fn fail(err: &Error, source_map: &SourceMap) -> ExitCode {
+    eprintln!("error: {}", err.display_with(source_map));
+
+    ExitCode::FAILURE
 }
@@ -0,0 +1,217 @@
+//! Parsing many files at once across threads, for checking whole collections of addons.
+//!
+//! Every worker owns a single [`Bump`] which is reset after each file, so memory use is bounded
+//! by the largest files being parsed at once rather than by the total. Since a tree borrows from
+//! its source and that arena, it can't outlive the file it was parsed from: instead it's handed to
+//! a callback on the worker, which turns it into an owned result such as diagnostics or a summary.
+//! Anything needed after the batch has to be copied out of the tree by that callback.
+
+use std::{
+    mem,
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bumpalo::Bump;
+
+use crate::{
+    ast::Block,
+    parser::{Parser, Result, SpannedToken},
+};
+
+/// Options for parsing a batch of files
+#[derive(Clone, Debug)]
+pub struct Batch {
+    threads: usize,
+}
+
+/// The results of parsing a batch, in the same order as the sources
+#[derive(Clone, Debug)]
+pub struct Output<T> {
+    pub results: Vec<T>,
+    pub files: Vec<FileStats>,
+    pub stats: Stats,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileStats {
+    /// Whether the file lexed and parsed without errors
+    pub parsed: bool,
+    pub bytes: usize,
+    pub tokens: usize,
+    pub lex_time: Duration,
+    pub parse_time: Duration,
+    /// Memory held by the worker's arena after parsing the file, which includes capacity kept
+    /// from earlier files
+    pub arena_bytes: usize,
+    /// Memory held by the file's tokens, which are lexed onto the heap rather than into the arena
+    pub token_bytes: usize,
+    /// The index of the worker that parsed the file
+    pub worker: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub threads: usize,
+    pub files: usize,
+    /// Files that failed to lex or parse
+    pub failed: usize,
+    pub bytes: usize,
+    pub tokens: usize,
+    /// Wall time for the whole batch, including the callbacks
+    pub elapsed: Duration,
+    /// Total time spent lexing and parsing across all workers
+    pub parse_time: Duration,
+    /// The most memory any worker's arena held at once
+    pub peak_arena_bytes: usize,
+    /// The most memory used to parse a single file, counting both the arena and the tokens
+    pub peak_bytes: usize,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        }
+    }
+}
+
+impl Batch {
+    /// A batch using a thread per available core
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `threads` workers, or one if zero
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+
+        self
+    }
+
+    /// Parse every source, calling `f` on a worker with each source's index, the source and its
+    /// chunk or error, and collecting what it returns.
+    pub fn parse<S, T, F>(&self, sources: &[S], f: F) -> Output<T>
+    where
+        S: AsRef<str> + Sync,
+        T: Send,
+        F: for<'a> Fn(usize, &'a str, Result<'a, Block<'a>>) -> T + Sync,
+    {
+        let start = Instant::now();
+
+        let threads = self.threads.min(sources.len()).max(1);
+        let next = AtomicUsize::new(0);
+
+        let worker = |worker: usize| {
+            let mut bump = Bump::new();
+            let mut done = Vec::new();
+
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+
+                let Some(source) = sources.get(index) else {
+                    break;
+                };
+
+                let source = source.as_ref();
+
+                let (result, stats) = parse(source, &bump, worker, &f, index);
+
+                done.push((index, result, stats));
+
+                bump.reset();
+            }
+
+            done
+        };
+
+        let mut done = thread::scope(|scope| {
+            let worker = &worker;
+
+            let handles = (0..threads)
+                .map(|i| scope.spawn(move || worker(i)))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("a parse worker panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        done.sort_unstable_by_key(|(index, ..)| *index);
+
+        let mut stats = Stats {
+            threads,
+            ..Stats::default()
+        };
+
+        let mut results = Vec::with_capacity(done.len());
+        let mut files = Vec::with_capacity(done.len());
+
+        for (_, result, file) in done {
+            stats.files += 1;
+            stats.failed += usize::from(!file.parsed);
+            stats.bytes += file.bytes;
+            stats.tokens += file.tokens;
+            stats.parse_time += file.lex_time + file.parse_time;
+            stats.peak_arena_bytes = stats.peak_arena_bytes.max(file.arena_bytes);
+            stats.peak_bytes = stats.peak_bytes.max(file.arena_bytes + file.token_bytes);
+
+            results.push(result);
+            files.push(file);
+        }
+
+        stats.elapsed = start.elapsed();
+
+        Output {
+            results,
+            files,
+            stats,
+        }
+    }
+}
+
+/// Parse one source in `bump`, handing the result to `f`
+fn parse<T, F>(source: &str, bump: &Bump, worker: usize, f: &F, index: usize) -> (T, FileStats)
+where
+    F: for<'a> Fn(usize, &'a str, Result<'a, Block<'a>>) -> T,
+{
+    let mut stats = FileStats {
+        parsed: false,
+        bytes: source.len(),
+        tokens: 0,
+        lex_time: Duration::ZERO,
+        parse_time: Duration::ZERO,
+        arena_bytes: 0,
+        token_bytes: 0,
+        worker,
+    };
+
+    let start = Instant::now();
+    let tokens = Parser::lex(source, bump);
+    stats.lex_time = start.elapsed();
+
+    let tokens = match tokens {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            stats.arena_bytes = bump.allocated_bytes();
+
+            return (f(index, source, Err(err)), stats);
+        }
+    };
+
+    stats.tokens = tokens.len();
+    stats.token_bytes = tokens.capacity() * mem::size_of::<SpannedToken>();
+
+    // The tokens stay on the heap rather than in the arena, so they're freed with each file
+    let start = Instant::now();
+    let result = Parser::new_in(&tokens, bump).parse_chunk();
+    stats.parse_time = start.elapsed();
+
+    stats.parsed = result.is_ok();
+    stats.arena_bytes = bump.allocated_bytes();
+
+    (f(index, source, result), stats)
+}
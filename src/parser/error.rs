@@ -5,6 +5,7 @@ use logos::Span;
 use crate::{
     ast::Exp,
     lexer::{Keyword, Op, Token},
+    source_map::SourceMap,
 };
 
 #[derive(Debug)]
@@ -23,24 +24,36 @@ pub enum Expectation<'a> {
     Var,
 }
 
+/// A lexer or parser error. Its `Display` gives only the message, [`Error::display_with`] also
+/// gives the line and column it occurred at.
 #[derive(thiserror::Error, Debug)]
 pub enum Error<'a> {
     UnexpectedEof {
         expected: Option<Expectation<'a>>,
+        opener: Option<Box<Opener<'a>>>,
     },
     UnexpectedExp {
         span: Span,
         expected: Expectation<'a>,
-        got: Exp<'a>,
+        got: Box<Exp<'a>>,
     },
     UnexpectedToken {
         span: Span,
         expected: Option<Expectation<'a>>,
         got: Token<'a>,
+        opener: Option<Box<Opener<'a>>>,
     },
     Lexer(Span),
 }
 
+/// The token that opened the construct being closed when an error occurred, e.g. the `if` whose
+/// `then` is missing
+#[derive(Debug)]
+pub struct Opener<'a> {
+    pub token: Token<'a>,
+    pub span: Span,
+}
+
 impl<'a> Expectation<'a> {
     pub fn tokens<T>(tokens: impl IntoIterator<Item = T>) -> Self
     where
@@ -53,33 +66,33 @@ impl<'a> Expectation<'a> {
 impl Display for Expectation<'_> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Self::Args => write!(f, "args"),
-            Self::Eof => write!(f, "eof"),
+            Self::Args => write!(f, "function arguments"),
+            Self::Eof => write!(f, "end of file"),
             Self::Expression => write!(f, "expression"),
-            Self::FunctionCall => write!(f, "functioncall"),
-            Self::Keyword(keyword) => write!(f, "{:?}", keyword),
+            Self::FunctionCall => write!(f, "function call"),
+            Self::Keyword(keyword) => write!(f, "`{}`", keyword),
             Self::Name => write!(f, "name"),
-            Self::Op(op) => write!(f, "{:?}", op),
-            Self::PrefixExp => write!(f, "prefixexp"),
-            Self::Stat => write!(f, "stat"),
-            Self::Token(token) => write!(f, "{:?}", token),
+            Self::Op(op) => write!(f, "`{}`", op),
+            Self::PrefixExp => write!(f, "prefix expression"),
+            Self::Stat => write!(f, "statement"),
+            Self::Token(token) => write!(f, "{}", token),
             Self::Tokens(tokens) => match tokens.len() {
-                1 => write!(f, "{:?}", tokens[0]),
-                2 => write!(f, "{:?} or {:?}", tokens[0], tokens[1]),
+                1 => write!(f, "{}", tokens[0]),
+                2 => write!(f, "{} or {}", tokens[0], tokens[1]),
                 _ => {
                     for token in &tokens[..tokens.len() - 2] {
-                        write!(f, "{:?}, ", token)?;
+                        write!(f, "{}, ", token)?;
                     }
 
                     write!(
                         f,
-                        "{:?} or {:?}",
+                        "{} or {}",
                         tokens[tokens.len() - 2],
                         tokens[tokens.len() - 1]
                     )
                 }
             },
-            Self::Var => write!(f, "var"),
+            Self::Var => write!(f, "variable"),
         }
     }
 }
@@ -115,6 +128,7 @@ impl<'a> Error<'a> {
     pub(crate) fn unexpected_eof(expected: impl Into<Option<Expectation<'a>>>) -> Self {
         Self::UnexpectedEof {
             expected: expected.into(),
+            opener: None,
         }
     }
 
@@ -126,7 +140,7 @@ impl<'a> Error<'a> {
         Self::UnexpectedExp {
             span: span.to_owned(),
             expected: expected.into(),
-            got,
+            got: Box::new(got),
         }
     }
 
@@ -139,6 +153,59 @@ impl<'a> Error<'a> {
             span: span.to_owned(),
             expected: expected.into(),
             got: got.into(),
+            opener: None,
+        }
+    }
+
+    /// Record the token that opened the construct the parser was trying to close
+    pub(crate) fn opened_by(mut self, token: Token<'a>, span: &Span) -> Self {
+        if let Self::UnexpectedEof { opener, .. } | Self::UnexpectedToken { opener, .. } = &mut self
+        {
+            *opener = Some(Box::new(Opener {
+                token,
+                span: span.to_owned(),
+            }));
+        }
+
+        self
+    }
+}
+
+impl<'a> Error<'a> {
+    /// The span of source the error occurred at, if known
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Self::UnexpectedEof { .. } => None,
+            Self::UnexpectedExp { span, .. }
+            | Self::UnexpectedToken { span, .. }
+            | Self::Lexer(span) => Some(span),
+        }
+    }
+
+    pub fn opener(&self) -> Option<&Opener<'a>> {
+        match self {
+            Self::UnexpectedEof { opener, .. } | Self::UnexpectedToken { opener, .. } => {
+                opener.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn expected(&self) -> Option<&Expectation<'a>> {
+        match self {
+            Self::UnexpectedEof { expected, .. } | Self::UnexpectedToken { expected, .. } => {
+                expected.as_ref()
+            }
+            Self::UnexpectedExp { expected, .. } => Some(expected),
+            Self::Lexer(_) => None,
+        }
+    }
+
+    /// Display the error prefixed with its location in `source_map`, e.g. `file.lua:12:5: ...`
+    pub fn display_with<'s>(&'s self, source_map: &'s SourceMap) -> impl Display + 's {
+        Located {
+            error: self,
+            source_map,
         }
     }
 }
@@ -146,20 +213,16 @@ impl<'a> Error<'a> {
 impl Display for Error<'_> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Self::UnexpectedToken {
-                span,
-                expected,
-                got,
-            } => {
-                write!(f, "Unexpected {:?}", got)?;
+            Self::UnexpectedToken { expected, got, .. } => {
+                write!(f, "Unexpected {}", got)?;
 
                 if let Some(expectation) = expected {
                     write!(f, ", expecting {}", expectation)?;
                 }
 
-                write!(f, " at {:?}", span)
+                Ok(())
             }
-            Self::UnexpectedEof { expected } => {
+            Self::UnexpectedEof { expected, .. } => {
                 write!(f, "Unexpected EOF")?;
 
                 if let Some(expectation) = expected {
@@ -168,16 +231,51 @@ impl Display for Error<'_> {
 
                 Ok(())
             }
-            Self::UnexpectedExp {
-                span,
-                expected,
-                got,
-            } => write!(
-                f,
-                "Unexpected {:?}, expecting {} at {:?}",
-                got, expected, span
-            ),
-            Self::Lexer(span) => write!(f, "Unrecognised token at `{:?}` in input", span),
+            Self::UnexpectedExp { expected, got, .. } => {
+                write!(
+                    f,
+                    "Unexpected {}, expecting {}",
+                    describe_exp(got),
+                    expected
+                )
+            }
+            Self::Lexer(_) => write!(f, "Unrecognised token in input"),
         }
     }
 }
+
+struct Located<'s, 'a> {
+    error: &'s Error<'a>,
+    source_map: &'s SourceMap<'s>,
+}
+
+impl Display for Located<'_, '_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        // EOF errors have no span, so point just past the last token
+        let end = self.source_map.source().trim_end().len();
+
+        let span = self.error.span().cloned().unwrap_or(end..end);
+
+        write!(f, "{}: {}", self.source_map.display_span(&span), self.error)
+    }
+}
+
+fn describe_exp(exp: &Exp) -> &'static str {
+    match exp {
+        Exp::Binary(_) => "binary expression",
+        Exp::Bool(_) => "boolean",
+        Exp::Function(_) => "function",
+        Exp::FunctionCall(_) => "function call",
+        Exp::Index(_) => "index expression",
+        Exp::Member(_) => "member expression",
+        Exp::MethodCall(_) => "method call",
+        Exp::Nil => "`nil`",
+        Exp::Number(_) => "number",
+        Exp::Paren(_) => "parenthesised expression",
+        Exp::Ref(_) => "name",
+        Exp::String(_) => "string",
+        Exp::Table(_) => "table",
+        Exp::Unary(_) => "unary expression",
+        Exp::VarArgs => "`...`",
+    }
+}
@@ -0,0 +1,395 @@
+//! Reparsing of edited source, for editors which need a fresh tree on every keystroke.
+//!
+//! Only the top-level statements touched by an edit (and one either side, which the edit could
+//! have joined onto) are relexed and reparsed, and the statements around them are reused as is.
+//! Rather than rebuilding the statements after an edit to shift their spans, how far each has
+//! moved is recorded alongside it, and applied only when [`Parsed::settled`] is called.
+
+use bumpalo::{collections::Vec as BumpVec, Bump};
+use logos::{Logos, Span};
+
+use crate::{
+    ast::{node::Node, trivia::Comment, visitors::fold::Fold, Block, Stat},
+    lexer::Token,
+    parser::{comment_kind, Error, Parser, Result, SpannedToken},
+};
+
+/// A chunk along with the source, tokens and comments it was parsed from
+#[derive(Clone, Copy, Debug)]
+pub struct Parsed<'a> {
+    source: &'a str,
+    tokens: &'a [SpannedToken<'a>],
+    comments: &'a [Comment<'a>],
+    chunk: Block<'a>,
+    /// How far each statement in `chunk` has moved since it was parsed, or empty if none have
+    offsets: &'a [isize],
+}
+
+/// Replacement of the source in `range` with `text`
+#[derive(Clone, Debug)]
+pub struct Edit<'s> {
+    pub range: Span,
+    pub text: &'s str,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EditError<'a> {
+    /// The edit's range is reversed, ends past the end of the source or splits a character
+    #[error("invalid edit range {}..{}", .0.start, .0.end)]
+    Range(Span),
+    #[error("{0}")]
+    Parse(Error<'a>),
+}
+
+impl<'a> From<Error<'a>> for EditError<'a> {
+    fn from(err: Error<'a>) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl<'a> Parsed<'a> {
+    pub fn parse(source: &'a str, bump: &'a Bump) -> Result<'a, Self> {
+        let (tokens, comments) = Parser::lex_with_comments(source, bump)?;
+
+        let tokens = bump.alloc_slice_clone(&tokens);
+        let comments = bump.alloc_slice_copy(&comments);
+
+        let chunk = Parser::new_in(tokens, bump)
+            .with_comments(comments)
+            .parse_chunk()?;
+
+        Ok(Self {
+            source,
+            tokens,
+            comments,
+            chunk,
+            offsets: &[],
+        })
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn tokens(&self) -> &'a [SpannedToken<'a>] {
+        self.tokens
+    }
+
+    pub fn comments(&self) -> &'a [Comment<'a>] {
+        self.comments
+    }
+
+    /// The top-level statements as they were parsed, so the spans of any that have moved since
+    /// are out of date by [`Parsed::offset`]. See [`Parsed::settled`] for up to date spans.
+    pub fn chunk(&self) -> Block<'a> {
+        self.chunk
+    }
+
+    /// How far the statement at `index` in [`Parsed::chunk`] has moved since it was parsed
+    pub fn offset(&self, index: usize) -> isize {
+        self.offsets.get(index).copied().unwrap_or(0)
+    }
+
+    /// The chunk with every span up to date, rebuilding only the statements that have moved
+    pub fn settled(&self, bump: &'a Bump) -> Block<'a> {
+        if self.offsets.iter().all(|&offset| offset == 0) {
+            return self.chunk;
+        }
+
+        let stats = self
+            .chunk
+            .iter()
+            .zip(self.offsets)
+            .map(|(stat, &delta)| match delta {
+                0 => *stat,
+                _ => Shift { bump, delta }.fold_stat(stat),
+            });
+
+        bump.alloc_slice_fill_iter(stats)
+    }
+
+    /// Apply `edit` to the source, reparsing only the statements it affects
+    pub fn edit(&self, edit: &Edit, bump: &'a Bump) -> Result<'a, Self, EditError<'a>> {
+        let source = &*bump.alloc_str(&edit.apply(self.source)?);
+
+        let shift = Shift {
+            bump,
+            delta: edit.text.len() as isize - edit.range.len() as isize,
+        };
+
+        let relexed = self.relex(source, edit, &shift, bump)?;
+
+        // Statements that overlap the relexed source, plus one either side
+        let stats = self.chunk;
+
+        // The extent of the statement at `index` in the old source
+        let extent = |index: usize| {
+            let moved = Shift {
+                bump,
+                delta: self.offset(index),
+            };
+            let span = extent(&stats[index]);
+
+            moved.pos(span.start)..moved.pos(span.end)
+        };
+
+        let lo = (0..stats.len())
+            .position(|i| extent(i).end >= relexed.start)
+            .unwrap_or(stats.len())
+            .saturating_sub(1);
+
+        let hi = (0..stats.len())
+            .position(|i| extent(i).start > relexed.end)
+            .map_or(stats.len(), |hi| hi + 1);
+
+        let start = if lo == 0 { 0 } else { extent(lo).start };
+
+        let end = if hi == stats.len() {
+            source.len()
+        } else {
+            shift.pos(extent(hi - 1).end)
+        };
+
+        let (tokens, comments) = (relexed.tokens, relexed.comments);
+
+        let region = |span: Span| start <= span.start && span.end <= end;
+
+        let region_tokens = slice(tokens, |(_, span)| region(span.clone()));
+        let region_comments = slice(comments, |comment| region(comment.span()));
+
+        let reparsed = Parser::new_in(region_tokens, bump)
+            .with_comments(region_comments)
+            .parse_chunk();
+
+        let (chunk, offsets) = match reparsed {
+            Ok(reparsed) => {
+                let mut chunk = BumpVec::with_capacity_in(stats.len(), bump);
+
+                chunk.extend_from_slice(&stats[..lo]);
+                chunk.extend_from_slice(reparsed);
+                chunk.extend_from_slice(&stats[hi..]);
+
+                // The statements after the edit are reused as they are, having moved by its delta
+                let offsets = (0..lo)
+                    .map(|i| self.offset(i))
+                    .chain(reparsed.iter().map(|_| 0))
+                    .chain((hi..stats.len()).map(|i| self.offset(i) + shift.delta));
+
+                let offsets = match self.offsets.is_empty() && shift.delta == 0 {
+                    true => &[],
+                    false => BumpVec::from_iter_in(offsets, bump).into_bump_slice(),
+                };
+
+                (chunk.into_bump_slice(), offsets)
+            }
+            // The edit reaches beyond the statements around it, e.g. by removing an `end`
+            Err(_) => {
+                let chunk = Parser::new_in(tokens, bump)
+                    .with_comments(comments)
+                    .parse_chunk()?;
+
+                (chunk, &[][..])
+            }
+        };
+
+        Ok(Self {
+            source,
+            tokens,
+            comments,
+            chunk,
+            offsets,
+        })
+    }
+
+    /// Lex `source` from the last token before `edit` until the tokens line up with the old
+    /// tokens again, reusing the tokens either side
+    fn relex(
+        &self,
+        source: &'a str,
+        edit: &Edit,
+        shift: &Shift,
+        bump: &'a Bump,
+    ) -> Result<'a, Relexed<'a>> {
+        let prefix_tokens = self
+            .tokens
+            .partition_point(|(_, span)| span.end < edit.range.start);
+        let prefix_comments = self
+            .comments
+            .partition_point(|comment| comment.span().end < edit.range.start);
+
+        let start = self.tokens[..prefix_tokens]
+            .last()
+            .map(|(_, span)| span.end)
+            .into_iter()
+            .chain(
+                self.comments[..prefix_comments]
+                    .last()
+                    .map(|c| c.span().end),
+            )
+            .max()
+            .unwrap_or(0);
+
+        let mut tokens = self.tokens[..prefix_tokens].to_vec();
+        let mut comments = self.comments[..prefix_comments].to_vec();
+
+        let inserted_end = edit.range.start + edit.text.len();
+
+        let mut last_end = start;
+        // Where the old tokens line up with the new ones again, in old and new positions
+        let mut synced = None;
+
+        for (res, span) in Token::lexer_with_extras(&source[start..], bump).spanned() {
+            let span = span.start + start..span.end + start;
+
+            if span.start >= inserted_end {
+                let old = shift.unshift(span.start);
+
+                let token = self.tokens.partition_point(|(_, s)| s.start < old);
+                let comment = self.comments.partition_point(|c| c.span().start < old);
+
+                let starts_token = matches!(self.tokens.get(token), Some((_, s)) if s.start == old);
+                let starts_comment =
+                    matches!(self.comments.get(comment), Some(c) if c.span().start == old);
+
+                if starts_token || starts_comment {
+                    synced = Some((old, span.start, token, comment));
+
+                    break;
+                }
+            }
+
+            let own_line = last_end == 0
+                || memchr::memchr(b'\n', &source.as_bytes()[last_end..span.start]).is_some();
+
+            last_end = span.end;
+
+            match res {
+                Ok(Token::Comment(text)) => {
+                    let kind = comment_kind(&source[span.clone()]);
+
+                    comments.push(Comment::new(kind, text, own_line, span))
+                }
+                Ok(token) => tokens.push((token, span)),
+                Err(_) => return Err(Error::Lexer(span)),
+            }
+        }
+
+        let end = match synced {
+            Some((old, new, token, comment)) => {
+                let shifted = |span: Span| shift.pos(span.start)..shift.pos(span.end);
+
+                tokens.extend(
+                    self.tokens[token..]
+                        .iter()
+                        .map(|(token, span)| (*token, shifted(span.clone()))),
+                );
+
+                let first = comments.len();
+
+                comments.extend(
+                    self.comments[comment..]
+                        .iter()
+                        .map(|c| Comment::new(c.kind, c.text, c.own_line, shifted(c.span()))),
+                );
+
+                // Whether the first reused comment is on its own line depends on the new source
+                if let Some(c) = comments.get_mut(first).filter(|c| c.span().start == new) {
+                    c.own_line = last_end == 0
+                        || memchr::memchr(b'\n', &source.as_bytes()[last_end..new]).is_some();
+                }
+
+                old
+            }
+            None => self.source.len(),
+        };
+
+        Ok(Relexed {
+            start,
+            end,
+            tokens: bump.alloc_slice_clone(&tokens),
+            comments: bump.alloc_slice_copy(&comments),
+        })
+    }
+}
+
+impl Edit<'_> {
+    /// Apply the edit to `source`, failing if its range isn't within `source` or doesn't start and
+    /// end on character boundaries
+    pub fn apply(&self, source: &str) -> Result<'static, String, EditError<'static>> {
+        let Span { start, end } = self.range;
+
+        if start > end || !source.is_char_boundary(start) || !source.is_char_boundary(end) {
+            return Err(EditError::Range(self.range.clone()));
+        }
+
+        let mut edited = String::with_capacity(source.len() + self.text.len() - self.range.len());
+
+        edited.push_str(&source[..self.range.start]);
+        edited.push_str(self.text);
+        edited.push_str(&source[self.range.end..]);
+
+        Ok(edited)
+    }
+}
+
+/// The result of relexing an edit, `start..end` being the range of the old source that was relexed
+struct Relexed<'a> {
+    start: usize,
+    end: usize,
+    tokens: &'a [SpannedToken<'a>],
+    comments: &'a [Comment<'a>],
+}
+
+/// Moves spans after an edit by the change in length of the source
+#[derive(Clone, Copy)]
+struct Shift<'a> {
+    bump: &'a Bump,
+    delta: isize,
+}
+
+impl Shift<'_> {
+    fn pos(&self, pos: usize) -> usize {
+        (pos as isize + self.delta) as usize
+    }
+
+    fn unshift(&self, pos: usize) -> usize {
+        (pos as isize - self.delta) as usize
+    }
+}
+
+impl<'b> Fold<'b> for Shift<'b> {
+    fn bump(&self) -> &'b Bump {
+        self.bump
+    }
+
+    fn fold_span(&mut self, v: Span) -> Span {
+        self.pos(v.start)..self.pos(v.end)
+    }
+}
+
+/// The span of `stat` including its comments
+fn extent(stat: &Node<&Stat>) -> Span {
+    let span = stat.span();
+
+    let start = stat
+        .leading_comments()
+        .first()
+        .map_or(span.start, |c| c.span().start.min(span.start));
+    let end = stat
+        .trailing_comments()
+        .last()
+        .map_or(span.end, |c| c.span().end.max(span.end));
+
+    start..end
+}
+
+/// The contiguous run of `items` matching `predicate`
+fn slice<T>(items: &[T], predicate: impl Fn(&T) -> bool) -> &[T] {
+    let start = items.iter().position(&predicate).unwrap_or(items.len());
+    let len = items[start..]
+        .iter()
+        .take_while(|item| predicate(item))
+        .count();
+
+    &items[start..start + len]
+}
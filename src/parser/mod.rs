@@ -1,24 +1,31 @@
-use std::fmt::Debug;
+use std::{cell::RefCell, fmt::Debug};
 
-use bumpalo::{
-    collections::{String as BumpString, Vec as BumpVec},
-    Bump,
-};
+use bumpalo::{collections::Vec as BumpVec, Bump};
 pub use error::Error;
 use logos::Logos;
 pub use logos::Span;
 
 use crate::{
-    ast::{exps::*, node::Node, stats::*, Exp, Stat, *},
+    ast::{
+        exps::*,
+        node::Node,
+        stats::*,
+        trivia::{Comment, CommentKind, Trivia},
+        Exp, Stat, *,
+    },
     lexer::*,
     parser::{
         error::Expectation,
         parselets::{led, nud, nud::TableConstructorParselet, Led, Nud},
+        stream::Stream,
     },
 };
 
+pub mod batch;
 pub mod error;
+pub mod incremental;
 mod parselets;
+mod stream;
 
 pub type Result<'a, T, E = Error<'a>> = std::result::Result<T, E>;
 
@@ -26,8 +33,20 @@ pub type SpannedToken<'a> = (Token<'a>, Span);
 
 pub struct Parser<'a> {
     bump: &'a Bump,
-    tokens: &'a [SpannedToken<'a>],
+    tokens: Tokens<'a>,
     pos: usize,
+    comments: &'a [Comment<'a>],
+    comment_pos: usize,
+    /// Errors recovered from, only present when parsing with [`Parser::parse_chunk_recovering`]
+    errors: Option<Vec<Error<'a>>>,
+}
+
+/// Where the parser's tokens come from
+enum Tokens<'a> {
+    /// Lexed up front, e.g. by [`Parser::lex`]
+    Slice(&'a [SpannedToken<'a>]),
+    /// Lexed as they're needed, see [`Parser::stream_in`]
+    Stream(Box<RefCell<Stream<'a>>>),
 }
 
 enum Rewind<'a> {
@@ -55,49 +74,305 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Lex `source`, skipping over anything that isn't a token rather than stopping there.
+    ///
+    /// Returns the tokens alongside an [`Error::Lexer`] for each part skipped.
+    pub fn lex_recovering(
+        source: &'a str,
+        bump: &'a Bump,
+    ) -> (Vec<SpannedToken<'a>>, Vec<Error<'a>>) {
+        let lex = || {
+            let mut errors = Vec::new();
+
+            let tokens = Token::lexer_with_extras(source, bump)
+                .spanned()
+                .filter_map(|(res, span)| match res {
+                    Ok(Token::Comment(_)) => None,
+                    Ok(token) => Some((token, span)),
+                    Err(_) => {
+                        errors.push(Error::Lexer(span));
+
+                        None
+                    }
+                })
+                .collect();
+
+            (tokens, errors)
+        };
+
+        if cfg!(debug_assertions) {
+            stacker::maybe_grow(source.len() * 96, source.len() * 96, lex)
+        } else {
+            lex()
+        }
+    }
+
+    /// Lex `source`, retaining comments separately so that they can be attached to nodes as trivia
+    /// with [`Parser::with_comments`]
+    pub fn lex_with_comments(
+        source: &'a str,
+        bump: &'a Bump,
+    ) -> Result<'a, (Vec<SpannedToken<'a>>, Vec<Comment<'a>>)> {
+        let lex = || {
+            let mut tokens = Vec::new();
+            let mut comments = Vec::new();
+
+            let mut last_end = 0;
+
+            for (res, span) in Token::lexer_with_extras(source, bump).spanned() {
+                let own_line = last_end == 0
+                    || memchr::memchr(b'\n', &source.as_bytes()[last_end..span.start]).is_some();
+
+                last_end = span.end;
+
+                match res {
+                    Ok(Token::Comment(text)) => {
+                        let kind = comment_kind(&source[span.clone()]);
+
+                        comments.push(Comment::new(kind, text, own_line, span))
+                    }
+                    Ok(token) => tokens.push((token, span)),
+                    Err(_) => return Err(Error::Lexer(span)),
+                }
+            }
+
+            Ok((tokens, comments))
+        };
+
+        if cfg!(debug_assertions) {
+            stacker::maybe_grow(source.len() * 96, source.len() * 96, lex)
+        } else {
+            lex()
+        }
+    }
+
     pub fn new_in(tokens: &'a [SpannedToken<'a>], bump: &'a Bump) -> Self {
         Self {
-            tokens,
+            tokens: Tokens::Slice(tokens),
             bump,
             pos: 0,
+            comments: &[],
+            comment_pos: 0,
+            errors: None,
         }
     }
 
+    /// Parse `source` while lexing it, rather than lexing it all up front with [`Parser::lex`].
+    ///
+    /// Only a few tokens around the one being parsed are kept, so this uses much less memory for
+    /// large files. Tokens are lexed again if the parser rewinds past them. Comments are skipped, and an unrecognised
+    /// token is only reported if it's reached before any syntax error.
+    pub fn stream_in(source: &'a str, bump: &'a Bump) -> Self {
+        Self {
+            tokens: Tokens::Stream(Box::new(RefCell::new(Stream::new(source, bump)))),
+            bump,
+            pos: 0,
+            comments: &[],
+            comment_pos: 0,
+            errors: None,
+        }
+    }
+
+    /// The most tokens that have been held at once, which is every token unless streaming
+    pub fn peak_buffered_tokens(&self) -> usize {
+        match &self.tokens {
+            Tokens::Slice(tokens) => tokens.len(),
+            Tokens::Stream(stream) => stream.borrow().peak(),
+        }
+    }
+
+    /// Attach `comments` (as produced by [`Parser::lex_with_comments`]) to the parsed nodes
+    pub fn with_comments(mut self, comments: &'a [Comment<'a>]) -> Self {
+        self.comments = comments;
+
+        self
+    }
+
     pub fn parse_chunk(&mut self) -> Result<'a, Block<'a>> {
-        let block = self.parse_block()?;
+        let block = self.parse_block();
+
+        // The stream ending early on an unrecognised token would otherwise look like the end
+        if let Some(err) = self.lexer_error() {
+            return Err(err);
+        }
 
-        match self.tokens.get(self.pos) {
+        let block = block?;
+
+        match self.token(self.pos) {
             None => Ok(block),
-            Some((token, span)) => Err(Error::unexpected_token(span, Expectation::Eof, *token)),
+            Some((token, span)) => Err(Error::unexpected_token(&span, Expectation::Eof, token)),
+        }
+    }
+
+    /// Parse a chunk, recovering from errors by skipping to the next statement and inserting a
+    /// [`Stat::Error`] in place of anything that couldn't be parsed.
+    ///
+    /// Returns the best-effort chunk alongside every error encountered.
+    pub fn parse_chunk_recovering(&mut self) -> (Block<'a>, Vec<Error<'a>>) {
+        self.errors = Some(Vec::new());
+
+        let mut stats = BumpVec::new_in(self.bump);
+
+        loop {
+            match self.parse_block() {
+                Ok(block) => stats.extend_from_slice(block),
+                Err(err) => self.errors.get_or_insert_with(Vec::new).push(err),
+            }
+
+            // Anything left over must be a stray `end`, `else`, `elseif` or `until`
+            let Some((token, span)) = self.token(self.pos) else {
+                break;
+            };
+
+            let err = Error::unexpected_token(&span, Expectation::Eof, token);
+
+            if let Ok(stat) = self.recover(err, self.pos) {
+                stats.push(stat);
+            }
         }
+
+        let mut errors = self.errors.take().unwrap_or_default();
+
+        errors.extend(self.lexer_error());
+
+        (stats.into_bump_slice(), errors)
     }
 
     fn parse_block(&mut self) -> Result<'a, Block<'a>> {
+        let checkpoint = self.checkpoint();
+
+        let block = self.parse_stats(checkpoint);
+
+        self.release(checkpoint);
+
+        block
+    }
+
+    fn parse_stats(&mut self, checkpoint: usize) -> Result<'a, Block<'a>> {
         let mut stats = BumpVec::new_in(self.bump);
 
-        // Rewind here, because Lua has SYNTACTICALLY ASCENDED THE MORTAL FUCKING PLANE
-        while let Some(stat) = self.with_rewind(|p| match p.node(Self::parse_stat) {
-            Err(
-                Error::UnexpectedEof { .. }
-                | Error::UnexpectedToken {
-                    expected: Some(Expectation::Stat),
-                    ..
-                },
-            ) => Err(Rewind::Rewind),
-            res => res.map_err(Rewind::Abort),
-        })? {
-            self.consume_a(Token::Semicolon);
+        loop {
+            let start = self.pos;
+
+            // Move the block's checkpoint to the start of each statement, which is where one that
+            // fails to parse is recovered from
+            self.release(checkpoint);
+            self.checkpoint();
+
+            // Rewind here, because Lua has SYNTACTICALLY ASCENDED THE MORTAL FUCKING PLANE
+            match self.with_rewind(|p| match p.node(Self::parse_stat) {
+                // When recovering, running out of tokens part way through a statement is an error
+                // rather than the end of the block
+                Err(err @ Error::UnexpectedEof { .. }) if p.errors.is_some() && p.pos > start => {
+                    Err(Rewind::Abort(err))
+                }
+                Err(
+                    Error::UnexpectedEof { .. }
+                    | Error::UnexpectedToken {
+                        expected: Some(Expectation::Stat),
+                        ..
+                    },
+                ) => Err(Rewind::Rewind),
+                res => res.map_err(Rewind::Abort),
+            }) {
+                Ok(Some(stat)) => {
+                    self.consume_a(Token::Semicolon);
+
+                    stats.push(self.claim_trailing_comments(stat));
 
+                    continue;
+                }
+
+                Ok(None) => {}
+
+                Err(err) => {
+                    stats.push(self.recover(err, start)?);
+
+                    continue;
+                }
+            }
+
+            let last_stat = self.next_is_in([Keyword::Break, Keyword::Continue, Keyword::Return]);
+
+            if last_stat {
+                match self.node(|p| p.parse_last_stat()) {
+                    Ok(stat) => {
+                        self.consume_a(Token::Semicolon);
+
+                        stats.push(self.claim_trailing_comments(stat));
+                    }
+
+                    Err(err) => {
+                        stats.push(self.recover(err, start)?);
+
+                        continue;
+                    }
+                }
+            }
+
+            // When recovering, skip over anything that can neither start a statement nor end the
+            // block, which includes any statements following a `return`
+            if self.errors.is_some() {
+                if let Some((token, span)) = self.token(self.pos) {
+                    if !BLOCK_END.iter().any(|end| end.eq(&token)) {
+                        let expected = (!last_stat).then_some(Expectation::Stat);
+
+                        let err = Error::unexpected_token(&span, expected, token);
+
+                        stats.push(self.recover(err, self.pos)?);
+
+                        continue;
+                    }
+                }
+            }
+
+            break;
+        }
+
+        // Comments after the last statement of a block are kept on an empty statement
+        if let Some(stat) = self.dangling_comments() {
             stats.push(stat);
         }
 
-        if self.next_is_in([Keyword::Break, Keyword::Continue, Keyword::Return]) {
-            stats.push(self.node(|p| p.parse_last_stat())?);
+        Ok(stats.into_bump_slice())
+    }
 
-            self.consume_a(Token::Semicolon);
+    /// Record `err` and skip to the next token that may start or end a statement, producing a
+    /// [`Stat::Error`] spanning everything from `start`. Fails with `err` unless recovering.
+    fn recover(&mut self, err: Error<'a>, start: usize) -> Result<'a, Node<'a, &'a Stat<'a>>> {
+        let Some(errors) = &mut self.errors else {
+            return Err(err);
+        };
+
+        errors.push(err);
+
+        // Read before skipping ahead, as it might not be kept when streaming
+        let first = self.token(start).map(|(_, span)| span.start);
+
+        // Always make progress, but don't skip a keyword that was only consumed to report the error
+        match self.previous_token() {
+            _ if self.pos == start => self.pos += 1,
+            Some((token, _)) if self.pos > start + 1 && SYNC.iter().any(|s| s.eq(&token)) => {
+                self.pos -= 1
+            }
+            _ => {}
         }
 
-        Ok(stats.into_bump_slice())
+        while let Some((token, _)) = self.token(self.pos) {
+            if SYNC.iter().any(|sync| sync.eq(&token)) || matches!(token, Token::Label(_)) {
+                break;
+            }
+
+            self.pos += 1;
+        }
+
+        let (Some(first), Some((_, last))) = (first, self.previous_token()) else {
+            unreachable!("a token has been consumed since `start`");
+        };
+
+        let span = first..last.end;
+
+        Ok(Node::new(span, &Stat::Error))
     }
 
     pub fn parse_stat(&mut self) -> Result<'a, Stat<'a>> {
@@ -145,9 +420,9 @@ impl<'a> Parser<'a> {
                         Exp::MethodCall(call) => Ok(Stat::MethodCall(call)),
 
                         _ => Err(Error::unexpected_token(
-                            self.span()?,
+                            &self.span()?,
                             Expectation::FunctionCall,
-                            *token,
+                            token,
                         )),
                     },
                 }
@@ -156,12 +431,14 @@ impl<'a> Parser<'a> {
             Token::Keyword(keyword) => {
                 self.consume()?;
 
+                let opener = self.last_token()?;
+
                 match keyword {
                     // do block end
                     Keyword::Do => {
                         let body = self.parse_block()?;
 
-                        self.expect(Keyword::End)?;
+                        self.expect_closing(Keyword::End, &opener)?;
 
                         Ok(Do::new(body).into())
                     }
@@ -170,11 +447,11 @@ impl<'a> Parser<'a> {
                     Keyword::While => {
                         let cond = self.node(Self::parse_exp)?;
 
-                        self.expect(Keyword::Do)?;
+                        self.expect_closing(Keyword::Do, &opener)?;
 
                         let body = self.parse_block()?;
 
-                        self.expect(Keyword::End)?;
+                        self.expect_closing(Keyword::End, &opener)?;
 
                         Ok(While::new(cond, body).into())
                     }
@@ -183,7 +460,7 @@ impl<'a> Parser<'a> {
                     Keyword::Repeat => {
                         let body = self.parse_block()?;
 
-                        self.expect(Keyword::Until)?;
+                        self.expect_closing(Keyword::Until, &opener)?;
 
                         let cond = self.node(Self::parse_exp)?;
 
@@ -194,16 +471,18 @@ impl<'a> Parser<'a> {
                     Keyword::If => {
                         let cond = self.node(Self::parse_exp)?;
 
-                        self.expect(Keyword::Then)?;
+                        self.expect_closing(Keyword::Then, &opener)?;
 
                         let body = self.parse_block()?;
 
                         let mut else_ifs = BumpVec::new_in(self.bump);
 
                         while self.consume_a(Keyword::ElseIf) {
+                            let opener = self.last_token()?;
+
                             let cond = self.node(Self::parse_exp)?;
 
-                            self.expect(Keyword::Then)?;
+                            self.expect_closing(Keyword::Then, &opener)?;
 
                             let body = self.parse_block()?;
 
@@ -215,7 +494,7 @@ impl<'a> Parser<'a> {
                             false => None,
                         };
 
-                        self.expect(Keyword::End)?;
+                        self.expect_closing(Keyword::End, &opener)?;
 
                         Ok(IfElse::new(cond, body, else_ifs.into_bump_slice(), else_block).into())
                     }
@@ -242,11 +521,11 @@ impl<'a> Parser<'a> {
                                 false => None,
                             };
 
-                            self.expect(Keyword::Do)?;
+                            self.expect_closing(Keyword::Do, &opener)?;
 
                             let body = self.parse_block()?;
 
-                            self.expect(Keyword::End)?;
+                            self.expect_closing(Keyword::End, &opener)?;
 
                             Ok(For::new(init, test, update, body).into())
                         }
@@ -259,11 +538,11 @@ impl<'a> Parser<'a> {
 
                             let exps = self.parse_list(|p| p.node(Self::parse_exp))?;
 
-                            self.expect(Keyword::Do)?;
+                            self.expect_closing(Keyword::Do, &opener)?;
 
                             let body = self.parse_block()?;
 
-                            self.expect(Keyword::End)?;
+                            self.expect_closing(Keyword::End, &opener)?;
 
                             Ok(
                                 ForIn::new(names.into_bump_slice(), exps.into_bump_slice(), body)
@@ -275,47 +554,25 @@ impl<'a> Parser<'a> {
                     // function funcname funcbody
                     Keyword::Function => {
                         let name = {
-                            let mut len = 0;
-
-                            let parts = self.parse_delimited(
-                                Op::Dot,
-                                |p| {
-                                    let part = p.parse_name()?;
-
-                                    len += part.len();
-
-                                    Ok(part)
-                                },
-                                |token| [Token::LParens, Token::Op(Op::Colon)].contains(token),
-                            )?;
-
-                            let mut name =
-                                BumpString::with_capacity_in(len + parts.len(), self.bump);
-
-                            parts.iter().for_each(|part| {
-                                name.push_str(part);
-                                name.push('.');
-                            });
-
-                            name.pop().unwrap();
+                            let base = self.parse_name()?;
 
-                            if self.consume_a(Op::Colon) {
-                                let part = self.parse_name()?;
+                            let mut fields = BumpVec::new_in(self.bump);
 
-                                // We already reserved a single extra byte for the trailing `.` that
-                                // is unused, so we will re-use it for the `:`
-                                name.reserve_exact(part.len());
-
-                                name.push(':');
-                                name.push_str(part)
+                            while self.consume_a(Op::Dot) {
+                                fields.push(self.parse_name()?);
                             }
 
-                            name
+                            let method = match self.consume_a(Op::Colon) {
+                                true => Some(self.parse_name()?),
+                                false => None,
+                            };
+
+                            FunctionName::new(base, fields.into_bump_slice(), method)
                         };
 
-                        let body = self.node(|p| p.parse_function())?;
+                        let body = self.node(|p| p.parse_function(&opener))?;
 
-                        Ok(FunctionDef::new(false, name.into_bump_str(), body).into())
+                        Ok(FunctionDef::new(false, name, body).into())
                     }
 
                     Keyword::Local => match self.peek(0)? {
@@ -323,9 +580,11 @@ impl<'a> Parser<'a> {
                         Token::Keyword(Keyword::Function) => {
                             self.consume()?;
 
-                            let name = self.parse_name()?;
+                            let opener = self.last_token()?;
 
-                            let body = self.node(|p| p.parse_function())?;
+                            let name = FunctionName::new(self.parse_name()?, &[], None);
+
+                            let body = self.node(|p| p.parse_function(&opener))?;
 
                             Ok(FunctionDef::new(true, name, body).into())
                         }
@@ -353,20 +612,22 @@ impl<'a> Parser<'a> {
                             // goto Name
                             self.consume()?;
 
-                            Ok(Goto::new(label).into())
+                            let span = self.last_span()?;
+
+                            Ok(Goto::new(Node::new(span, label)).into())
                         }
 
                         token => Err(Error::unexpected_token(
-                            self.span()?,
+                            &self.span()?,
                             Expectation::Name,
-                            *token,
+                            token,
                         )),
                     },
 
                     _ => Err(Error::unexpected_token(
-                        self.span()?,
+                        &self.last_span()?,
                         Expectation::Stat,
-                        *keyword,
+                        keyword,
                     )),
                 }
             }
@@ -374,13 +635,16 @@ impl<'a> Parser<'a> {
             Token::Label(name) => {
                 self.consume()?;
 
-                Ok(Label::new(name).into())
+                // Just the name, without the surrounding `::`
+                let span = self.last_span()?;
+
+                Ok(Label::new(Node::new(span.start + 2..span.end - 2, name)).into())
             }
 
             token => Err(Error::unexpected_token(
-                self.span()?,
+                &self.span()?,
                 Expectation::Stat,
-                *token,
+                token,
             )),
         }
     }
@@ -388,6 +652,8 @@ impl<'a> Parser<'a> {
     fn parse_last_stat(&mut self) -> Result<'a, Stat<'a>> {
         match self.consume()? {
             Token::Keyword(Keyword::Return) => {
+                let start = self.pos;
+
                 match self.with_rewind(|parser| {
                     let res = parser.parse_list(|p| p.node(Self::parse_exp));
 
@@ -396,6 +662,10 @@ impl<'a> Parser<'a> {
                             expected: Some(Expectation::Expression),
                             ..
                         }) => Err(Rewind::Rewind),
+                        // A bare `return` at the end of the chunk
+                        Err(Error::UnexpectedEof { .. }) if parser.pos == start => {
+                            Err(Rewind::Rewind)
+                        }
                         _ => res.map_err(Rewind::Abort),
                     }
                 })? {
@@ -410,9 +680,9 @@ impl<'a> Parser<'a> {
             Token::Keyword(Keyword::Continue) => Ok(Stat::Continue),
 
             token => Err(Error::unexpected_token(
-                self.span()?,
+                &self.last_span()?,
                 Expectation::tokens([Keyword::Return, Keyword::Continue, Keyword::Break]),
-                *token,
+                token,
             )),
         }
     }
@@ -436,15 +706,15 @@ impl<'a> Parser<'a> {
             let token = self.consume()?;
 
             lhs = match get_led_parselet(token) {
-                Some(parselet) => {
-                    self.stack_node(move |p| parselet.parse(p, p.alloc_node(lhs), token))?
-                }
+                Some(parselet) => self.stack_node_from(lhs.span().start, move |p| {
+                    parselet.parse(p, p.alloc_node(lhs), token)
+                })?,
 
-                None => return Err(Error::unexpected_token(self.span()?, None, *token)),
+                None => return Err(Error::unexpected_token(&self.last_span()?, None, token)),
             }
         }
 
-        Ok(lhs.into_inner())
+        Ok(self.unwrap_node(lhs))
     }
 
     fn parse_prefix_exp(&mut self) -> Result<'a, Exp<'a>> {
@@ -455,16 +725,16 @@ impl<'a> Parser<'a> {
                 Some(parselet) => parselet.parse(p, token),
 
                 None => Err(Error::unexpected_token(
-                    p.last_span()?,
+                    &p.last_span()?,
                     Expectation::Expression,
-                    *token,
+                    token,
                 )),
             }
         })?;
 
         while let Ok(next) = self.peek(0) {
             if let Some(parselet) = get_prefix_led_parselet(next) {
-                lhs = self.stack_node(|p| {
+                lhs = self.stack_node_from(lhs.span().start, |p| {
                     let token = p.consume()?;
 
                     parselet.parse(p, p.alloc_node(lhs), token)
@@ -474,7 +744,7 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(lhs.into_inner())
+        Ok(self.unwrap_node(lhs))
     }
 
     /// Parses a var, basically a more selective prefixexp
@@ -489,31 +759,35 @@ impl<'a> Parser<'a> {
             Exp::Ref(_) => Ok(exp),
 
             _ => Err(Error::unexpected_expression(
-                self.span()?,
+                &self.span()?,
                 Expectation::Var,
                 exp,
             )),
         }
     }
 
-    fn parse_function(&mut self) -> Result<'a, Function<'a>> {
+    /// Parse a function's parameters and body, `opener` being the `function` keyword
+    fn parse_function(&mut self, opener: &SpannedToken<'a>) -> Result<'a, Function<'a>> {
         self.expect(Token::LParens)?;
 
-        let mut params = self.parse_delimited(Token::Comma, Self::parse_name, |token| {
+        let lparens = self.last_token()?;
+
+        let params = self.parse_delimited(Token::Comma, Self::parse_name, |token| {
             matches!(token, Token::Ellipsis | Token::RParens)
         })?;
 
-        if self.consume_a(Token::Ellipsis) {
-            params.push("...");
-        }
+        let vararg = match self.consume_a(Token::Ellipsis) {
+            true => Some(Node::new(self.last_span()?, ())),
+            false => None,
+        };
 
-        self.expect(Token::RParens)?;
+        self.expect_closing(Token::RParens, &lparens)?;
 
         let body = self.parse_block()?;
 
-        self.expect(Keyword::End)?;
+        self.expect_closing(Keyword::End, opener)?;
 
-        Ok(Function::new(params.into_bump_slice(), body))
+        Ok(Function::new(params.into_bump_slice(), vararg, body))
     }
 
     // <Helpers>
@@ -533,18 +807,17 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn peek(&self, n: usize) -> Result<'a, &'a Token<'a>> {
-        match self.tokens.get(self.pos + n) {
+    fn peek(&self, n: usize) -> Result<'a, Token<'a>> {
+        match self.token(self.pos + n) {
             Some((token, _)) => Ok(token),
 
-            None => Err(Error::UnexpectedEof { expected: None }),
+            None => Err(Error::unexpected_eof(None)),
         }
     }
 
-    fn consume(&mut self) -> Result<'a, &'a Token<'a>> {
-        self.tokens
-            .get(self.pos)
-            .ok_or(Error::UnexpectedEof { expected: None })
+    fn consume(&mut self) -> Result<'a, Token<'a>> {
+        self.token(self.pos)
+            .ok_or(Error::unexpected_eof(None))
             .map(|(token, _)| {
                 self.pos += 1;
 
@@ -564,13 +837,22 @@ impl<'a> Parser<'a> {
             _ => unreachable!(),
         };
 
-        if expected.eq(got) {
+        if expected.eq(&got) {
             Ok(())
         } else {
-            Err(Error::unexpected_token(self.span()?, expected, *got))
+            Err(Error::unexpected_token(&self.last_span()?, expected, got))
         }
     }
 
+    /// Expect the token closing the construct opened by `opener`, e.g. the `end` of an `if`
+    fn expect_closing<E>(&mut self, expected: E, opener: &SpannedToken<'a>) -> Result<'a, ()>
+    where
+        E: Debug + Into<Option<Expectation<'a>>> + PartialEq<Token<'a>>,
+    {
+        self.expect(expected)
+            .map_err(|err| err.opened_by(opener.0, &opener.1))
+    }
+
     fn consume_a(&mut self, expected: impl PartialEq<Token<'a>>) -> bool {
         let consume = self.next_is(expected);
 
@@ -595,7 +877,7 @@ impl<'a> Parser<'a> {
     }
 
     fn next_is(&mut self, expected: impl PartialEq<Token<'a>>) -> bool {
-        self.peek(0).map(|got| expected.eq(got)).unwrap_or(false)
+        self.peek(0).map(|got| expected.eq(&got)).unwrap_or(false)
     }
 
     fn next_is_in<P>(&mut self, possibilities: impl IntoIterator<Item = P>) -> bool
@@ -606,20 +888,25 @@ impl<'a> Parser<'a> {
             return false;
         };
 
-        possibilities.into_iter().any(|p| p.eq(next))
+        possibilities.into_iter().any(|p| p.eq(&next))
     }
 
     fn with_rewind<T, F>(&mut self, func: F) -> Result<'a, Option<T>>
     where
         F: FnOnce(&mut Parser<'a>) -> Result<'a, T, Rewind<'a>>,
     {
-        let rewind_to = self.pos;
+        let rewind_to = (self.pos, self.comment_pos);
+
+        let checkpoint = self.checkpoint();
+        let res = func(self);
+
+        self.release(checkpoint);
 
-        match func(self) {
+        match res {
             Ok(result) => Ok(Some(result)),
             Err(err) => match err {
                 Rewind::Rewind => {
-                    self.pos = rewind_to;
+                    (self.pos, self.comment_pos) = rewind_to;
 
                     Ok(None)
                 }
@@ -630,55 +917,202 @@ impl<'a> Parser<'a> {
     }
 
     /// Produce and allocate a node on the bump heap
-    fn node<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<'a, T>) -> Result<'a, Node<&'a T>> {
+    fn node<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<'a, T>,
+    ) -> Result<'a, Node<'a, &'a T>> {
         let node = self.stack_node(f)?;
 
         Ok(self.alloc_node(node))
     }
 
     /// Produce a stack-allocated node
-    fn stack_node<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<'a, T>) -> Result<'a, Node<T>> {
+    fn stack_node<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<'a, T>,
+    ) -> Result<'a, Node<'a, T>> {
         let start = self.span()?.start;
 
+        self.stack_node_from(start, f)
+    }
+
+    /// Produce a stack-allocated node starting at `start`, attaching any comments before or within
+    /// it that haven't been claimed by an inner node
+    fn stack_node_from<T>(
+        &mut self,
+        start: usize,
+        f: impl FnOnce(&mut Self) -> Result<'a, T>,
+    ) -> Result<'a, Node<'a, T>> {
+        let leading = self.claim_comments(start);
+
         let inner = f(self)?;
 
-        let span = start..self.last_span()?.end;
+        let end = self.last_span()?.end;
+
+        let trailing = self.claim_comments(end);
 
-        Ok(Node::new(span, inner))
+        Ok(Node::new(start..end, inner).with_trivia(self.trivia(leading, trailing)))
+    }
+
+    /// Unwrap a node that is about to be wrapped by an outer node spanning the same source,
+    /// releasing its trailing comments to be claimed by the outer node instead
+    fn unwrap_node<T>(&mut self, node: Node<'a, T>) -> T {
+        // The trailing comments were the last to be claimed
+        self.comment_pos -= node.trailing_comments().len();
+
+        node.into_inner()
     }
 
     /// Allocate a stack node on the bump heap
-    fn alloc_node<T>(&self, node: Node<T>) -> Node<&'a T> {
+    fn alloc_node<T>(&self, node: Node<'a, T>) -> Node<'a, &'a T> {
         Node::map(node, |value| self.bump.alloc(value) as &_)
     }
 
-    fn span(&self) -> Result<'a, &'a Span> {
-        self.tokens
-            .get(self.pos)
+    /// Claim unclaimed comments that end before `offset`
+    fn claim_comments(&mut self, offset: usize) -> &'a [Comment<'a>] {
+        if self.comments.is_empty() {
+            return &[];
+        }
+
+        let start = self.comment_pos;
+
+        while let Some(comment) = self.comments.get(self.comment_pos) {
+            if comment.span().end > offset {
+                break;
+            }
+
+            self.comment_pos += 1;
+        }
+
+        &self.comments[start..self.comment_pos]
+    }
+
+    /// Attach comments following `stat` on the same line as its trailing trivia
+    fn claim_trailing_comments(&mut self, stat: Node<'a, &'a Stat<'a>>) -> Node<'a, &'a Stat<'a>> {
+        if self.comments.is_empty() {
+            return stat;
+        }
+
+        // Any trailing comments `stat` already holds were the last to be claimed
+        let start = self.comment_pos - stat.trailing_comments().len();
+
+        let next = self.span().map(|span| span.start).unwrap_or(usize::MAX);
+
+        while let Some(comment) = self.comments.get(self.comment_pos) {
+            if comment.own_line || comment.span().end > next {
+                break;
+            }
+
+            self.comment_pos += 1;
+        }
+
+        if start + stat.trailing_comments().len() == self.comment_pos {
+            return stat;
+        }
+
+        let trivia = self.trivia(
+            stat.leading_comments(),
+            &self.comments[start..self.comment_pos],
+        );
+
+        stat.with_trivia(trivia)
+    }
+
+    /// Produce an empty statement holding any unclaimed comments before the next token
+    fn dangling_comments(&mut self) -> Option<Node<'a, &'a Stat<'a>>> {
+        if self.comments.is_empty() {
+            return None;
+        }
+
+        let next = self.span().map(|span| span.start).unwrap_or(usize::MAX);
+
+        let comments = self.claim_comments(next);
+
+        let (first, last) = (comments.first()?, comments.last()?);
+
+        let node = Node::new(first.span().start..last.span().end, &Stat::None);
+
+        Some(node.with_trivia(self.trivia(comments, &[])))
+    }
+
+    fn trivia(
+        &self,
+        leading: &'a [Comment<'a>],
+        trailing: &'a [Comment<'a>],
+    ) -> Option<&'a Trivia<'a>> {
+        if leading.is_empty() && trailing.is_empty() {
+            return None;
+        }
+
+        Some(self.bump.alloc(Trivia { leading, trailing }))
+    }
+
+    fn span(&self) -> Result<'a, Span> {
+        self.token(self.pos)
             .map(|(_, span)| span)
-            .ok_or(Error::UnexpectedEof { expected: None })
+            .ok_or(Error::unexpected_eof(None))
+    }
+
+    /// The token at `index`, lexing it first if streaming
+    fn token(&self, index: usize) -> Option<SpannedToken<'a>> {
+        match &self.tokens {
+            Tokens::Slice(tokens) => tokens.get(index).cloned(),
+            Tokens::Stream(stream) => stream.borrow_mut().get(index),
+        }
     }
 
-    fn last_span(&self) -> Result<'a, &'a Span> {
-        self.tokens
-            .get(self.pos.wrapping_sub(1))
+    /// Keep the current token available to rewind to while streaming, until released
+    fn checkpoint(&self) -> usize {
+        match &self.tokens {
+            Tokens::Slice(_) => 0,
+            Tokens::Stream(stream) => stream.borrow_mut().checkpoint(self.pos),
+        }
+    }
+
+    /// Release the checkpoint returned by [`Parser::checkpoint`], along with any made after it
+    fn release(&self, checkpoint: usize) {
+        if let Tokens::Stream(stream) = &self.tokens {
+            stream.borrow_mut().release(checkpoint);
+        }
+    }
+
+    fn previous_token(&self) -> Option<SpannedToken<'a>> {
+        self.token(self.pos.checked_sub(1)?)
+    }
+
+    /// The error that ended the stream of tokens early, if streaming
+    fn lexer_error(&mut self) -> Option<Error<'a>> {
+        match &mut self.tokens {
+            Tokens::Slice(_) => None,
+            Tokens::Stream(stream) => stream.get_mut().take_error(),
+        }
+    }
+
+    fn last_token(&self) -> Result<'a, SpannedToken<'a>> {
+        self.previous_token().ok_or(Error::unexpected_eof(None))
+    }
+
+    fn last_span(&self) -> Result<'a, Span> {
+        self.previous_token()
             .map(|(_, span)| span)
-            .ok_or(Error::UnexpectedEof { expected: None })
+            .ok_or(Error::unexpected_eof(None))
     }
     // </Helpers>
 
     // <Parse Helpers>
     /// Parse function / method arguments
-    fn parse_args(&mut self, token: &'a Token<'a>) -> Result<'a, BumpVec<'a, Node<&'a Exp<'a>>>> {
+    fn parse_args(&mut self, token: Token<'a>) -> Result<'a, BumpVec<'a, Node<'a, &'a Exp<'a>>>> {
         match token {
             // function(arg, arg2)
             Token::LParens => {
+                let opener = self.last_token()?;
+
                 if self.consume_a(Token::RParens) {
                     Ok(BumpVec::new_in(self.bump))
                 } else {
                     let args = self.parse_list(|p| p.node(Self::parse_exp))?;
 
-                    self.expect(Token::RParens)?;
+                    self.expect_closing(Token::RParens, &opener)?;
 
                     Ok(args)
                 }
@@ -688,39 +1122,38 @@ impl<'a> Parser<'a> {
             Token::LBrace => {
                 let start = self.last_span()?.start;
 
-                let inner = TableConstructorParselet.parse(self, token)?;
-
-                let span = start..self.last_span()?.end;
+                let node =
+                    self.stack_node_from(start, |p| TableConstructorParselet.parse(p, token))?;
 
-                Ok(bumpalo::vec![in self.bump; self.alloc_node(Node::new(span, inner))])
+                Ok(bumpalo::vec![in self.bump; self.alloc_node(node)])
             }
 
             // function"string"
             Token::Literal(Literal::String(arg)) => Ok(bumpalo::vec![
                 in self.bump;
-                self.alloc_node(Node::new(self.last_span()?.clone(), Exp::String(arg)))
+                self.alloc_node(Node::new(self.last_span()?, Exp::String(arg)))
             ]),
 
             token => Err(Error::unexpected_token(
-                self.span()?,
+                &self.last_span()?,
                 Expectation::Args,
-                *token,
+                token,
             )),
         }
     }
 
     /// Parse a name
-    fn parse_name(&mut self) -> Result<'a, &'a str> {
+    fn parse_name(&mut self) -> Result<'a, Name<'a>> {
         let token = self.consume()?;
 
         match token {
-            Token::Name(name) => Ok(name),
-            Token::Keyword(Keyword::Goto) => Ok("goto"),
+            Token::Name(name) => Ok(Node::new(self.last_span()?, name)),
+            Token::Keyword(Keyword::Goto) => Ok(Node::new(self.last_span()?, "goto")),
 
             _ => Err(Error::unexpected_token(
-                self.span()?,
+                &self.last_span()?,
                 Expectation::Name,
-                *token,
+                token,
             )),
         }
     }
@@ -739,7 +1172,7 @@ impl<'a> Parser<'a> {
     {
         let mut items = BumpVec::new_in(self.bump);
 
-        while !is_end(self.peek(0)?) {
+        while !is_end(&self.peek(0)?) {
             items.push(parse(self)?);
 
             if !self.consume_a(delim) {
@@ -769,7 +1202,29 @@ impl<'a> Parser<'a> {
     // </Parse Helpers>
 }
 
-const fn get_nud_parselet(token: &Token) -> Option<&'static dyn Nud> {
+/// Keywords that end a block
+const BLOCK_END: [Keyword; 4] = [Keyword::Else, Keyword::ElseIf, Keyword::End, Keyword::Until];
+
+/// Keywords that error recovery synchronises on
+const SYNC: [Keyword; 15] = [
+    Keyword::Break,
+    Keyword::Continue,
+    Keyword::Do,
+    Keyword::Else,
+    Keyword::ElseIf,
+    Keyword::End,
+    Keyword::For,
+    Keyword::Function,
+    Keyword::Goto,
+    Keyword::If,
+    Keyword::Local,
+    Keyword::Repeat,
+    Keyword::Return,
+    Keyword::Until,
+    Keyword::While,
+];
+
+const fn get_nud_parselet(token: Token) -> Option<&'static dyn Nud> {
     match token {
         Token::Ellipsis => Some(&nud::EllipsisParselet),
 
@@ -785,7 +1240,7 @@ const fn get_nud_parselet(token: &Token) -> Option<&'static dyn Nud> {
     }
 }
 
-const fn get_led_parselet(token: &Token) -> Option<&'static dyn Led> {
+const fn get_led_parselet(token: Token) -> Option<&'static dyn Led> {
     match token {
         Token::Op(Op::Exp) => Some(&led::ExponentiationParselet),
 
@@ -812,7 +1267,7 @@ const fn get_led_parselet(token: &Token) -> Option<&'static dyn Led> {
     }
 }
 
-const fn get_prefix_nud_parselet(token: &Token) -> Option<&'static dyn Nud> {
+const fn get_prefix_nud_parselet(token: Token) -> Option<&'static dyn Nud> {
     match token {
         Token::LParens => Some(&nud::ParensParselet),
 
@@ -822,7 +1277,7 @@ const fn get_prefix_nud_parselet(token: &Token) -> Option<&'static dyn Nud> {
     }
 }
 
-const fn get_prefix_led_parselet(token: &Token) -> Option<&'static dyn Led> {
+const fn get_prefix_led_parselet(token: Token) -> Option<&'static dyn Led> {
     match token {
         Token::LBracket | Token::Op(Op::Dot) => Some(&led::AccessParselet),
 
@@ -836,6 +1291,19 @@ const fn get_prefix_led_parselet(token: &Token) -> Option<&'static dyn Led> {
     }
 }
 
+pub(crate) fn comment_kind(slice: &str) -> CommentKind {
+    if slice.starts_with("//") {
+        CommentKind::CLine
+    } else if slice.starts_with("/*") {
+        CommentKind::CBlock
+    } else if slice[2..].starts_with('[') && slice[3..].starts_with(['=', '[']) {
+        // Mirrors the check in `lexer::token::comment`
+        CommentKind::Block
+    } else {
+        CommentKind::Line
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
 pub enum Precedence {
     None,
@@ -14,15 +14,17 @@ impl Led for AccessParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
         match token {
             // foo[Exp]
             Token::LBracket => {
+                let opener = parser.last_token()?;
+
                 let exp = parser.node(Parser::parse_exp)?;
 
-                parser.expect(Token::RBracket)?;
+                parser.expect_closing(Token::RBracket, &opener)?;
 
                 Ok(Index::new(lhs, exp).into())
             }
@@ -49,8 +51,8 @@ impl Led for AdditiveParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
         let op = match token {
             Token::Op(Op::Add) => BinOp::Add,
@@ -74,10 +76,10 @@ impl Led for AndParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Op(Op::And), *token);
+        debug_assert_eq!(Token::Op(Op::And), token);
 
         let rhs = parser.node(|p| p.parse_exp_prec(self.get_precedence()))?;
 
@@ -95,10 +97,10 @@ impl Led for ConcatParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Op(Op::DotDot), *token);
+        debug_assert_eq!(Token::Op(Op::DotDot), token);
 
         // Right associative so pass one lower precedence level than us
         let rhs = parser.node(|p| p.parse_exp_prec(Precedence::Comparative))?;
@@ -117,8 +119,8 @@ impl Led for ComparativeParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
         let op = match token {
             Token::Op(Op::EqEq) => BinOp::Eq,
@@ -146,10 +148,10 @@ impl Led for ExponentiationParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Op(Op::Exp), *token);
+        debug_assert_eq!(Token::Op(Op::Exp), token);
 
         // Right associative so pass one lower precedence level than us
         let rhs = parser.node(|p| p.parse_exp_prec(Precedence::Unary))?;
@@ -168,8 +170,8 @@ impl Led for FunctionCallParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
         let args = parser.parse_args(token)?;
 
@@ -187,10 +189,10 @@ impl Led for MethodCallParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Op(Op::Colon), *token);
+        debug_assert_eq!(Token::Op(Op::Colon), token);
 
         let name = parser.parse_name()?;
 
@@ -214,8 +216,8 @@ impl Led for MultiplicativeParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
         let op = match token {
             Token::Op(Op::Mod) => BinOp::Mod,
@@ -240,10 +242,10 @@ impl Led for OrParselet {
     fn parse<'a>(
         &self,
         parser: &mut Parser<'a>,
-        lhs: Node<&'a Exp>,
-        token: &'a Token<'a>,
+        lhs: Node<'a, &'a Exp>,
+        token: Token<'a>,
     ) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Op(Op::Or), *token);
+        debug_assert_eq!(Token::Op(Op::Or), token);
 
         let rhs = parser.node(|p| p.parse_exp_prec(self.get_precedence()))?;
 
@@ -12,8 +12,8 @@ use crate::{
 pub struct EllipsisParselet;
 
 impl Nud for EllipsisParselet {
-    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Ellipsis, *token);
+    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
+        debug_assert_eq!(Token::Ellipsis, token);
 
         Ok(Exp::VarArgs)
     }
@@ -22,22 +22,26 @@ impl Nud for EllipsisParselet {
 pub struct FunctionParselet;
 
 impl Nud for FunctionParselet {
-    fn parse<'a>(&self, parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::Keyword(Keyword::Function), *token);
+    fn parse<'a>(&self, parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
+        debug_assert_eq!(Token::Keyword(Keyword::Function), token);
 
-        parser.parse_function().map(|function| function.into())
+        let opener = parser.last_token()?;
+
+        parser
+            .parse_function(&opener)
+            .map(|function| function.into())
     }
 }
 
 pub struct LiteralParselet;
 
 impl Nud for LiteralParselet {
-    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
+    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
         match token {
             Token::Literal(literal) => match literal {
-                Literal::Bool(value) => Ok(Exp::Bool(*value)),
+                Literal::Bool(value) => Ok(Exp::Bool(value)),
                 Literal::Nil => Ok(Exp::Nil),
-                Literal::Number(value) => Ok(Exp::Number(*value)),
+                Literal::Number(value) => Ok(Exp::Number(value)),
                 Literal::String(value) => Ok(Exp::String(value)),
             },
 
@@ -49,7 +53,7 @@ impl Nud for LiteralParselet {
 pub struct NameParselet;
 
 impl Nud for NameParselet {
-    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
+    fn parse<'a>(&self, _parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
         let name = match token {
             Token::Name(name) => name,
             Token::Keyword(Keyword::Goto) => "goto",
@@ -63,22 +67,24 @@ impl Nud for NameParselet {
 pub struct ParensParselet;
 
 impl Nud for ParensParselet {
-    fn parse<'a>(&self, parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::LParens, *token);
+    fn parse<'a>(&self, parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
+        debug_assert_eq!(Token::LParens, token);
+
+        let opener = parser.last_token()?;
 
-        let exp = parser.parse_exp()?;
+        let exp = parser.node(Parser::parse_exp)?;
 
-        parser.expect(Token::RParens)?;
+        parser.expect_closing(Token::RParens, &opener)?;
 
-        Ok(exp)
+        Ok(Exp::Paren(exp))
     }
 }
 
 pub struct TableConstructorParselet;
 
 impl Nud for TableConstructorParselet {
-    fn parse<'a>(&self, parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
-        debug_assert_eq!(Token::LBrace, *token);
+    fn parse<'a>(&self, parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
+        debug_assert_eq!(Token::LBrace, token);
 
         let mut fields = Vec::new_in(parser.bump);
 
@@ -86,7 +92,7 @@ impl Nud for TableConstructorParselet {
             fields.push(match parser.peek(0)? {
                 // { name = Exp }
                 Token::Keyword(Keyword::Goto) | Token::Name(_)
-                    if parser.peek(1)? == &Token::Op(Op::Eq) =>
+                    if parser.peek(1)? == Token::Op(Op::Eq) =>
                 {
                     let key = parser.node(|p| p.parse_name().map(|s| Exp::String(s.as_bytes())))?;
 
@@ -105,9 +111,11 @@ impl Nud for TableConstructorParselet {
                 Token::LBracket => {
                     parser.consume()?;
 
+                    let opener = parser.last_token()?;
+
                     let key = parser.node(Parser::parse_exp)?;
 
-                    parser.expect(Token::RBracket)?;
+                    parser.expect_closing(Token::RBracket, &opener)?;
 
                     parser.expect(Op::Eq)?;
 
@@ -136,7 +144,7 @@ impl Nud for TableConstructorParselet {
 pub struct UnaryParselet;
 
 impl Nud for UnaryParselet {
-    fn parse<'a>(&self, parser: &mut Parser<'a>, token: &'a Token<'a>) -> Result<'a, Exp<'a>> {
+    fn parse<'a>(&self, parser: &mut Parser<'a>, token: Token<'a>) -> Result<'a, Exp<'a>> {
         let op = match token {
             Token::Op(Op::Len) => UnOp::Len,
             Token::Op(Op::Not) => UnOp::Not,
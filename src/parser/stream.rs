@@ -0,0 +1,127 @@
+//! Lexing tokens as the parser needs them, rather than all of them up front.
+
+use std::collections::VecDeque;
+
+use bumpalo::Bump;
+use logos::{Lexer, Logos};
+
+use crate::{
+    lexer::Token,
+    parser::{Error, SpannedToken},
+};
+
+/// How many tokens before the last one looked at are kept, so that short rewinds don't lex again
+const LOOKBEHIND: usize = 8;
+
+/// Tokens lexed from a source on demand, keeping only the few around the parser's position.
+///
+/// Tokens further back are lexed again from the innermost checkpoint before them if the parser
+/// rewinds to them.
+pub(super) struct Stream<'a> {
+    lexer: Lexer<'a, Token<'a>>,
+    /// The tokens lexed from `offset` onwards
+    buffer: VecDeque<SpannedToken<'a>>,
+    offset: usize,
+    /// The most tokens that have been in `buffer` at once
+    peak: usize,
+    /// The indices of the tokens the parser may rewind to, along with where in the source they
+    /// start, innermost last
+    checkpoints: Vec<(usize, usize)>,
+    /// The number of tokens, once the end of the source or an unrecognised token is reached
+    len: Option<usize>,
+    /// The unrecognised token that ended the stream
+    error: Option<Error<'a>>,
+}
+
+impl<'a> Stream<'a> {
+    pub fn new(source: &'a str, bump: &'a Bump) -> Self {
+        Self {
+            lexer: Token::lexer_with_extras(source, bump),
+            buffer: VecDeque::new(),
+            offset: 0,
+            peak: 0,
+            checkpoints: Vec::new(),
+            len: None,
+            error: None,
+        }
+    }
+
+    /// The token at `index`, lexing up to it if needed, or `None` past the end of the source
+    pub fn get(&mut self, index: usize) -> Option<SpannedToken<'a>> {
+        if index < self.offset {
+            self.rewind(index);
+        }
+
+        let index = index - self.offset;
+
+        while self.buffer.len() <= index {
+            if self.len == Some(self.offset + self.buffer.len()) {
+                return None;
+            }
+
+            match self.lexer.next() {
+                Some(Ok(Token::Comment(_))) => {}
+                Some(Ok(token)) => {
+                    self.buffer.push_back((token, self.lexer.span()));
+                    self.peak = self.peak.max(self.buffer.len());
+                }
+                Some(Err(_)) => {
+                    self.error = Some(Error::Lexer(self.lexer.span()));
+                    self.len = Some(self.offset + self.buffer.len());
+                }
+                None => self.len = Some(self.offset + self.buffer.len()),
+            }
+        }
+
+        let token = self.buffer[index].clone();
+
+        let discarded = index.saturating_sub(LOOKBEHIND);
+
+        self.buffer.drain(..discarded);
+        self.offset += discarded;
+
+        Some(token)
+    }
+
+    /// Allow rewinding to the token at `index` until released, returning what to release to
+    pub fn checkpoint(&mut self, index: usize) -> usize {
+        let start = self
+            .get(index)
+            .map_or(self.lexer.source().len(), |(_, span)| span.start);
+
+        self.checkpoints.push((index, start));
+
+        self.checkpoints.len() - 1
+    }
+
+    /// Drop the checkpoint returned by [`Stream::checkpoint`] along with any made after it
+    pub fn release(&mut self, checkpoint: usize) {
+        self.checkpoints.truncate(checkpoint);
+    }
+
+    /// Lex again from the innermost checkpoint at or before `index`, which has been discarded
+    fn rewind(&mut self, index: usize) {
+        let &(checkpoint, start) = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|(checkpoint, _)| *checkpoint <= index)
+            .expect("the parser only rewinds to checkpoints");
+
+        // Nothing past `len` is lexed again, so an unrecognised token is only reported once
+        self.lexer = Token::lexer_with_extras(self.lexer.source(), self.lexer.extras);
+        self.lexer.bump(start);
+
+        self.buffer.clear();
+        self.offset = checkpoint;
+    }
+
+    /// The most tokens that have been kept at once
+    pub fn peak(&self) -> usize {
+        self.peak
+    }
+
+    pub fn take_error(&mut self) -> Option<Error<'a>> {
+        self.error.take()
+    }
+}
@@ -0,0 +1,437 @@
+//! A whole addon or gamemode: every file parsed, along with the graph of which files include or
+//! send which with `include`, `AddCSLuaFile` and `IncludeCS`.
+//!
+//! Paths passed to those functions are resolved like Garry's Mod does, relative to the including
+//! file's directory first and then to the `lua` directory it's in (or `gamemodes` for a
+//! gamemode). Only string literals can be resolved.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bumpalo::Bump;
+use logos::Span;
+
+use crate::{
+    analysis::{
+        realm::{file_realm, Realms},
+        scope::{Resolution, ScopeTree},
+    },
+    api::Realm,
+    ast::{
+        exps::FunctionCall,
+        node::Node,
+        visitors::listener::{traverse, Ancestors, Listener},
+        Block, Exp, Name,
+    },
+    diagnostic::Diagnostic,
+    Parser, SourceMap,
+};
+
+#[derive(thiserror::Error, Debug)]
+#[error("failed to read {}: {source}", path.display())]
+pub struct Error {
+    pub path: PathBuf,
+    #[source]
+    pub source: io::Error,
+}
+
+pub struct Project<'a> {
+    files: Vec<File<'a>>,
+}
+
+pub struct File<'a> {
+    /// The file's path, with `/` separators
+    pub path: String,
+    pub source_map: SourceMap<'a>,
+    /// The parsed chunk, or `None` if the file has a syntax error
+    pub chunk: Option<Block<'a>>,
+    /// The syntax error that stopped the file from parsing
+    pub error: Option<Diagnostic>,
+    /// The realm the file runs in according to its path, see [`file_realm`]
+    pub realm: Option<Realm>,
+    pub dependencies: Vec<Dependency<'a>>,
+}
+
+/// A call to `include`, `AddCSLuaFile` or `IncludeCS`
+#[derive(Clone, Debug)]
+pub struct Dependency<'a> {
+    pub kind: DependencyKind,
+    /// The path passed, spanning its string literal, or `None` for `AddCSLuaFile()` which sends
+    /// the file calling it
+    pub path: Option<Name<'a>>,
+    /// The whole call
+    pub span: Span,
+    /// The realm the call runs in, if known
+    pub realm: Option<Realm>,
+    /// The index of the file the path resolves to, if it's in the project
+    pub file: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DependencyKind {
+    /// `include`, which runs the file
+    Include,
+    /// `AddCSLuaFile`, which sends the file to clients so they can include it
+    AddCSLuaFile,
+    /// `IncludeCS`, which does both
+    IncludeCS,
+}
+
+impl DependencyKind {
+    /// Whether the file is run
+    pub fn includes(self) -> bool {
+        matches!(self, Self::Include | Self::IncludeCS)
+    }
+
+    /// Whether the file is sent to clients
+    pub fn sends(self) -> bool {
+        matches!(self, Self::AddCSLuaFile | Self::IncludeCS)
+    }
+}
+
+impl<'a> Project<'a> {
+    /// Load every `.lua` file under `root`, allocating their sources and syntax trees in `bump`
+    pub fn load(root: impl AsRef<Path>, bump: &'a Bump) -> Result<Self, Error> {
+        let mut sources = Vec::new();
+
+        for path in lua_files(root.as_ref())? {
+            let source = fs::read_to_string(&path).map_err(|source| Error {
+                path: path.clone(),
+                source,
+            })?;
+
+            sources.push((
+                path.to_string_lossy().into_owned(),
+                &*bump.alloc_str(&source),
+            ));
+        }
+
+        Ok(Self::from_sources(sources, bump))
+    }
+
+    /// Parse the given files, named by their paths, and resolve their dependencies on each other
+    pub fn from_sources(
+        sources: impl IntoIterator<Item = (String, &'a str)>,
+        bump: &'a Bump,
+    ) -> Self {
+        let mut files = sources
+            .into_iter()
+            .map(|(path, source)| {
+                let path = path.replace('\\', "/");
+
+                let chunk = match Parser::lex(source, bump) {
+                    Ok(tokens) => {
+                        Parser::new_in(bump.alloc_slice_clone(&tokens), bump).parse_chunk()
+                    }
+                    Err(err) => Err(err),
+                };
+
+                let source_map = SourceMap::new(source).with_name(path.clone());
+                let realm = file_realm(&path);
+
+                let (chunk, error) = match chunk {
+                    Ok(chunk) => (Some(chunk), None),
+                    Err(err) => (None, Some(Diagnostic::from_error(&err, &source_map))),
+                };
+
+                // The files included by one with a syntax error are still found in what can be
+                // recovered of it, so they aren't reported as never included
+                let dependencies =
+                    Collector::collect(chunk.unwrap_or_else(|| recover(source, bump)), realm);
+
+                File {
+                    path,
+                    source_map,
+                    chunk,
+                    error,
+                    realm,
+                    dependencies,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let paths = files
+            .iter()
+            .map(|file| normalize(&file.path))
+            .collect::<Vec<_>>();
+
+        for (index, file) in files.iter_mut().enumerate() {
+            for dependency in &mut file.dependencies {
+                dependency.file = match dependency.path {
+                    Some(path) => candidates(&paths[index], *path)
+                        .into_iter()
+                        .find_map(|candidate| paths.iter().position(|path| *path == candidate)),
+                    None => Some(index),
+                };
+            }
+        }
+
+        Self { files }
+    }
+
+    pub fn files(&self) -> &[File<'a>] {
+        &self.files
+    }
+
+    pub fn file(&self, index: usize) -> &File<'a> {
+        &self.files[index]
+    }
+
+    /// The index of the file at `path`
+    pub fn find(&self, path: &str) -> Option<usize> {
+        let path = normalize(path);
+
+        self.files
+            .iter()
+            .position(|file| normalize(&file.path) == path)
+    }
+
+    /// The dependencies on the file at `index`, along with the index of the file each is in
+    pub fn dependents(&self, index: usize) -> impl Iterator<Item = (usize, &Dependency<'a>)> {
+        self.files.iter().enumerate().flat_map(move |(i, file)| {
+            file.dependencies
+                .iter()
+                .filter(move |dependency| dependency.file == Some(index))
+                .map(move |dependency| (i, dependency))
+        })
+    }
+
+    /// Every cycle of files that include each other, as the files in the order they're included,
+    /// starting from the one loaded first
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut search = CycleSearch {
+            project: self,
+            state: vec![Visit::New; self.files.len()],
+            stack: Vec::new(),
+            cycles: Vec::new(),
+        };
+
+        for index in 0..self.files.len() {
+            search.visit(index);
+        }
+
+        let mut seen = HashSet::new();
+
+        search
+            .cycles
+            .into_iter()
+            .filter(|cycle| {
+                let mut files = cycle.clone();
+                files.sort_unstable();
+
+                seen.insert(files)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    /// On the stack of files being searched
+    Open,
+    Done,
+}
+
+struct CycleSearch<'p, 'a> {
+    project: &'p Project<'a>,
+    state: Vec<Visit>,
+    stack: Vec<usize>,
+    cycles: Vec<Vec<usize>>,
+}
+
+impl CycleSearch<'_, '_> {
+    fn visit(&mut self, index: usize) {
+        if self.state[index] != Visit::New {
+            return;
+        }
+
+        self.state[index] = Visit::Open;
+        self.stack.push(index);
+
+        let included = self.project.files[index]
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.kind.includes())
+            .filter_map(|dependency| dependency.file);
+
+        for file in included {
+            match self.state[file] {
+                Visit::New => self.visit(file),
+                Visit::Open => {
+                    let start = self.stack.iter().rposition(|&i| i == file).unwrap();
+
+                    self.cycles.push(self.stack[start..].to_vec());
+                }
+                Visit::Done => {}
+            }
+        }
+
+        self.stack.pop();
+        self.state[index] = Visit::Done;
+    }
+}
+
+/// Every `.lua` file under `root`, sorted by path
+pub fn lua_files(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_owned()];
+    let mut visited = HashSet::new();
+
+    while let Some(dir) = dirs.pop() {
+        let error = |source| Error {
+            path: dir.clone(),
+            source,
+        };
+
+        // Symlinks are followed, so a directory reachable through more than one is only read
+        // once, which also stops at symlink cycles
+        if !visited.insert(fs::canonicalize(&dir).map_err(error)?) {
+            continue;
+        }
+
+        for entry in fs::read_dir(&dir).map_err(error)? {
+            let path = entry.map_err(error)?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "lua") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// The best-effort chunk of a file with syntax errors
+fn recover<'a>(source: &'a str, bump: &'a Bump) -> Block<'a> {
+    let (tokens, _) = Parser::lex_recovering(source, bump);
+
+    Parser::new_in(bump.alloc_slice_clone(&tokens), bump)
+        .parse_chunk_recovering()
+        .0
+}
+
+/// A path with `.` and `..` segments resolved and no leading `./`
+fn normalize(path: &str) -> String {
+    let mut segments = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if matches!(segments.last(), Some(&last) if last != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let normalized = segments.join("/");
+
+    match path.starts_with('/') {
+        true => format!("/{}", normalized),
+        false => normalized,
+    }
+}
+
+/// The paths `target` could refer to when included from the file at `from`, in the order they're
+/// tried
+fn candidates(from: &str, target: &str) -> Vec<String> {
+    let target = target.replace('\\', "/");
+
+    let dir = match from.rfind('/') {
+        Some(i) => &from[..=i],
+        None => "",
+    };
+
+    let mut candidates = vec![normalize(&format!("{}{}", dir, target))];
+
+    // The innermost `lua` or `gamemodes` directory, or the project's root
+    let segments = from.split('/').collect::<Vec<_>>();
+
+    let root = segments[..segments.len() - 1]
+        .iter()
+        .rposition(|&segment| segment == "lua" || segment == "gamemodes")
+        .map(|i| segments[..=i].join("/") + "/")
+        .unwrap_or_default();
+
+    candidates.push(normalize(&format!("{}{}", root, target)));
+
+    candidates
+}
+
+/// Finds the dependencies of a chunk
+struct Collector<'a> {
+    tree: ScopeTree<'a>,
+    realms: Realms,
+    dependencies: Vec<Dependency<'a>>,
+}
+
+impl<'a> Collector<'a> {
+    fn collect(chunk: Block<'a>, realm: Option<Realm>) -> Vec<Dependency<'a>> {
+        let mut collector = Collector {
+            tree: ScopeTree::build(chunk),
+            realms: Realms::infer(chunk, realm),
+            dependencies: Vec::new(),
+        };
+
+        traverse(&mut collector, chunk);
+
+        collector.dependencies
+    }
+}
+
+impl<'a> Listener<'a> for Collector<'a> {
+    fn enter_function_call(
+        &mut self,
+        call: &Node<'a, &'a FunctionCall<'a>>,
+        _ancestors: &Ancestors<'a>,
+    ) {
+        let Exp::Ref(function) = **call.lhs else {
+            return;
+        };
+
+        let kind = match function {
+            "include" => DependencyKind::Include,
+            "AddCSLuaFile" => DependencyKind::AddCSLuaFile,
+            "IncludeCS" => DependencyKind::IncludeCS,
+            _ => return,
+        };
+
+        let global = self
+            .tree
+            .reference_at(call.lhs.span().start)
+            .is_some_and(|reference| reference.resolution == Resolution::Global);
+
+        if !global {
+            return;
+        }
+
+        let path = match call.args.first() {
+            Some(arg) => match ***arg {
+                Exp::String(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(path) => Some(Node::morph(arg, path)),
+                    Err(_) => return,
+                },
+                // Can't be resolved
+                _ => return,
+            },
+            None if kind == DependencyKind::AddCSLuaFile => None,
+            None => return,
+        };
+
+        self.dependencies.push(Dependency {
+            kind,
+            path,
+            span: call.span(),
+            realm: self.realms.realm_at(call.span().start),
+            file: None,
+        });
+    }
+}
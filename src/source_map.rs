@@ -0,0 +1,176 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+use logos::Span;
+
+/// A line index over a source file, used to convert byte offsets into line and column positions
+pub struct SourceMap<'a> {
+    name: Option<String>,
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+/// A zero-based line and column position within a source file
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    /// Column in characters (Unicode scalar values)
+    pub column: usize,
+    /// Column in UTF-8 code units (bytes)
+    pub utf8_column: usize,
+    /// Column in UTF-16 code units, as used by LSP
+    pub utf16_column: usize,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(memchr::memchr_iter(b'\n', source.as_bytes()).map(|i| i + 1))
+            .collect();
+
+        Self {
+            name: None,
+            source,
+            line_starts,
+        }
+    }
+
+    /// Name the source, usually with its file path, for use when displaying locations
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+
+        self
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The zero-based line containing `offset`
+    pub fn line(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    /// The span of `line`, excluding its line terminator
+    pub fn line_span(&self, line: usize) -> Span {
+        let start = self.line_starts[line];
+
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => next - 1,
+            None => self.source.len(),
+        };
+
+        let end = match self.source[start..end].ends_with('\r') {
+            true => end - 1,
+            false => end,
+        };
+
+        start..end
+    }
+
+    /// The text of `line`, excluding its line terminator
+    pub fn line_text(&self, line: usize) -> &'a str {
+        &self.source[self.line_span(line)]
+    }
+
+    pub fn location(&self, offset: usize) -> Location {
+        let mut offset = offset.min(self.source.len());
+
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line(offset);
+
+        let prefix = &self.source[self.line_starts[line]..offset];
+
+        Location {
+            line,
+            column: prefix.chars().count(),
+            utf8_column: prefix.len(),
+            utf16_column: prefix.chars().map(char::len_utf16).sum(),
+        }
+    }
+
+    /// The locations of the start and end of `span`
+    pub fn range(&self, span: &Span) -> (Location, Location) {
+        (self.location(span.start), self.location(span.end))
+    }
+
+    /// Convert a zero-based line and UTF-16 column back into a byte offset, clamping the column
+    /// to the end of the line
+    pub fn offset_utf16(&self, line: usize, utf16_column: usize) -> Option<usize> {
+        let span = self.line_span_with_terminator(line)?;
+
+        let line = &self.source[span.clone()];
+
+        let mut remaining = utf16_column;
+
+        for (i, c) in line.char_indices() {
+            // Stop before the terminator, which is `\r\n` on some lines
+            let terminator = c == '\n' || (c == '\r' && line[i + 1..].starts_with('\n'));
+
+            if remaining == 0 || terminator {
+                return Some(span.start + i);
+            }
+
+            remaining = remaining.saturating_sub(c.len_utf16());
+        }
+
+        Some(span.end)
+    }
+
+    fn line_span_with_terminator(&self, line: usize) -> Option<Span> {
+        let start = *self.line_starts.get(line)?;
+
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.source.len());
+
+        Some(start..end)
+    }
+
+    /// Display `span` as `name:line:column`, or `line:column` when the source is unnamed
+    pub fn display_span(&self, span: &Span) -> impl Display + '_ {
+        DisplaySpan {
+            name: self.name(),
+            location: self.location(span.start),
+        }
+    }
+}
+
+struct DisplaySpan<'s> {
+    name: Option<&'s str>,
+    location: Location,
+}
+
+impl Display for DisplaySpan<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(name) = self.name {
+            write!(f, "{}:", name)?;
+        }
+
+        write!(f, "{}", self.location)
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
@@ -0,0 +1,166 @@
+--[[
+    Sample Garry's Mod addon code used by the tests, benches and the profiling binary.
+
+    It deliberately exercises as much of the GLua grammar as possible, including the GMod
+    specific operators, comments and statements.
+]]
+
+AddCSLuaFile()
+
+if SERVER then
+    util.AddNetworkString("glua_sync")
+    util.AddNetworkString("glua_request")
+end
+
+-- Module table
+local MODULE = MODULE or {}
+MODULE.Version = "1.2.0"
+MODULE.Colors = {
+    primary = Color(52, 152, 219),
+    secondary = Color(46, 204, 113, 255), // C-style line comment
+    ["danger"] = Color(231, 76, 60);
+    Color(0, 0, 0),
+}
+
+local math_floor, math_max = math.floor, math.max
+local hexValue, floatValue, expValue = 0xFF, .5, 1e-3
+local escaped = "tab\there\nnew line \"quoted\" \\ \65\066\x43"
+local single = 'it\'s'
+local long = [==[
+long string with ]] inside
+]==]
+
+/* C-style
+   block comment */
+
+--[==[
+    Long comment with ]] inside
+]==]
+
+local function clamp(value, min, max)
+    if value < min then
+        return min
+    elseif value > max then
+        return max
+    else
+        return value
+    end
+end
+
+function MODULE.Sum(...)
+    local total = 0
+
+    for _, v in ipairs({ ... }) do
+        total = total + v
+    end
+
+    return total
+end
+
+function MODULE:GetPlayers(filter)
+    local out = {}
+
+    for i = 1, #player.GetAll() do
+        local ply = player.GetAll()[i]
+
+        if !IsValid(ply) || ply:IsBot() then
+            continue
+        end
+
+        if filter == nil or filter(ply) then
+            out[#out + 1] = ply
+        end
+    end
+
+    return out
+end
+
+function MODULE.nested.deeply.Call(a, b)
+    return a ^ b ^ 2, -a .. b .. "c", not a == b, a != b and a ~= b
+end
+
+local ENT = {}
+
+function ENT:Initialize()
+    self:SetModel("models/props_c17/oildrum001.mdl")
+    self:PhysicsInit(SOLID_VPHYSICS)
+
+    local phys = self:GetPhysicsObject()
+
+    if IsValid(phys) then phys:Wake() end
+end
+
+function ENT:Think()
+    self.NextThinkTime = (self.NextThinkTime or 0) + 1
+
+    return true
+end
+
+hook.Add("PlayerSpawn", "glua_spawn", function(ply, transition)
+    timer.Simple(0, function()
+        if not IsValid(ply) then return end
+
+        ply:SetHealth(math_max(ply:Health(), 100))
+    end)
+end)
+
+hook.Add("Think", "glua_think", function()
+    local i = 0
+
+    while i < 10 do
+        i = i + 1
+
+        if i % 2 == 0 then
+            goto skip
+        end
+
+        ::skip::
+    end
+
+    repeat
+        i = i - 1
+    until i <= 0
+
+    do
+        local scoped = i * 2 / 4 % 3
+    end
+end)
+
+if CLIENT then
+    net.Receive("glua_sync", function(len)
+        local id = net.ReadUInt(8)
+        local name = net.ReadString()
+
+        chat.AddText(MODULE.Colors.primary, name, " (", tostring(id), ")")
+    end)
+
+    hook.Add("HUDPaint", "glua_hud", function()
+        surface.SetDrawColor(255, 255, 255, 255)
+        surface.DrawRect(0, 0, ScrW() * .5, ScrH() / 2)
+        draw.SimpleText("Hello", "DermaDefault", 10, 10, color_white)
+    end)
+else
+    net.Receive("glua_request", function(len, ply)
+        net.Start("glua_sync")
+        net.WriteUInt(ply:UserID() % 256, 8)
+        net.WriteString(ply:Nick())
+        net.Send(ply)
+    end)
+end
+
+local tbl = setmetatable({}, {
+    __index = function(t, k)
+        return rawget(t, k:lower())
+    end,
+    __call = function(self, ...)
+        return select("#", ...)
+    end,
+})
+
+print(("x"):rep(3), tbl"string", tbl{ 1, 2, 3 }, #tbl, (tbl))
+
+local a, b = 1
+a, b = b, a
+tbl.key, tbl["other"] = a and b or nil, MODULE.Sum(1, 2, 3)
+
+return MODULE